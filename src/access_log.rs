@@ -0,0 +1,198 @@
+use crate::error::ErrorCode;
+use crate::metrics::Metrics;
+use crate::ratelimit::UsageRecorder;
+use crate::recorder::Capture;
+use crate::stream;
+use axum::body::{Body, HttpBody};
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Response};
+use futures_util::StreamExt;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{Level, Subscriber, info};
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::Layer;
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::registry::LookupSpan;
+
+pub const REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// The `tracing` target of access log records.
+pub const TARGET: &str = "access_log";
+
+/// Longest client-supplied `x-request-id` that is reused as is.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Writes the access log to `writer` as JSON lines, whatever the format and
+/// filter of the other logs.
+pub fn layer<S, W>(writer: W) -> impl Layer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    tracing_subscriber::fmt::layer()
+        .json()
+        .with_writer(writer)
+        .with_filter(Targets::new().with_target(TARGET, Level::INFO))
+}
+
+/// Keeps access log records out of a layer filtered by `filter`.
+pub fn exclude(filter: EnvFilter) -> EnvFilter {
+    filter.add_directive(format!("{}=off", TARGET).parse().expect("valid directive"))
+}
+
+/// One client request, from its arrival until its response body has been
+/// sent. When dropped it is written to the access log (target `access_log`)
+/// and its duration, time to first token and token usage go to the metrics.
+pub struct RequestLog {
+    metrics: Arc<Metrics>,
+    started: Instant,
+    /// The client's `x-request-id`, or a generated UUID.
+    pub id: String,
+    /// Name of the client key the request was made with.
+    pub key: Option<String>,
+    /// Requested model, set once it is known to be routable so arbitrary
+    /// client input does not create new metric series.
+    pub model: String,
+    /// Backend of the latest attempt.
+    pub backend: String,
+    /// Backend attempts made, including retries.
+    pub attempts: u32,
+    /// Token usage reported by the backend.
+    pub usage: Option<UsageRecorder>,
+    pub request_bytes: usize,
+    /// Request and response being recorded, if the request was sampled.
+    pub capture: Option<Capture>,
+    status: u16,
+    first_token: Option<Duration>,
+    response_bytes: u64,
+}
+
+impl RequestLog {
+    pub fn start(metrics: &Arc<Metrics>, headers: &HeaderMap) -> Self {
+        Self {
+            metrics: metrics.clone(),
+            started: Instant::now(),
+            id: request_id(headers),
+            key: None,
+            model: String::new(),
+            backend: String::new(),
+            attempts: 0,
+            usage: None,
+            request_bytes: 0,
+            capture: None,
+            status: 0,
+            first_token: None,
+            response_bytes: 0,
+        }
+    }
+
+    /// Counts an attempt against `backend` that ended with `outcome`, an
+    /// HTTP status or `timeout`, `connect_error` or `error`.
+    pub fn attempt(&mut self, backend: &str, outcome: &str) {
+        self.metrics
+            .upstream_response(&self.model, backend, outcome);
+        self.backend = backend.to_string();
+        self.attempts += 1;
+    }
+
+    /// Counts the response, tags it with the request id and ties the rest of
+    /// the record to the end of its body.
+    pub fn finish(mut self, mut response: Response<Body>) -> Response<Body> {
+        self.status = response.status().as_u16();
+        let error = response
+            .extensions()
+            .get::<ErrorCode>()
+            .map(|ErrorCode(code)| *code);
+        self.metrics.response(
+            &self.model,
+            &self.backend,
+            response.status().as_str(),
+            error,
+        );
+        if let Ok(id) = HeaderValue::from_str(&self.id) {
+            response.headers_mut().insert(REQUEST_ID, id);
+        }
+
+        if !stream::is_event_stream(response.headers()) {
+            // Other bodies are fully buffered by now.
+            self.response_bytes = response.body().size_hint().exact().unwrap_or(0);
+            return response;
+        }
+        let (parts, body) = response.into_parts();
+        // The closure owns the record, so it is dropped with the stream.
+        let body = body.into_data_stream().map(move |chunk| {
+            if let Ok(bytes) = &chunk {
+                if self.first_token.is_none() {
+                    let elapsed = self.started.elapsed();
+                    self.first_token = Some(elapsed);
+                    self.metrics
+                        .first_token(&self.model, &self.backend, elapsed);
+                }
+                self.response_bytes += bytes.len() as u64;
+            }
+            chunk
+        });
+        Response::from_parts(parts, Body::from_stream(body))
+    }
+}
+
+impl Drop for RequestLog {
+    fn drop(&mut self) {
+        let duration = self.started.elapsed();
+        let usage = self.usage.as_ref().and_then(UsageRecorder::usage);
+        self.metrics
+            .completed(&self.model, &self.backend, duration, usage);
+
+        info!(
+            target: TARGET,
+            request_id = %self.id,
+            key = self.key.as_deref(),
+            model = %self.model,
+            backend = %self.backend,
+            status = self.status,
+            latency_ms = millis(duration),
+            ttft_ms = self.first_token.map(millis),
+            prompt_tokens = usage.map(|u| u.prompt_tokens),
+            completion_tokens = usage.map(|u| u.completion_tokens),
+            attempts = self.attempts,
+            request_bytes = self.request_bytes,
+            response_bytes = self.response_bytes,
+        );
+
+        if let Some(capture) = self.capture.take() {
+            let started = SystemTime::now() - duration;
+            let timestamp = started
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs_f64();
+            capture.finish(json!({
+                "timestamp": timestamp,
+                "request_id": self.id,
+                "key": self.key,
+                "model": self.model,
+                "backend": self.backend,
+                "status": self.status,
+                "latency_ms": millis(duration),
+                "ttft_ms": self.first_token.map(millis),
+                "attempts": self.attempts,
+            }));
+        }
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    (duration.as_secs_f64() * 1000.0 * 1000.0).round() / 1000.0
+}
+
+/// The client's `x-request-id` if it is usable, otherwise a new UUID.
+fn request_id(headers: &HeaderMap) -> String {
+    headers
+        .get(REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN)
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+}
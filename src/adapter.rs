@@ -0,0 +1,418 @@
+use crate::config::{AuthConfig, BackendConfig, BackendKind};
+use crate::error::RouterError;
+use crate::model::ModelInfo;
+use crate::stream;
+use axum::http::{self, HeaderMap, HeaderName, HeaderValue, header};
+use base64::Engine;
+use bytes::Bytes;
+use futures_util::stream::{BoxStream, StreamExt};
+use serde_json::{Value, json};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+mod anthropic;
+mod gemini;
+mod ollama;
+mod openai;
+
+pub const CHAT_COMPLETIONS: &str = "/v1/chat/completions";
+pub const COMPLETIONS: &str = "/v1/completions";
+
+/// Speaks a backend's protocol on behalf of clients that speak OpenAI:
+/// discovers its models, translates requests to it and its responses back.
+/// The defaults describe an OpenAI-compatible backend.
+pub trait Adapter: Send + Sync {
+    /// Path of the model list, relative to the backend URL.
+    fn models_path(&self, backend: &BackendConfig) -> String;
+
+    /// Reads the models out of the model list.
+    fn parse_models(&self, body: &Value) -> Result<Vec<ModelInfo>, String>;
+
+    /// Models known from the configuration, served without discovery.
+    fn configured_models(&self, _backend: &BackendConfig) -> Option<Vec<ModelInfo>> {
+        None
+    }
+
+    /// Whether the backend can serve an OpenAI `endpoint`.
+    fn supports(&self, _endpoint: &str) -> bool {
+        true
+    }
+
+    /// URL of the backend's counterpart of `endpoint`.
+    fn url(&self, backend: &BackendConfig, endpoint: &str, _request: &Value) -> String {
+        format!("{}{}", backend.url, endpoint)
+    }
+
+    /// The request body to send, or `None` to send the client's unchanged.
+    fn translate_request(&self, _endpoint: &str, _request: &Value) -> Option<Value> {
+        None
+    }
+
+    /// Adds the backend's credentials to `headers`.
+    fn apply_auth(
+        &self,
+        headers: &mut HeaderMap,
+        backend: &BackendConfig,
+    ) -> Result<(), http::Error> {
+        apply_auth(headers, backend, None)
+    }
+
+    /// Whether a response is streamed.
+    fn is_stream(&self, headers: &HeaderMap) -> bool {
+        stream::is_event_stream(headers)
+    }
+
+    /// The OpenAI response for a complete successful response body, or
+    /// `None` to pass the body on unchanged.
+    fn translate_response(&self, _endpoint: &str, _request: &Value, _body: &[u8]) -> Option<Bytes> {
+        None
+    }
+
+    /// The OpenAI error for a failed response body, or `None` to pass the
+    /// body on unchanged.
+    fn translate_error(&self, _body: &[u8]) -> Option<Bytes> {
+        None
+    }
+
+    /// Translates a streamed response into OpenAI chunks, or `None` when the
+    /// backend already streams them.
+    fn stream_translator(
+        &self,
+        _endpoint: &str,
+        _request: &Value,
+    ) -> Option<Box<dyn StreamTranslator>> {
+        None
+    }
+}
+
+/// Turns a backend's streamed response into OpenAI Server-Sent Events.
+pub trait StreamTranslator: Send {
+    /// The events for the next piece of the upstream body.
+    fn push(&mut self, chunk: &[u8]) -> Bytes;
+
+    /// Any events left once the upstream body has ended.
+    fn finish(&mut self) -> Bytes;
+}
+
+/// The adapter for the protocol `backend` speaks.
+pub fn for_backend(backend: &BackendConfig) -> &'static dyn Adapter {
+    match backend.kind {
+        BackendKind::OpenAi => &openai::OpenAi,
+        BackendKind::Azure => &openai::Azure,
+        BackendKind::Tgi => &openai::Tgi,
+        BackendKind::Ollama => &ollama::Ollama,
+        BackendKind::Anthropic => &anthropic::Anthropic,
+        BackendKind::Gemini => &gemini::Gemini,
+    }
+}
+
+/// Runs an upstream body through `translator`.
+pub fn translate_stream(
+    upstream: BoxStream<'static, Result<Bytes, reqwest::Error>>,
+    translator: Box<dyn StreamTranslator>,
+) -> BoxStream<'static, Result<Bytes, reqwest::Error>> {
+    futures_util::stream::unfold(Some((upstream, translator)), |state| async move {
+        let (mut upstream, mut translator) = state?;
+        match upstream.next().await {
+            Some(Ok(chunk)) => Some((Ok(translator.push(&chunk)), Some((upstream, translator)))),
+            Some(Err(err)) => Some((Err(err), None)),
+            None => Some((Ok(translator.finish()), None)),
+        }
+    })
+    .boxed()
+}
+
+/// Applies the backend's `auth`. Bearer tokens go into `key_header` instead
+/// of `Authorization` for protocols that expect their API key there.
+/// Fails if a credential cannot be sent in a header, which configuration
+/// validation normally rules out.
+pub fn apply_auth(
+    headers: &mut HeaderMap,
+    backend: &BackendConfig,
+    key_header: Option<&str>,
+) -> Result<(), http::Error> {
+    let (name, mut value): (HeaderName, HeaderValue) = match &backend.auth {
+        Some(AuthConfig::Bearer { token }) => match key_header {
+            Some(name) => (HeaderName::from_bytes(name.as_bytes())?, token.parse()?),
+            None => (header::AUTHORIZATION, format!("Bearer {}", token).parse()?),
+        },
+        Some(AuthConfig::Basic { username, password }) => {
+            let credentials = base64::engine::general_purpose::STANDARD
+                .encode(format!("{}:{}", username, password));
+            (
+                header::AUTHORIZATION,
+                format!("Basic {}", credentials).parse()?,
+            )
+        }
+        Some(AuthConfig::CustomHeader { name, value }) => {
+            (HeaderName::from_bytes(name.as_bytes())?, value.parse()?)
+        }
+        None => return Ok(()),
+    };
+    value.set_sensitive(true);
+    headers.insert(name, value);
+    Ok(())
+}
+
+/// A backend's answer, collected from its own response format.
+#[derive(Debug, Default)]
+struct Output {
+    content: String,
+    tool_calls: Vec<ToolCall>,
+    finish_reason: Option<&'static str>,
+    usage: Option<(u64, u64)>,
+}
+
+#[derive(Debug)]
+struct ToolCall {
+    id: String,
+    name: String,
+    /// Arguments as a JSON string.
+    arguments: String,
+}
+
+impl Output {
+    fn finish_reason(&self) -> &'static str {
+        match self.finish_reason {
+            Some("stop") | None if !self.tool_calls.is_empty() => "tool_calls",
+            Some(reason) => reason,
+            None => "stop",
+        }
+    }
+
+    /// The OpenAI response to `request` on `endpoint`.
+    fn into_response(self, endpoint: &str, request: &Value) -> Bytes {
+        let finish_reason = self.finish_reason();
+        let chat = endpoint == CHAT_COMPLETIONS;
+        let choice = if chat {
+            let mut message = json!({"role": "assistant", "content": self.content});
+            if !self.tool_calls.is_empty() {
+                let calls: Vec<Value> = self
+                    .tool_calls
+                    .iter()
+                    .map(|call| {
+                        json!({
+                            "id": call.id,
+                            "type": "function",
+                            "function": {"name": call.name, "arguments": call.arguments},
+                        })
+                    })
+                    .collect();
+                message["tool_calls"] = Value::Array(calls);
+            }
+            json!({"index": 0, "message": message, "finish_reason": finish_reason})
+        } else {
+            json!({"index": 0, "text": self.content, "finish_reason": finish_reason})
+        };
+        let mut response = json!({
+            "id": response_id(chat),
+            "object": if chat { "chat.completion" } else { "text_completion" },
+            "created": now(),
+            "model": model(request),
+            "choices": [choice],
+        });
+        if let Some((prompt, completion)) = self.usage {
+            response["usage"] = usage(prompt, completion);
+        }
+        Bytes::from(response.to_string())
+    }
+}
+
+/// Writes the OpenAI chunks of a translated stream.
+struct Chunks {
+    id: String,
+    model: String,
+    created: u64,
+    chat: bool,
+    include_usage: bool,
+    tool_calls: usize,
+    done: bool,
+}
+
+impl Chunks {
+    fn new(endpoint: &str, request: &Value) -> Self {
+        let chat = endpoint == CHAT_COMPLETIONS;
+        Self {
+            id: response_id(chat),
+            model: model(request).to_string(),
+            created: now(),
+            chat,
+            include_usage: request["stream_options"]["include_usage"] == true,
+            tool_calls: 0,
+            done: false,
+        }
+    }
+
+    fn chunk(&self, delta: Value, finish_reason: Option<&str>) -> String {
+        let choice = if self.chat {
+            json!({"index": 0, "delta": delta, "finish_reason": finish_reason})
+        } else {
+            let text = delta.get("content").cloned().unwrap_or_else(|| json!(""));
+            json!({"index": 0, "text": text, "finish_reason": finish_reason})
+        };
+        event(&json!({
+            "id": self.id,
+            "object": if self.chat { "chat.completion.chunk" } else { "text_completion" },
+            "created": self.created,
+            "model": self.model,
+            "choices": [choice],
+        }))
+    }
+
+    /// The first chunk of a chat stream, announcing the assistant.
+    fn role(&self) -> String {
+        if self.chat {
+            self.chunk(json!({"role": "assistant", "content": ""}), None)
+        } else {
+            String::new()
+        }
+    }
+
+    fn content(&self, text: &str) -> String {
+        if text.is_empty() {
+            return String::new();
+        }
+        self.chunk(json!({"content": text}), None)
+    }
+
+    /// Starts the next tool call and returns its index with the chunk.
+    fn tool_call(&mut self, id: &str, name: &str, arguments: &str) -> (usize, String) {
+        let index = self.tool_calls;
+        self.tool_calls += 1;
+        let delta = json!({"tool_calls": [{
+            "index": index,
+            "id": id,
+            "type": "function",
+            "function": {"name": name, "arguments": arguments},
+        }]});
+        (index, self.chunk(delta, None))
+    }
+
+    fn arguments(&self, index: usize, arguments: &str) -> String {
+        let delta = json!({"tool_calls": [{"index": index, "function": {"arguments": arguments}}]});
+        self.chunk(delta, None)
+    }
+
+    /// Ends the stream: the finish reason, the usage if the client asked for
+    /// it, and `[DONE]`.
+    fn finish(&mut self, finish_reason: Option<&str>, usage_counts: Option<(u64, u64)>) -> String {
+        if self.done {
+            return String::new();
+        }
+        self.done = true;
+        let finish_reason = match finish_reason {
+            Some("stop") | None if self.tool_calls > 0 => "tool_calls",
+            Some(reason) => reason,
+            None => "stop",
+        };
+        let mut events = self.chunk(json!({}), Some(finish_reason));
+        if self.include_usage
+            && let Some((prompt, completion)) = usage_counts
+        {
+            events.push_str(&event(&json!({
+                "id": self.id,
+                "object": if self.chat { "chat.completion.chunk" } else { "text_completion" },
+                "created": self.created,
+                "model": self.model,
+                "choices": [],
+                "usage": usage(prompt, completion),
+            })));
+        }
+        events.push_str("data: [DONE]\n\n");
+        events
+    }
+
+    /// Ends the stream with an error event.
+    fn error(&mut self, message: &str) -> String {
+        self.done = true;
+        RouterError::Upstream(message.to_string()).to_sse_event()
+    }
+}
+
+fn event(data: &Value) -> String {
+    format!("data: {}\n\n", data)
+}
+
+fn usage(prompt: u64, completion: u64) -> Value {
+    json!({
+        "prompt_tokens": prompt,
+        "completion_tokens": completion,
+        "total_tokens": prompt + completion,
+    })
+}
+
+fn response_id(chat: bool) -> String {
+    let prefix = if chat { "chatcmpl" } else { "cmpl" };
+    format!("{}-{}", prefix, uuid::Uuid::new_v4().simple())
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn model(request: &Value) -> &str {
+    request["model"].as_str().unwrap_or_default()
+}
+
+/// The text of an OpenAI message `content`, a string or content parts.
+fn text_of(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(|part| part["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// The image URLs in an OpenAI message `content`.
+fn image_urls(content: &Value) -> Vec<&str> {
+    content
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|part| part["image_url"]["url"].as_str())
+        .collect()
+}
+
+/// Splits a `data:` URL into its media type and base64 data.
+fn parse_data_url(url: &str) -> Option<(&str, &str)> {
+    let (media_type, data) = url.strip_prefix("data:")?.split_once(";base64,")?;
+    Some((media_type, data))
+}
+
+/// `stop` as a list, whether given as a string or an array.
+fn stop_sequences(request: &Value) -> Option<Value> {
+    match &request["stop"] {
+        Value::String(stop) => Some(json!([stop])),
+        Value::Array(stop) => Some(Value::Array(stop.clone())),
+        _ => None,
+    }
+}
+
+fn max_tokens(request: &Value) -> Option<&Value> {
+    request
+        .get("max_completion_tokens")
+        .or_else(|| request.get("max_tokens"))
+        .filter(|value| !value.is_null())
+}
+
+/// Wraps a backend's error body in the OpenAI error format.
+fn upstream_error(body: &[u8]) -> Bytes {
+    let parsed: Value = serde_json::from_slice(body).unwrap_or_default();
+    let message = [
+        &parsed["error"]["message"],
+        &parsed["error"],
+        &parsed["message"],
+        &parsed["detail"],
+    ]
+    .into_iter()
+    .find_map(Value::as_str)
+    .map(str::to_string)
+    .unwrap_or_else(|| String::from_utf8_lossy(body).into_owned());
+    Bytes::from(serde_json::to_vec(&RouterError::Upstream(message).body()).unwrap_or_default())
+}
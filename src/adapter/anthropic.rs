@@ -0,0 +1,361 @@
+use super::{
+    Adapter, CHAT_COMPLETIONS, Chunks, Output, StreamTranslator, ToolCall, max_tokens,
+    parse_data_url, stop_sequences, text_of, upstream_error,
+};
+use crate::config::BackendConfig;
+use crate::model::ModelInfo;
+use crate::stream::SseFramer;
+use axum::http::{self, HeaderMap, HeaderName, HeaderValue};
+use bytes::Bytes;
+use chrono::DateTime;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+
+pub const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// `max_tokens` is required by the Messages API; OpenAI requests may omit it.
+const DEFAULT_MAX_TOKENS: u64 = 4096;
+
+/// The Anthropic Messages API.
+pub struct Anthropic;
+
+impl Adapter for Anthropic {
+    fn models_path(&self, _backend: &BackendConfig) -> String {
+        "/v1/models?limit=1000".to_string()
+    }
+
+    fn parse_models(&self, body: &Value) -> Result<Vec<ModelInfo>, String> {
+        let models = body["data"]
+            .as_array()
+            .ok_or("missing `data` in the model list")?;
+        Ok(models
+            .iter()
+            .filter_map(|model| {
+                let created = model["created_at"]
+                    .as_str()
+                    .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+                    .map_or(0, |at| at.timestamp());
+                Some(ModelInfo {
+                    id: model["id"].as_str()?.to_string(),
+                    object: "model".to_string(),
+                    created,
+                    owned_by: "anthropic".to_string(),
+                })
+            })
+            .collect())
+    }
+
+    fn supports(&self, endpoint: &str) -> bool {
+        endpoint == CHAT_COMPLETIONS
+    }
+
+    fn url(&self, backend: &BackendConfig, _endpoint: &str, _request: &Value) -> String {
+        format!("{}/v1/messages", backend.url)
+    }
+
+    fn apply_auth(
+        &self,
+        headers: &mut HeaderMap,
+        backend: &BackendConfig,
+    ) -> Result<(), http::Error> {
+        super::apply_auth(headers, backend, Some("x-api-key"))?;
+        let version = HeaderName::from_static("anthropic-version");
+        if !headers.contains_key(&version) {
+            headers.insert(version, HeaderValue::from_static(ANTHROPIC_VERSION));
+        }
+        Ok(())
+    }
+
+    fn translate_request(&self, _endpoint: &str, request: &Value) -> Option<Value> {
+        let mut system = Vec::new();
+        let mut messages: Vec<Value> = Vec::new();
+        for message in request["messages"].as_array().into_iter().flatten() {
+            let (role, blocks) = match message["role"].as_str() {
+                Some("system" | "developer") => {
+                    system.push(text_of(&message["content"]));
+                    continue;
+                }
+                Some("assistant") => ("assistant", assistant_blocks(message)),
+                Some("tool") => ("user", vec![tool_result(message)]),
+                _ => ("user", user_blocks(&message["content"])),
+            };
+            // Messages must alternate between the user and the assistant.
+            match messages.last_mut() {
+                Some(last) if last["role"] == role => {
+                    if let Some(content) = last["content"].as_array_mut() {
+                        content.extend(blocks);
+                    }
+                }
+                _ => messages.push(json!({"role": role, "content": blocks})),
+            }
+        }
+
+        let mut body = json!({
+            "model": request["model"],
+            "messages": messages,
+            "max_tokens": max_tokens(request).cloned().unwrap_or(json!(DEFAULT_MAX_TOKENS)),
+        });
+        if !system.is_empty() {
+            body["system"] = json!(system.join("\n\n"));
+        }
+        if request["stream"] == true {
+            body["stream"] = json!(true);
+        }
+        for field in ["temperature", "top_p"] {
+            if let Some(value) = request.get(field) {
+                body[field] = value.clone();
+            }
+        }
+        if let Some(stop) = stop_sequences(request) {
+            body["stop_sequences"] = stop;
+        }
+        if let Some(user) = request["user"].as_str() {
+            body["metadata"] = json!({"user_id": user});
+        }
+        if let Some(tools) = request["tools"].as_array() {
+            let tools: Vec<Value> = tools
+                .iter()
+                .map(|tool| {
+                    let function = &tool["function"];
+                    let mut converted = json!({
+                        "name": function["name"],
+                        "input_schema": function
+                            .get("parameters")
+                            .cloned()
+                            .unwrap_or_else(|| json!({"type": "object"})),
+                    });
+                    if let Some(description) = function.get("description") {
+                        converted["description"] = description.clone();
+                    }
+                    converted
+                })
+                .collect();
+            body["tools"] = Value::Array(tools);
+        }
+        let mut tool_choice = match &request["tool_choice"] {
+            Value::String(choice) if choice == "required" => Some(json!({"type": "any"})),
+            Value::String(choice) if choice == "none" => Some(json!({"type": "none"})),
+            Value::Object(choice) => {
+                Some(json!({"type": "tool", "name": choice["function"]["name"]}))
+            }
+            _ => None,
+        };
+        if request["parallel_tool_calls"] == false {
+            let choice = tool_choice.get_or_insert_with(|| json!({"type": "auto"}));
+            choice["disable_parallel_tool_use"] = json!(true);
+        }
+        if let Some(tool_choice) = tool_choice {
+            body["tool_choice"] = tool_choice;
+        }
+        Some(body)
+    }
+
+    fn translate_response(&self, endpoint: &str, request: &Value, body: &[u8]) -> Option<Bytes> {
+        let response: Value = serde_json::from_slice(body).ok()?;
+        let mut output = Output {
+            finish_reason: Some(finish_reason(response["stop_reason"].as_str())),
+            usage: usage(&response["usage"]),
+            ..Default::default()
+        };
+        let mut texts = Vec::new();
+        for block in response["content"].as_array().into_iter().flatten() {
+            match block["type"].as_str() {
+                Some("text") => texts.push(block["text"].as_str().unwrap_or_default()),
+                Some("tool_use") => output.tool_calls.push(ToolCall {
+                    id: block["id"].as_str().unwrap_or_default().to_string(),
+                    name: block["name"].as_str().unwrap_or_default().to_string(),
+                    arguments: block["input"].to_string(),
+                }),
+                _ => {}
+            }
+        }
+        output.content = texts.concat();
+        Some(output.into_response(endpoint, request))
+    }
+
+    fn translate_error(&self, body: &[u8]) -> Option<Bytes> {
+        Some(upstream_error(body))
+    }
+
+    fn stream_translator(
+        &self,
+        endpoint: &str,
+        request: &Value,
+    ) -> Option<Box<dyn StreamTranslator>> {
+        Some(Box::new(EventTranslator {
+            chunks: Chunks::new(endpoint, request),
+            framer: SseFramer::default(),
+            tool_calls: HashMap::new(),
+            finish_reason: None,
+            input_tokens: 0,
+            output_tokens: 0,
+        }))
+    }
+}
+
+fn user_blocks(content: &Value) -> Vec<Value> {
+    let Value::Array(parts) = content else {
+        return vec![json!({"type": "text", "text": text_of(content)})];
+    };
+    parts
+        .iter()
+        .filter_map(|part| match part["type"].as_str()? {
+            "text" => Some(json!({"type": "text", "text": part["text"]})),
+            "image_url" => {
+                let url = part["image_url"]["url"].as_str()?;
+                let source = match parse_data_url(url) {
+                    Some((media_type, data)) => {
+                        json!({"type": "base64", "media_type": media_type, "data": data})
+                    }
+                    None => json!({"type": "url", "url": url}),
+                };
+                Some(json!({"type": "image", "source": source}))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn assistant_blocks(message: &Value) -> Vec<Value> {
+    let mut blocks = Vec::new();
+    let text = text_of(&message["content"]);
+    if !text.is_empty() {
+        blocks.push(json!({"type": "text", "text": text}));
+    }
+    for call in message["tool_calls"].as_array().into_iter().flatten() {
+        let function = &call["function"];
+        let input = function["arguments"]
+            .as_str()
+            .and_then(|arguments| serde_json::from_str(arguments).ok())
+            .unwrap_or_else(|| json!({}));
+        blocks.push(json!({
+            "type": "tool_use",
+            "id": call["id"],
+            "name": function["name"],
+            "input": input,
+        }));
+    }
+    blocks
+}
+
+fn tool_result(message: &Value) -> Value {
+    json!({
+        "type": "tool_result",
+        "tool_use_id": message["tool_call_id"],
+        "content": text_of(&message["content"]),
+    })
+}
+
+fn finish_reason(stop_reason: Option<&str>) -> &'static str {
+    match stop_reason {
+        Some("max_tokens") => "length",
+        Some("tool_use") => "tool_calls",
+        Some("refusal") => "content_filter",
+        _ => "stop",
+    }
+}
+
+fn usage(usage: &Value) -> Option<(u64, u64)> {
+    Some((
+        usage["input_tokens"].as_u64()?,
+        usage["output_tokens"].as_u64()?,
+    ))
+}
+
+/// Translates the Messages event sequence.
+struct EventTranslator {
+    chunks: Chunks,
+    framer: SseFramer,
+    /// Content block index -> tool call index.
+    tool_calls: HashMap<u64, usize>,
+    finish_reason: Option<&'static str>,
+    input_tokens: u64,
+    output_tokens: u64,
+}
+
+impl EventTranslator {
+    fn translate_event(&mut self, event: &[u8], events: &mut String) {
+        let text = String::from_utf8_lossy(event);
+        for data in text.lines().filter_map(|line| line.strip_prefix("data:")) {
+            let Ok(data) = serde_json::from_str::<Value>(data.trim()) else {
+                continue;
+            };
+            if self.chunks.done {
+                return;
+            }
+            match data["type"].as_str() {
+                Some("message_start") => {
+                    let usage = &data["message"]["usage"];
+                    self.input_tokens = usage["input_tokens"].as_u64().unwrap_or(0);
+                    self.output_tokens = usage["output_tokens"].as_u64().unwrap_or(0);
+                    events.push_str(&self.chunks.role());
+                }
+                Some("content_block_start") => {
+                    let block = &data["content_block"];
+                    if block["type"] == "tool_use" {
+                        let (index, chunk) = self.chunks.tool_call(
+                            block["id"].as_str().unwrap_or_default(),
+                            block["name"].as_str().unwrap_or_default(),
+                            "",
+                        );
+                        let block_index = data["index"].as_u64().unwrap_or(0);
+                        self.tool_calls.insert(block_index, index);
+                        events.push_str(&chunk);
+                    }
+                }
+                Some("content_block_delta") => {
+                    let delta = &data["delta"];
+                    match delta["type"].as_str() {
+                        Some("text_delta") => {
+                            let text = delta["text"].as_str().unwrap_or_default();
+                            events.push_str(&self.chunks.content(text));
+                        }
+                        Some("input_json_delta") => {
+                            let block_index = data["index"].as_u64().unwrap_or(0);
+                            if let Some(&index) = self.tool_calls.get(&block_index) {
+                                let json = delta["partial_json"].as_str().unwrap_or_default();
+                                events.push_str(&self.chunks.arguments(index, json));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                Some("message_delta") => {
+                    self.finish_reason = Some(finish_reason(data["delta"]["stop_reason"].as_str()));
+                    if let Some(tokens) = data["usage"]["output_tokens"].as_u64() {
+                        self.output_tokens = tokens;
+                    }
+                }
+                Some("message_stop") => {
+                    let usage = Some((self.input_tokens, self.output_tokens));
+                    events.push_str(&self.chunks.finish(self.finish_reason, usage));
+                }
+                Some("error") => {
+                    let message = data["error"]["message"]
+                        .as_str()
+                        .unwrap_or("The backend stream failed.");
+                    events.push_str(&self.chunks.error(message));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl StreamTranslator for EventTranslator {
+    fn push(&mut self, chunk: &[u8]) -> Bytes {
+        let mut events = String::new();
+        for event in self.framer.push(chunk) {
+            self.translate_event(&event, &mut events);
+        }
+        Bytes::from(events)
+    }
+
+    fn finish(&mut self) -> Bytes {
+        let mut events = String::new();
+        if let Some(rest) = self.framer.finish() {
+            self.translate_event(&rest, &mut events);
+        }
+        Bytes::from(events)
+    }
+}
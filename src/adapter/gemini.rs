@@ -0,0 +1,362 @@
+use super::{
+    Adapter, CHAT_COMPLETIONS, Chunks, Output, StreamTranslator, ToolCall, image_urls, max_tokens,
+    parse_data_url, stop_sequences, text_of, upstream_error,
+};
+use crate::config::BackendConfig;
+use crate::model::ModelInfo;
+use crate::stream::SseFramer;
+use axum::http::{self, HeaderMap};
+use bytes::Bytes;
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+
+/// The Gemini API. The backend URL is the API root, e.g.
+/// `https://generativelanguage.googleapis.com`.
+pub struct Gemini;
+
+impl Adapter for Gemini {
+    fn models_path(&self, _backend: &BackendConfig) -> String {
+        "/v1beta/models?pageSize=1000".to_string()
+    }
+
+    fn parse_models(&self, body: &Value) -> Result<Vec<ModelInfo>, String> {
+        let models = body["models"]
+            .as_array()
+            .ok_or("missing `models` in the model list")?;
+        Ok(models
+            .iter()
+            .filter(|model| {
+                model["supportedGenerationMethods"]
+                    .as_array()
+                    .is_none_or(|methods| methods.iter().any(|m| m == "generateContent"))
+            })
+            .filter_map(|model| {
+                let name = model["name"].as_str()?;
+                Some(ModelInfo {
+                    id: name.strip_prefix("models/").unwrap_or(name).to_string(),
+                    object: "model".to_string(),
+                    created: 0,
+                    owned_by: "google".to_string(),
+                })
+            })
+            .collect())
+    }
+
+    fn supports(&self, endpoint: &str) -> bool {
+        endpoint == CHAT_COMPLETIONS
+    }
+
+    fn url(&self, backend: &BackendConfig, _endpoint: &str, request: &Value) -> String {
+        let method = if request["stream"] == true {
+            "streamGenerateContent?alt=sse"
+        } else {
+            "generateContent"
+        };
+        format!(
+            "{}/v1beta/models/{}:{}",
+            backend.url,
+            request["model"].as_str().unwrap_or_default(),
+            method
+        )
+    }
+
+    fn apply_auth(
+        &self,
+        headers: &mut HeaderMap,
+        backend: &BackendConfig,
+    ) -> Result<(), http::Error> {
+        super::apply_auth(headers, backend, Some("x-goog-api-key"))
+    }
+
+    fn translate_request(&self, _endpoint: &str, request: &Value) -> Option<Value> {
+        let mut system = Vec::new();
+        let mut contents: Vec<Value> = Vec::new();
+        // Function responses are matched to calls by name, not id.
+        let mut call_names: HashMap<&str, &str> = HashMap::new();
+        for message in request["messages"].as_array().into_iter().flatten() {
+            let (role, parts) = match message["role"].as_str() {
+                Some("system" | "developer") => {
+                    system.push(json!({"text": text_of(&message["content"])}));
+                    continue;
+                }
+                Some("assistant") => {
+                    for call in message["tool_calls"].as_array().into_iter().flatten() {
+                        if let (Some(id), Some(name)) =
+                            (call["id"].as_str(), call["function"]["name"].as_str())
+                        {
+                            call_names.insert(id, name);
+                        }
+                    }
+                    ("model", model_parts(message))
+                }
+                Some("tool") => {
+                    let id = message["tool_call_id"].as_str().unwrap_or_default();
+                    let name = call_names.get(id).copied().unwrap_or(id);
+                    let part = json!({"functionResponse": {
+                        "name": name,
+                        "response": {"content": text_of(&message["content"])},
+                    }});
+                    ("user", vec![part])
+                }
+                _ => ("user", user_parts(&message["content"])),
+            };
+            // Turns must alternate between the user and the model.
+            match contents.last_mut() {
+                Some(last) if last["role"] == role => {
+                    if let Some(existing) = last["parts"].as_array_mut() {
+                        existing.extend(parts);
+                    }
+                }
+                _ => contents.push(json!({"role": role, "parts": parts})),
+            }
+        }
+
+        let mut body = json!({"contents": contents});
+        if !system.is_empty() {
+            body["systemInstruction"] = json!({"parts": system});
+        }
+        let config = generation_config(request);
+        if !config.is_empty() {
+            body["generationConfig"] = Value::Object(config);
+        }
+        if let Some(tools) = request["tools"].as_array() {
+            let declarations: Vec<Value> = tools
+                .iter()
+                .map(|tool| {
+                    let function = &tool["function"];
+                    let mut declaration = json!({"name": function["name"]});
+                    for field in ["description", "parameters"] {
+                        if let Some(value) = function.get(field) {
+                            declaration[field] = value.clone();
+                        }
+                    }
+                    declaration
+                })
+                .collect();
+            body["tools"] = json!([{"functionDeclarations": declarations}]);
+        }
+        let calling = match &request["tool_choice"] {
+            Value::String(choice) if choice == "required" => Some(json!({"mode": "ANY"})),
+            Value::String(choice) if choice == "none" => Some(json!({"mode": "NONE"})),
+            Value::Object(choice) => Some(json!({
+                "mode": "ANY",
+                "allowedFunctionNames": [choice["function"]["name"]],
+            })),
+            _ => None,
+        };
+        if let Some(calling) = calling {
+            body["toolConfig"] = json!({"functionCallingConfig": calling});
+        }
+        Some(body)
+    }
+
+    fn translate_response(&self, endpoint: &str, request: &Value, body: &[u8]) -> Option<Bytes> {
+        let response: Value = serde_json::from_slice(body).ok()?;
+        let candidate = &response["candidates"][0];
+        let mut output = Output {
+            finish_reason: candidate["finishReason"].as_str().map(finish_reason),
+            usage: usage(&response),
+            ..Default::default()
+        };
+        let mut texts = Vec::new();
+        for (i, part) in parts(candidate).iter().enumerate() {
+            if let Some(text) = part["text"].as_str() {
+                texts.push(text);
+            }
+            if let Some(call) = part.get("functionCall") {
+                output.tool_calls.push(tool_call(i, call));
+            }
+        }
+        output.content = texts.concat();
+        Some(output.into_response(endpoint, request))
+    }
+
+    fn translate_error(&self, body: &[u8]) -> Option<Bytes> {
+        Some(upstream_error(body))
+    }
+
+    fn stream_translator(
+        &self,
+        endpoint: &str,
+        request: &Value,
+    ) -> Option<Box<dyn StreamTranslator>> {
+        Some(Box::new(SseTranslator {
+            chunks: Chunks::new(endpoint, request),
+            framer: SseFramer::default(),
+            started: false,
+            finish_reason: None,
+            usage: None,
+        }))
+    }
+}
+
+fn generation_config(request: &Value) -> Map<String, Value> {
+    let mut config = Map::new();
+    for (field, name) in [
+        ("temperature", "temperature"),
+        ("top_p", "topP"),
+        ("seed", "seed"),
+        ("presence_penalty", "presencePenalty"),
+        ("frequency_penalty", "frequencyPenalty"),
+    ] {
+        if let Some(value) = request.get(field) {
+            config.insert(name.to_string(), value.clone());
+        }
+    }
+    if let Some(value) = max_tokens(request) {
+        config.insert("maxOutputTokens".to_string(), value.clone());
+    }
+    if let Some(stop) = stop_sequences(request) {
+        config.insert("stopSequences".to_string(), stop);
+    }
+    let format = &request["response_format"];
+    match format["type"].as_str() {
+        Some("json_object") => {
+            config.insert("responseMimeType".to_string(), json!("application/json"));
+        }
+        Some("json_schema") => {
+            config.insert("responseMimeType".to_string(), json!("application/json"));
+            config.insert(
+                "responseJsonSchema".to_string(),
+                format["json_schema"]["schema"].clone(),
+            );
+        }
+        _ => {}
+    }
+    config
+}
+
+fn user_parts(content: &Value) -> Vec<Value> {
+    let mut parts = Vec::new();
+    let text = text_of(content);
+    if !text.is_empty() {
+        parts.push(json!({"text": text}));
+    }
+    for url in image_urls(content) {
+        parts.push(match parse_data_url(url) {
+            Some((mime_type, data)) => {
+                json!({"inlineData": {"mimeType": mime_type, "data": data}})
+            }
+            None => json!({"fileData": {"fileUri": url}}),
+        });
+    }
+    parts
+}
+
+fn model_parts(message: &Value) -> Vec<Value> {
+    let mut parts = Vec::new();
+    let text = text_of(&message["content"]);
+    if !text.is_empty() {
+        parts.push(json!({"text": text}));
+    }
+    for call in message["tool_calls"].as_array().into_iter().flatten() {
+        let function = &call["function"];
+        let args = function["arguments"]
+            .as_str()
+            .and_then(|arguments| serde_json::from_str(arguments).ok())
+            .unwrap_or_else(|| json!({}));
+        parts.push(json!({"functionCall": {"name": function["name"], "args": args}}));
+    }
+    parts
+}
+
+fn parts(candidate: &Value) -> &[Value] {
+    candidate["content"]["parts"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+fn tool_call(index: usize, call: &Value) -> ToolCall {
+    ToolCall {
+        id: format!("call_{}", index),
+        name: call["name"].as_str().unwrap_or_default().to_string(),
+        arguments: call["args"].to_string(),
+    }
+}
+
+fn finish_reason(reason: &str) -> &'static str {
+    match reason {
+        "MAX_TOKENS" => "length",
+        "SAFETY" | "RECITATION" | "BLOCKLIST" | "PROHIBITED_CONTENT" | "SPII" => "content_filter",
+        _ => "stop",
+    }
+}
+
+fn usage(response: &Value) -> Option<(u64, u64)> {
+    let usage = &response["usageMetadata"];
+    Some((
+        usage["promptTokenCount"].as_u64()?,
+        usage["candidatesTokenCount"].as_u64().unwrap_or(0),
+    ))
+}
+
+/// Translates `streamGenerateContent` events, each a partial response. The
+/// stream has no end event, so the finish chunk is sent when it ends.
+struct SseTranslator {
+    chunks: Chunks,
+    framer: SseFramer,
+    started: bool,
+    finish_reason: Option<&'static str>,
+    usage: Option<(u64, u64)>,
+}
+
+impl SseTranslator {
+    fn translate_event(&mut self, event: &[u8], events: &mut String) {
+        let text = String::from_utf8_lossy(event);
+        for data in text.lines().filter_map(|line| line.strip_prefix("data:")) {
+            let Ok(response) = serde_json::from_str::<Value>(data.trim()) else {
+                continue;
+            };
+            if self.chunks.done {
+                return;
+            }
+            if let Some(message) = response["error"]["message"].as_str() {
+                events.push_str(&self.chunks.error(message));
+                return;
+            }
+            if !self.started {
+                self.started = true;
+                events.push_str(&self.chunks.role());
+            }
+            let candidate = &response["candidates"][0];
+            for part in parts(candidate) {
+                if let Some(text) = part["text"].as_str() {
+                    events.push_str(&self.chunks.content(text));
+                }
+                if let Some(call) = part.get("functionCall") {
+                    let call = tool_call(self.chunks.tool_calls, call);
+                    let (_, chunk) = self.chunks.tool_call(&call.id, &call.name, &call.arguments);
+                    events.push_str(&chunk);
+                }
+            }
+            if let Some(reason) = candidate["finishReason"].as_str() {
+                self.finish_reason = Some(finish_reason(reason));
+            }
+            if let Some(usage) = usage(&response) {
+                self.usage = Some(usage);
+            }
+        }
+    }
+}
+
+impl StreamTranslator for SseTranslator {
+    fn push(&mut self, chunk: &[u8]) -> Bytes {
+        let mut events = String::new();
+        for event in self.framer.push(chunk) {
+            self.translate_event(&event, &mut events);
+        }
+        Bytes::from(events)
+    }
+
+    fn finish(&mut self) -> Bytes {
+        let mut events = String::new();
+        if let Some(rest) = self.framer.finish() {
+            self.translate_event(&rest, &mut events);
+        }
+        if self.started {
+            events.push_str(&self.chunks.finish(self.finish_reason, self.usage));
+        }
+        Bytes::from(events)
+    }
+}
@@ -0,0 +1,284 @@
+use super::{
+    Adapter, CHAT_COMPLETIONS, COMPLETIONS, Chunks, Output, StreamTranslator, ToolCall, image_urls,
+    max_tokens, parse_data_url, stop_sequences, text_of, upstream_error,
+};
+use crate::config::BackendConfig;
+use crate::model::ModelInfo;
+use axum::http::{HeaderMap, header};
+use bytes::{Bytes, BytesMut};
+use chrono::DateTime;
+use serde_json::{Map, Value, json};
+
+/// OpenAI sampling parameters under their Ollama `options` names.
+const OPTIONS: [(&str, &str); 5] = [
+    ("temperature", "temperature"),
+    ("top_p", "top_p"),
+    ("seed", "seed"),
+    ("frequency_penalty", "frequency_penalty"),
+    ("presence_penalty", "presence_penalty"),
+];
+
+/// Ollama's native API: `/api/chat` and `/api/generate`, streaming
+/// newline-delimited JSON.
+pub struct Ollama;
+
+impl Adapter for Ollama {
+    fn models_path(&self, _backend: &BackendConfig) -> String {
+        "/api/tags".to_string()
+    }
+
+    fn parse_models(&self, body: &Value) -> Result<Vec<ModelInfo>, String> {
+        let models = body["models"]
+            .as_array()
+            .ok_or("missing `models` in the model list")?;
+        Ok(models
+            .iter()
+            .filter_map(|model| {
+                let name = model["name"].as_str()?;
+                let created = model["modified_at"]
+                    .as_str()
+                    .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+                    .map_or(0, |at| at.timestamp());
+                Some(ModelInfo {
+                    id: name.to_string(),
+                    object: "model".to_string(),
+                    created,
+                    owned_by: "ollama".to_string(),
+                })
+            })
+            .collect())
+    }
+
+    fn supports(&self, endpoint: &str) -> bool {
+        endpoint == CHAT_COMPLETIONS || endpoint == COMPLETIONS
+    }
+
+    fn url(&self, backend: &BackendConfig, endpoint: &str, _request: &Value) -> String {
+        let path = if endpoint == CHAT_COMPLETIONS {
+            "/api/chat"
+        } else {
+            "/api/generate"
+        };
+        format!("{}{}", backend.url, path)
+    }
+
+    fn translate_request(&self, endpoint: &str, request: &Value) -> Option<Value> {
+        let mut body = json!({
+            "model": request["model"],
+            "stream": request["stream"] == true,
+            "options": options(request),
+        });
+        if endpoint == CHAT_COMPLETIONS {
+            let messages: Vec<Value> = request["messages"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(message)
+                .collect();
+            body["messages"] = Value::Array(messages);
+            if let Some(tools) = request.get("tools") {
+                body["tools"] = tools.clone();
+            }
+        } else {
+            body["prompt"] = match &request["prompt"] {
+                Value::Array(prompts) => prompts.first().cloned().unwrap_or_default(),
+                prompt => prompt.clone(),
+            };
+            if let Some(suffix) = request.get("suffix") {
+                body["suffix"] = suffix.clone();
+            }
+        }
+        match request["response_format"]["type"].as_str() {
+            Some("json_object") => body["format"] = json!("json"),
+            Some("json_schema") => {
+                body["format"] = request["response_format"]["json_schema"]["schema"].clone();
+            }
+            _ => {}
+        }
+        Some(body)
+    }
+
+    fn is_stream(&self, headers: &HeaderMap) -> bool {
+        headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("application/x-ndjson"))
+    }
+
+    fn translate_response(&self, endpoint: &str, request: &Value, body: &[u8]) -> Option<Bytes> {
+        let response: Value = serde_json::from_slice(body).ok()?;
+        let mut output = Output {
+            usage: usage(&response),
+            finish_reason: Some(finish_reason(&response)),
+            ..Default::default()
+        };
+        if endpoint == CHAT_COMPLETIONS {
+            let message = &response["message"];
+            output.content = message["content"].as_str().unwrap_or_default().to_string();
+            output.tool_calls = message["tool_calls"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .enumerate()
+                .map(|(i, call)| tool_call(i, call))
+                .collect();
+        } else {
+            output.content = response["response"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string();
+        }
+        Some(output.into_response(endpoint, request))
+    }
+
+    fn translate_error(&self, body: &[u8]) -> Option<Bytes> {
+        Some(upstream_error(body))
+    }
+
+    fn stream_translator(
+        &self,
+        endpoint: &str,
+        request: &Value,
+    ) -> Option<Box<dyn StreamTranslator>> {
+        Some(Box::new(NdjsonTranslator {
+            chunks: Chunks::new(endpoint, request),
+            buf: BytesMut::new(),
+            started: false,
+        }))
+    }
+}
+
+fn options(request: &Value) -> Value {
+    let mut options = Map::new();
+    for (field, option) in OPTIONS {
+        if let Some(value) = request.get(field) {
+            options.insert(option.to_string(), value.clone());
+        }
+    }
+    if let Some(value) = max_tokens(request) {
+        options.insert("num_predict".to_string(), value.clone());
+    }
+    if let Some(stop) = stop_sequences(request) {
+        options.insert("stop".to_string(), stop);
+    }
+    Value::Object(options)
+}
+
+/// Converts an OpenAI message. Images must be inline, as Ollama only takes
+/// base64 data.
+fn message(message: &Value) -> Value {
+    let role = match message["role"].as_str() {
+        Some("developer") => "system",
+        Some(role) => role,
+        None => "user",
+    };
+    let mut converted = json!({"role": role, "content": text_of(&message["content"])});
+    let images: Vec<&str> = image_urls(&message["content"])
+        .into_iter()
+        .filter_map(parse_data_url)
+        .map(|(_, data)| data)
+        .collect();
+    if !images.is_empty() {
+        converted["images"] = json!(images);
+    }
+    if let Some(calls) = message["tool_calls"].as_array() {
+        let calls: Vec<Value> = calls
+            .iter()
+            .map(|call| {
+                let function = &call["function"];
+                let arguments = function["arguments"]
+                    .as_str()
+                    .and_then(|arguments| serde_json::from_str(arguments).ok())
+                    .unwrap_or_else(|| json!({}));
+                json!({"function": {"name": function["name"], "arguments": arguments}})
+            })
+            .collect();
+        converted["tool_calls"] = Value::Array(calls);
+    }
+    converted
+}
+
+fn tool_call(index: usize, call: &Value) -> ToolCall {
+    let function = &call["function"];
+    ToolCall {
+        id: format!("call_{}", index),
+        name: function["name"].as_str().unwrap_or_default().to_string(),
+        arguments: function["arguments"].to_string(),
+    }
+}
+
+fn finish_reason(response: &Value) -> &'static str {
+    match response["done_reason"].as_str() {
+        Some("length") => "length",
+        _ => "stop",
+    }
+}
+
+fn usage(response: &Value) -> Option<(u64, u64)> {
+    Some((
+        response["prompt_eval_count"].as_u64()?,
+        response["eval_count"].as_u64()?,
+    ))
+}
+
+/// Translates Ollama's newline-delimited JSON stream.
+struct NdjsonTranslator {
+    chunks: Chunks,
+    buf: BytesMut,
+    started: bool,
+}
+
+impl NdjsonTranslator {
+    fn translate_line(&mut self, line: &[u8], events: &mut String) {
+        if self.chunks.done {
+            return;
+        }
+        let Ok(response) = serde_json::from_slice::<Value>(line) else {
+            return;
+        };
+        if let Some(error) = response["error"].as_str() {
+            events.push_str(&self.chunks.error(error));
+            return;
+        }
+        if !self.started {
+            self.started = true;
+            events.push_str(&self.chunks.role());
+        }
+        let message = &response["message"];
+        let text = message["content"]
+            .as_str()
+            .or_else(|| response["response"].as_str())
+            .unwrap_or_default();
+        events.push_str(&self.chunks.content(text));
+        for call in message["tool_calls"].as_array().into_iter().flatten() {
+            let call = tool_call(self.chunks.tool_calls, call);
+            let (_, chunk) = self.chunks.tool_call(&call.id, &call.name, &call.arguments);
+            events.push_str(&chunk);
+        }
+        if response["done"] == true {
+            let finish = self
+                .chunks
+                .finish(Some(finish_reason(&response)), usage(&response));
+            events.push_str(&finish);
+        }
+    }
+}
+
+impl StreamTranslator for NdjsonTranslator {
+    fn push(&mut self, chunk: &[u8]) -> Bytes {
+        self.buf.extend_from_slice(chunk);
+        let mut events = String::new();
+        while let Some(end) = self.buf.iter().position(|&byte| byte == b'\n') {
+            let line = self.buf.split_to(end + 1);
+            self.translate_line(&line, &mut events);
+        }
+        Bytes::from(events)
+    }
+
+    fn finish(&mut self) -> Bytes {
+        let mut events = String::new();
+        let rest = self.buf.split();
+        self.translate_line(&rest, &mut events);
+        Bytes::from(events)
+    }
+}
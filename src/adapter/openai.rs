@@ -0,0 +1,130 @@
+use super::{Adapter, upstream_error};
+use crate::config::BackendConfig;
+use crate::model::ModelInfo;
+use axum::http::{self, HeaderMap};
+use bytes::Bytes;
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Default `api-version` of Azure OpenAI requests.
+pub const AZURE_API_VERSION: &str = "2024-10-21";
+
+/// Last Azure OpenAI API version that can list deployments.
+const AZURE_DEPLOYMENTS_API_VERSION: &str = "2022-12-01";
+
+#[derive(Debug, Deserialize)]
+struct ModelsResponse {
+    data: Vec<ModelInfo>,
+}
+
+/// OpenAI-compatible servers, which need no translation.
+pub struct OpenAi;
+
+impl Adapter for OpenAi {
+    fn models_path(&self, _backend: &BackendConfig) -> String {
+        "/v1/models".to_string()
+    }
+
+    fn parse_models(&self, body: &Value) -> Result<Vec<ModelInfo>, String> {
+        ModelsResponse::deserialize(body)
+            .map(|models| models.data)
+            .map_err(|err| err.to_string())
+    }
+}
+
+/// Azure OpenAI speaks OpenAI below a per-deployment path, with the API
+/// version as a query parameter and the key in `api-key`. Models are
+/// mapped to deployments by `deployments`, or else addressed by deployment
+/// name.
+pub struct Azure;
+
+impl Adapter for Azure {
+    fn models_path(&self, _backend: &BackendConfig) -> String {
+        format!(
+            "/openai/deployments?api-version={}",
+            AZURE_DEPLOYMENTS_API_VERSION
+        )
+    }
+
+    fn parse_models(&self, body: &Value) -> Result<Vec<ModelInfo>, String> {
+        let deployments = body["data"]
+            .as_array()
+            .ok_or("missing `data` in the deployment list")?;
+        Ok(deployments
+            .iter()
+            .filter_map(|deployment| deployment["id"].as_str())
+            .map(|id| ModelInfo {
+                id: id.to_string(),
+                object: "model".to_string(),
+                created: 0,
+                owned_by: "azure".to_string(),
+            })
+            .collect())
+    }
+
+    fn configured_models(&self, backend: &BackendConfig) -> Option<Vec<ModelInfo>> {
+        if backend.deployments.is_empty() {
+            return None;
+        }
+        let mut models: Vec<ModelInfo> = backend
+            .deployments
+            .keys()
+            .map(|model| ModelInfo {
+                id: model.clone(),
+                object: "model".to_string(),
+                created: 0,
+                owned_by: "azure".to_string(),
+            })
+            .collect();
+        models.sort_by(|a, b| a.id.cmp(&b.id));
+        Some(models)
+    }
+
+    fn url(&self, backend: &BackendConfig, endpoint: &str, request: &Value) -> String {
+        let path = endpoint.strip_prefix("/v1").unwrap_or(endpoint);
+        let model = request["model"].as_str().unwrap_or_default();
+        let deployment = backend.deployments.get(model).map_or(model, String::as_str);
+        format!(
+            "{}/openai/deployments/{}{}?api-version={}",
+            backend.url,
+            deployment,
+            path,
+            backend.api_version.as_deref().unwrap_or(AZURE_API_VERSION)
+        )
+    }
+
+    fn apply_auth(
+        &self,
+        headers: &mut HeaderMap,
+        backend: &BackendConfig,
+    ) -> Result<(), http::Error> {
+        super::apply_auth(headers, backend, Some("api-key"))
+    }
+}
+
+/// Hugging Face Text Generation Inference serves the OpenAI endpoints but
+/// lists its single model at `/info`.
+pub struct Tgi;
+
+impl Adapter for Tgi {
+    fn models_path(&self, _backend: &BackendConfig) -> String {
+        "/info".to_string()
+    }
+
+    fn parse_models(&self, body: &Value) -> Result<Vec<ModelInfo>, String> {
+        let id = body["model_id"]
+            .as_str()
+            .ok_or("missing `model_id` in the server info")?;
+        Ok(vec![ModelInfo {
+            id: id.to_string(),
+            object: "model".to_string(),
+            created: 0,
+            owned_by: "tgi".to_string(),
+        }])
+    }
+
+    /// TGI errors are `{"error": "...", "error_type": "..."}`.
+    fn translate_error(&self, body: &[u8]) -> Option<Bytes> {
+        Some(upstream_error(body))
+    }
+}
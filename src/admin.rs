@@ -0,0 +1,55 @@
+use crate::auth;
+use crate::error::RouterError;
+use crate::model::AppState;
+use crate::reload::{ReloadSummary, reload_config};
+use axum::{Json, extract::State, http::HeaderMap};
+use serde::Serialize;
+use tracing::error;
+
+#[derive(Debug, Serialize)]
+pub struct BackendReport {
+    pub name: String,
+    pub url: String,
+    pub models: Vec<String>,
+    /// Seconds since the models were last refreshed successfully.
+    pub age_secs: Option<u64>,
+    /// Whether the latest refresh failed and cached models are being served.
+    pub stale: bool,
+    pub last_error: Option<String>,
+}
+
+pub async fn list_backends(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<BackendReport>>, RouterError> {
+    let config = state.config.load();
+    auth::authenticate_admin(&config, &headers)?;
+    let statuses = state.backend_status.read().await;
+    let reports = config
+        .backends
+        .iter()
+        .map(|backend| {
+            let status = statuses.get(&backend.name).cloned().unwrap_or_default();
+            BackendReport {
+                name: backend.name.clone(),
+                url: backend.url.clone(),
+                models: status.models.iter().map(|m| m.id.clone()).collect(),
+                age_secs: status.age().map(|age| age.as_secs()),
+                stale: status.is_stale(),
+                last_error: status.last_error,
+            }
+        })
+        .collect();
+    Ok(Json(reports))
+}
+
+pub async fn reload(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ReloadSummary>, RouterError> {
+    auth::authenticate_admin(&state.config.load(), &headers)?;
+    reload_config(&state).await.map(Json).map_err(|err| {
+        error!("Keeping the current configuration: {}", err);
+        RouterError::InvalidConfig(err)
+    })
+}
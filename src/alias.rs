@@ -0,0 +1,111 @@
+use crate::adapter::StreamTranslator;
+use crate::config::Config;
+use crate::model::ModelInfo;
+use crate::stream::SseFramer;
+use bytes::Bytes;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+
+/// Backend name -> upstream model for an aliased `model`, or `None` when
+/// `model` is not an alias.
+pub fn targets<'a>(config: &'a Config, model: &str) -> Option<HashMap<&'a str, &'a str>> {
+    let targets = config.aliases.get(model)?;
+    Some(
+        targets
+            .iter()
+            .map(|target| (target.backend.as_str(), target.model.as_str()))
+            .collect(),
+    )
+}
+
+/// The configured aliases, listed like models.
+pub fn models(config: &Config) -> Vec<ModelInfo> {
+    let mut models: Vec<ModelInfo> = config
+        .aliases
+        .keys()
+        .map(|alias| ModelInfo {
+            id: alias.clone(),
+            object: "model".to_string(),
+            created: 0,
+            owned_by: "llm-router".to_string(),
+        })
+        .collect();
+    models.sort_by(|a, b| a.id.cmp(&b.id));
+    models
+}
+
+/// The `discovered` models no alias shadows, followed by the aliases.
+pub fn with_models(config: &Config, discovered: &[ModelInfo]) -> Vec<ModelInfo> {
+    discovered
+        .iter()
+        .filter(|model| !config.aliases.contains_key(&model.id))
+        .cloned()
+        .chain(models(config))
+        .collect()
+}
+
+/// Copy of `request` asking for the `upstream` model.
+pub fn rewrite_request(request: &Value, upstream: &str) -> Value {
+    let mut request = request.clone();
+    request["model"] = json!(upstream);
+    request
+}
+
+/// Puts `alias` back into the `model` field of a JSON response, if it has one.
+pub fn rewrite_response(body: &[u8], alias: &str) -> Option<Bytes> {
+    let mut response: Value = serde_json::from_slice(body).ok()?;
+    response.get("model")?;
+    response["model"] = json!(alias);
+    Some(Bytes::from(response.to_string()))
+}
+
+/// Puts the alias back into the `model` field of every streamed chunk.
+pub struct StreamRewriter {
+    alias: String,
+    framer: SseFramer,
+}
+
+impl StreamRewriter {
+    pub fn new(alias: &str) -> Self {
+        Self {
+            alias: alias.to_string(),
+            framer: SseFramer::default(),
+        }
+    }
+
+    fn rewrite_event(&self, event: &[u8], out: &mut String) {
+        let text = String::from_utf8_lossy(event);
+        for line in text.split_inclusive('\n') {
+            let data = line
+                .trim_end()
+                .strip_prefix("data:")
+                .and_then(|data| serde_json::from_str::<Value>(data.trim()).ok())
+                .filter(|data| data.get("model").is_some());
+            match data {
+                Some(mut data) => {
+                    data["model"] = json!(self.alias);
+                    out.push_str(&format!("data: {}\n", data));
+                }
+                None => out.push_str(line),
+            }
+        }
+    }
+}
+
+impl StreamTranslator for StreamRewriter {
+    fn push(&mut self, chunk: &[u8]) -> Bytes {
+        let mut out = String::new();
+        for event in self.framer.push(chunk) {
+            self.rewrite_event(&event, &mut out);
+        }
+        Bytes::from(out)
+    }
+
+    fn finish(&mut self) -> Bytes {
+        let mut out = String::new();
+        if let Some(rest) = self.framer.finish() {
+            self.rewrite_event(&rest, &mut out);
+        }
+        Bytes::from(out)
+    }
+}
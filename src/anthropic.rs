@@ -0,0 +1,535 @@
+use crate::error::RouterError;
+use crate::model::AppState;
+use crate::router;
+use crate::stream::{self, SseFramer};
+use axum::{
+    Json,
+    body::Body,
+    extract::State,
+    http::{HeaderMap, HeaderValue, Response, StatusCode, header},
+    response::IntoResponse,
+};
+use bytes::Bytes;
+use futures_util::StreamExt;
+use http_body_util::BodyExt;
+use serde_json::{Value, json};
+
+/// Serves an Anthropic Messages request through the OpenAI chat completions
+/// endpoint and translates the response back, including the Messages event
+/// sequence for streams.
+pub async fn messages(
+    State(state): State<AppState>,
+    mut headers: HeaderMap,
+    body: Body,
+) -> Response<Body> {
+    let bytes = body.collect().await.unwrap_or_default().to_bytes();
+    let request: Value = match serde_json::from_slice(&bytes) {
+        Ok(request) => request,
+        Err(err) => return router_error(RouterError::InvalidJson(err.to_string())),
+    };
+    let model = request
+        .get("model")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    let response = router::forward(
+        state,
+        headers,
+        Body::from(openai_request(&request).to_string()),
+        "/v1/chat/completions",
+    )
+    .await;
+    if !response.status().is_success() {
+        return error_response(response).await;
+    }
+
+    let translator = Translator::new(model);
+    let (mut parts, body) = response.into_parts();
+    if stream::is_event_stream(&parts.headers) {
+        let events = futures_util::stream::unfold(
+            Some((body.into_data_stream(), translator)),
+            |state| async move {
+                let (mut chunks, mut translator) = state?;
+                match chunks.next().await {
+                    Some(Ok(chunk)) => {
+                        Some((Ok(translator.push(&chunk)), Some((chunks, translator))))
+                    }
+                    Some(Err(err)) => Some((Err(err), None)),
+                    None => Some((Ok(translator.finish()), None)),
+                }
+            },
+        );
+        return Response::from_parts(parts, Body::from_stream(events));
+    }
+
+    let bytes = body.collect().await.unwrap_or_default().to_bytes();
+    let completion: Value = serde_json::from_slice(&bytes).unwrap_or_default();
+    let message = translator.complete(&completion);
+    parts.headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    Response::from_parts(parts, Body::from(message.to_string()))
+}
+
+/// The OpenAI chat completion request for a Messages request. Streams ask
+/// for usage so `message_delta` can report the output tokens.
+fn openai_request(request: &Value) -> Value {
+    let mut messages = Vec::new();
+    match request.get("system") {
+        Some(Value::String(system)) => {
+            messages.push(json!({"role": "system", "content": system}));
+        }
+        Some(Value::Array(blocks)) => {
+            messages.push(json!({"role": "system", "content": text_of(blocks)}));
+        }
+        _ => {}
+    }
+    for message in request
+        .get("messages")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        openai_messages(message, &mut messages);
+    }
+
+    let streaming = request
+        .get("stream")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    let mut body = json!({
+        "model": request.get("model").cloned().unwrap_or_default(),
+        "messages": messages,
+        "stream": streaming,
+    });
+    if streaming {
+        body["stream_options"] = json!({"include_usage": true});
+    }
+    for field in ["max_tokens", "temperature", "top_p"] {
+        if let Some(value) = request.get(field) {
+            body[field] = value.clone();
+        }
+    }
+    if let Some(stop) = request.get("stop_sequences") {
+        body["stop"] = stop.clone();
+    }
+    if let Some(user) = request.pointer("/metadata/user_id") {
+        body["user"] = user.clone();
+    }
+    if let Some(tools) = request.get("tools").and_then(Value::as_array) {
+        let tools: Vec<Value> = tools
+            .iter()
+            .map(|tool| {
+                json!({
+                    "type": "function",
+                    "function": {
+                        "name": tool["name"],
+                        "description": tool.get("description").cloned().unwrap_or_default(),
+                        "parameters": tool["input_schema"],
+                    },
+                })
+            })
+            .collect();
+        body["tools"] = Value::Array(tools);
+    }
+    if let Some(choice) = request.get("tool_choice") {
+        body["tool_choice"] = match choice["type"].as_str() {
+            Some("any") => json!("required"),
+            Some("none") => json!("none"),
+            Some("tool") => json!({"type": "function", "function": {"name": choice["name"]}}),
+            _ => json!("auto"),
+        };
+        if choice["disable_parallel_tool_use"] == true {
+            body["parallel_tool_calls"] = json!(false);
+        }
+    }
+    body
+}
+
+/// Appends the OpenAI messages for one Messages message: `tool_result`
+/// blocks become `tool` messages, `tool_use` blocks assistant tool calls,
+/// and text and images the content.
+fn openai_messages(message: &Value, messages: &mut Vec<Value>) {
+    let role = message["role"].as_str().unwrap_or("user");
+    let blocks = match &message["content"] {
+        Value::Array(blocks) => blocks,
+        content => {
+            messages.push(json!({"role": role, "content": content}));
+            return;
+        }
+    };
+
+    let mut parts = Vec::new();
+    let mut tool_calls = Vec::new();
+    for block in blocks {
+        match block["type"].as_str() {
+            Some("text") => parts.push(json!({"type": "text", "text": block["text"]})),
+            Some("image") => {
+                if let Some(url) = image_url(&block["source"]) {
+                    parts.push(json!({"type": "image_url", "image_url": {"url": url}}));
+                }
+            }
+            Some("tool_use") => tool_calls.push(json!({
+                "id": block["id"],
+                "type": "function",
+                "function": {"name": block["name"], "arguments": block["input"].to_string()},
+            })),
+            Some("tool_result") => {
+                let content = match &block["content"] {
+                    Value::Array(blocks) => Value::String(text_of(blocks)),
+                    Value::Null => json!(""),
+                    content => content.clone(),
+                };
+                messages.push(json!({
+                    "role": "tool",
+                    "tool_call_id": block["tool_use_id"],
+                    "content": content,
+                }));
+            }
+            // Thinking blocks only make sense to the model that wrote them.
+            _ => {}
+        }
+    }
+
+    if role == "assistant" {
+        // Assistant content is text only.
+        let mut message = json!({"role": "assistant", "content": text_of(&parts)});
+        if !tool_calls.is_empty() {
+            message["tool_calls"] = Value::Array(tool_calls);
+        }
+        messages.push(message);
+    } else if !parts.is_empty() {
+        messages.push(json!({"role": role, "content": parts}));
+    }
+}
+
+/// Joins the text of `blocks`, which may be text blocks or plain strings.
+fn text_of(blocks: &[Value]) -> String {
+    blocks
+        .iter()
+        .filter_map(|block| block.as_str().or_else(|| block["text"].as_str()))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn image_url(source: &Value) -> Option<String> {
+    match source["type"].as_str()? {
+        "base64" => Some(format!(
+            "data:{};base64,{}",
+            source["media_type"].as_str()?,
+            source["data"].as_str()?
+        )),
+        "url" => source["url"].as_str().map(str::to_string),
+        _ => None,
+    }
+}
+
+fn stop_reason(finish_reason: Option<&str>) -> &'static str {
+    match finish_reason {
+        Some("length") => "max_tokens",
+        Some("tool_calls") => "tool_use",
+        Some("content_filter") => "refusal",
+        _ => "end_turn",
+    }
+}
+
+/// Builds Messages responses from OpenAI completions and completion chunks.
+struct Translator {
+    id: String,
+    model: String,
+    framer: SseFramer,
+    started: bool,
+    /// Index and kind of the content block being streamed.
+    block: Option<(usize, Block)>,
+    blocks: usize,
+    finish_reason: Option<String>,
+    usage: Option<Value>,
+    failed: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Block {
+    Text,
+    /// A tool call, by its OpenAI index.
+    ToolUse(u64),
+}
+
+impl Translator {
+    fn new(model: String) -> Self {
+        Self {
+            id: format!("msg_{}", uuid::Uuid::new_v4().simple()),
+            model,
+            framer: SseFramer::default(),
+            started: false,
+            block: None,
+            blocks: 0,
+            finish_reason: None,
+            usage: None,
+            failed: false,
+        }
+    }
+
+    /// Translates a non-streamed completion into a Messages response.
+    fn complete(&self, completion: &Value) -> Value {
+        let choice = &completion["choices"][0];
+        let message = &choice["message"];
+        let mut content = Vec::new();
+        if let Some(text) = message["content"].as_str()
+            && !text.is_empty()
+        {
+            content.push(json!({"type": "text", "text": text}));
+        }
+        for call in message["tool_calls"].as_array().into_iter().flatten() {
+            let function = &call["function"];
+            content.push(json!({
+                "type": "tool_use",
+                "id": call["id"],
+                "name": function["name"],
+                "input": tool_input(function["arguments"].as_str().unwrap_or_default()),
+            }));
+        }
+        let usage = &completion["usage"];
+        json!({
+            "id": self.id,
+            "type": "message",
+            "role": "assistant",
+            "model": self.model,
+            "content": content,
+            "stop_reason": stop_reason(choice["finish_reason"].as_str()),
+            "stop_sequence": null,
+            "usage": {
+                "input_tokens": usage["prompt_tokens"].as_u64().unwrap_or(0),
+                "output_tokens": usage["completion_tokens"].as_u64().unwrap_or(0),
+            },
+        })
+    }
+
+    /// Translates the Server-Sent Events in `chunk` into Messages events.
+    fn push(&mut self, chunk: &[u8]) -> Bytes {
+        let mut events = String::new();
+        for event in self.framer.push(chunk) {
+            self.translate_event(&event, &mut events);
+        }
+        Bytes::from(events)
+    }
+
+    /// The events ending a stream: `content_block_stop` for the open block,
+    /// `message_delta` with the stop reason and usage, and `message_stop`.
+    fn finish(&mut self) -> Bytes {
+        let mut events = String::new();
+        if let Some(rest) = self.framer.finish() {
+            self.translate_event(&rest, &mut events);
+        }
+        if self.failed {
+            return Bytes::from(events);
+        }
+        self.start(&mut events);
+        self.close_block(&mut events);
+        let usage = self.usage.clone().unwrap_or_default();
+        push_event(
+            &mut events,
+            "message_delta",
+            json!({
+                "delta": {
+                    "stop_reason": stop_reason(self.finish_reason.as_deref()),
+                    "stop_sequence": null,
+                },
+                "usage": {
+                    "input_tokens": usage["prompt_tokens"].as_u64().unwrap_or(0),
+                    "output_tokens": usage["completion_tokens"].as_u64().unwrap_or(0),
+                },
+            }),
+        );
+        push_event(&mut events, "message_stop", json!({}));
+        Bytes::from(events)
+    }
+
+    fn translate_event(&mut self, event: &[u8], events: &mut String) {
+        let text = String::from_utf8_lossy(event);
+        for data in text.lines().filter_map(|line| line.strip_prefix("data:")) {
+            let Ok(chunk) = serde_json::from_str::<Value>(data.trim()) else {
+                continue;
+            };
+            if let Some(error) = chunk.get("error") {
+                let message = error
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .unwrap_or("The backend stream failed.");
+                push_event(
+                    events,
+                    "error",
+                    json!({"error": {"type": "api_error", "message": message}}),
+                );
+                self.failed = true;
+                continue;
+            }
+            self.start(events);
+            if let Some(usage) = chunk.get("usage").filter(|usage| !usage.is_null()) {
+                self.usage = Some(usage.clone());
+            }
+            let choice = &chunk["choices"][0];
+            if let Some(reason) = choice["finish_reason"].as_str() {
+                self.finish_reason = Some(reason.to_string());
+            }
+            let delta = &choice["delta"];
+            if let Some(text) = delta["content"].as_str()
+                && !text.is_empty()
+            {
+                let index =
+                    self.open_block(Block::Text, json!({"type": "text", "text": ""}), events);
+                push_event(
+                    events,
+                    "content_block_delta",
+                    json!({"index": index, "delta": {"type": "text_delta", "text": text}}),
+                );
+            }
+            for call in delta["tool_calls"].as_array().into_iter().flatten() {
+                let block = Block::ToolUse(call["index"].as_u64().unwrap_or(0));
+                let function = &call["function"];
+                let index = self.open_block(
+                    block,
+                    json!({
+                        "type": "tool_use",
+                        "id": call["id"],
+                        "name": function["name"],
+                        "input": {},
+                    }),
+                    events,
+                );
+                if let Some(arguments) = function["arguments"].as_str()
+                    && !arguments.is_empty()
+                {
+                    push_event(
+                        events,
+                        "content_block_delta",
+                        json!({
+                            "index": index,
+                            "delta": {"type": "input_json_delta", "partial_json": arguments},
+                        }),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Sends `message_start` before anything else. Usage only arrives at
+    /// the end of the stream, so it is reported in `message_delta`.
+    fn start(&mut self, events: &mut String) {
+        if self.started {
+            return;
+        }
+        self.started = true;
+        push_event(
+            events,
+            "message_start",
+            json!({
+                "message": {
+                    "id": self.id,
+                    "type": "message",
+                    "role": "assistant",
+                    "model": self.model,
+                    "content": [],
+                    "stop_reason": null,
+                    "stop_sequence": null,
+                    "usage": {"input_tokens": 0, "output_tokens": 0},
+                },
+            }),
+        );
+    }
+
+    /// Returns the index of the content block for `block`, closing the open
+    /// block and starting a new one with `content_block` if it differs.
+    fn open_block(&mut self, block: Block, content_block: Value, events: &mut String) -> usize {
+        if let Some((index, open)) = self.block
+            && open == block
+        {
+            return index;
+        }
+        self.close_block(events);
+        let index = self.blocks;
+        self.blocks += 1;
+        self.block = Some((index, block));
+        push_event(
+            events,
+            "content_block_start",
+            json!({"index": index, "content_block": content_block}),
+        );
+        index
+    }
+
+    fn close_block(&mut self, events: &mut String) {
+        if let Some((index, _)) = self.block.take() {
+            push_event(events, "content_block_stop", json!({"index": index}));
+        }
+    }
+}
+
+/// Appends a Server-Sent Event whose data carries its `type`, as the
+/// Messages API does.
+fn push_event(events: &mut String, event: &str, mut data: Value) {
+    data["type"] = json!(event);
+    events.push_str(&format!("event: {}\ndata: {}\n\n", event, data));
+}
+
+/// Tool arguments arrive as a JSON string; Messages expects an object.
+fn tool_input(arguments: &str) -> Value {
+    serde_json::from_str(arguments).unwrap_or_else(|_| json!({}))
+}
+
+/// The Messages error type for an HTTP status.
+fn error_type(status: StatusCode) -> &'static str {
+    match status.as_u16() {
+        400 => "invalid_request_error",
+        401 => "authentication_error",
+        403 => "permission_error",
+        404 => "not_found_error",
+        413 => "request_too_large",
+        429 => "rate_limit_error",
+        529 => "overloaded_error",
+        _ => "api_error",
+    }
+}
+
+/// Messages errors are `{"type": "error", "error": {"type", "message"}}`.
+fn error(status: StatusCode, message: &str) -> Response<Body> {
+    let body = json!({
+        "type": "error",
+        "error": {"type": error_type(status), "message": message},
+    });
+    (status, Json(body)).into_response()
+}
+
+fn router_error(err: RouterError) -> Response<Body> {
+    error(err.status(), &err.to_string())
+}
+
+/// Rewrites an OpenAI-format error response, from the router or a backend,
+/// into the Messages format, keeping its status and headers.
+async fn error_response(response: Response<Body>) -> Response<Body> {
+    let status = response.status();
+    let (mut parts, body) = response.into_parts();
+    let bytes = body.collect().await.unwrap_or_default().to_bytes();
+    let body: Value = serde_json::from_slice(&bytes).unwrap_or_default();
+    let message = match &body["error"] {
+        Value::String(message) => message.clone(),
+        error => error["message"]
+            .as_str()
+            .map(str::to_string)
+            .unwrap_or_else(|| String::from_utf8_lossy(&bytes).into_owned()),
+    };
+    parts.headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    let body = json!({
+        "type": "error",
+        "error": {"type": error_type(status), "message": message},
+    });
+    Response::from_parts(parts, Body::from(body.to_string()))
+}
@@ -0,0 +1,128 @@
+use crate::config::{ClientKeyConfig, Config};
+use crate::error::RouterError;
+use axum::http::{HeaderMap, header};
+use sha2::{Digest, Sha256};
+use tracing::{Span, field::Empty, instrument};
+
+/// Identifies the client key a request was made with.
+///
+/// Returns `Ok(None)` when client authentication is disabled, i.e. no keys
+/// are configured. The key is read from `Authorization: Bearer <key>` or,
+/// failing that, from `x-api-key`.
+#[instrument(skip_all, fields(llm_router.client_key = Empty))]
+pub fn authenticate(
+    config: &Config,
+    headers: &HeaderMap,
+) -> Result<Option<ClientKeyConfig>, RouterError> {
+    let keys = &config.client_auth.keys;
+    if keys.is_empty() {
+        return Ok(None);
+    }
+
+    let presented = presented_key(headers).ok_or(RouterError::MissingApiKey)?;
+
+    let digest = hash_key(presented);
+    let key = keys
+        .iter()
+        .find(|key| {
+            constant_time_eq(
+                key.key_sha256.to_ascii_lowercase().as_bytes(),
+                digest.as_bytes(),
+            )
+        })
+        .filter(|key| key.enabled)
+        .ok_or(RouterError::InvalidApiKey)?;
+    Span::current().record("llm_router.client_key", key.name.as_str());
+    Ok(Some(key.clone()))
+}
+
+/// Checks the admin key of the `/admin` endpoints, which is presented like a
+/// client key. Fails with `403` while no admin key is configured.
+pub fn authenticate_admin(config: &Config, headers: &HeaderMap) -> Result<(), RouterError> {
+    let expected = config
+        .client_auth
+        .admin_key_sha256
+        .as_ref()
+        .ok_or(RouterError::AdminDisabled)?;
+    let presented = presented_key(headers).ok_or(RouterError::MissingApiKey)?;
+    if constant_time_eq(
+        expected.to_ascii_lowercase().as_bytes(),
+        hash_key(presented).as_bytes(),
+    ) {
+        Ok(())
+    } else {
+        Err(RouterError::InvalidApiKey)
+    }
+}
+
+/// Fails with `403` unless `key` may use `model`.
+pub fn authorize_model(key: Option<&ClientKeyConfig>, model: &str) -> Result<(), RouterError> {
+    match key {
+        Some(key) if !key.allows(model) => Err(RouterError::ModelNotAllowed(model.to_string())),
+        _ => Ok(()),
+    }
+}
+
+impl ClientKeyConfig {
+    pub fn allows(&self, model: &str) -> bool {
+        self.models.iter().any(|pattern| glob_match(pattern, model))
+    }
+}
+
+/// Hex-encoded SHA-256 digest of `key`, as expected in `key_sha256`.
+pub fn hash_key(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// The key from `Authorization: Bearer <key>` or, failing that, `x-api-key`.
+fn presented_key(headers: &HeaderMap) -> Option<&str> {
+    bearer_token(headers)
+        .or_else(|| headers.get("x-api-key").and_then(|v| v.to_str().ok()))
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    scheme.eq_ignore_ascii_case("bearer").then_some(token)
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Matches `text` against a pattern where `*` stands for any run of
+/// characters (including none) and `?` for exactly one.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text position it currently absorbs up to.
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, absorbed)) => {
+                    p = star + 1;
+                    t = absorbed + 1;
+                    backtrack = Some((star, absorbed + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
@@ -0,0 +1,104 @@
+use crate::config::{BackendConfig, Strategy};
+use rand::Rng;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Picks one of several backends serving the same model.
+#[derive(Default)]
+pub struct Balancer {
+    cursors: Mutex<HashMap<String, usize>>,
+    current_weights: Mutex<HashMap<String, HashMap<String, i64>>>,
+    outstanding: Mutex<HashMap<String, Arc<AtomicUsize>>>,
+}
+
+impl Balancer {
+    pub fn pick<'a>(
+        &self,
+        model: &str,
+        strategy: Strategy,
+        candidates: &[&'a BackendConfig],
+    ) -> Option<&'a BackendConfig> {
+        match candidates {
+            [] => None,
+            [only] => Some(only),
+            _ => {
+                let index = match strategy {
+                    Strategy::RoundRobin => self.next_cursor(model) % candidates.len(),
+                    Strategy::Weighted => self.pick_weighted(model, candidates),
+                    Strategy::Random => rand::rng().random_range(0..candidates.len()),
+                    Strategy::LeastOutstanding => self.pick_least_outstanding(model, candidates),
+                };
+                Some(candidates[index])
+            }
+        }
+    }
+
+    /// Marks a request to `backend` as in flight until the guard is dropped.
+    pub fn begin(&self, backend: &str) -> OutstandingGuard {
+        let counter = self.counter(backend);
+        counter.fetch_add(1, Ordering::SeqCst);
+        OutstandingGuard(counter)
+    }
+
+    pub fn outstanding(&self, backend: &str) -> usize {
+        self.counter(backend).load(Ordering::SeqCst)
+    }
+
+    fn counter(&self, backend: &str) -> Arc<AtomicUsize> {
+        self.outstanding
+            .lock()
+            .unwrap()
+            .entry(backend.to_string())
+            .or_default()
+            .clone()
+    }
+
+    fn next_cursor(&self, model: &str) -> usize {
+        let mut cursors = self.cursors.lock().unwrap();
+        let cursor = cursors.entry(model.to_string()).or_insert(0);
+        let value = *cursor;
+        *cursor = cursor.wrapping_add(1);
+        value
+    }
+
+    // Smooth weighted round-robin, as used by nginx: deterministic and
+    // interleaves backends instead of sending bursts to the heaviest one.
+    fn pick_weighted(&self, model: &str, candidates: &[&BackendConfig]) -> usize {
+        let mut all_weights = self.current_weights.lock().unwrap();
+        let current = all_weights.entry(model.to_string()).or_default();
+
+        let total: i64 = candidates.iter().map(|b| i64::from(b.weight)).sum();
+        let mut best = 0;
+        let mut best_weight = i64::MIN;
+        for (i, backend) in candidates.iter().enumerate() {
+            let weight = current.entry(backend.name.clone()).or_insert(0);
+            *weight += i64::from(backend.weight);
+            if *weight > best_weight {
+                best = i;
+                best_weight = *weight;
+            }
+        }
+        if let Some(weight) = current.get_mut(&candidates[best].name) {
+            *weight -= total;
+        }
+        best
+    }
+
+    fn pick_least_outstanding(&self, model: &str, candidates: &[&BackendConfig]) -> usize {
+        // Start from a rotating offset so ties don't always favour the first backend.
+        let offset = self.next_cursor(model);
+        (0..candidates.len())
+            .map(|i| (offset + i) % candidates.len())
+            .min_by_key(|&i| self.outstanding(&candidates[i].name))
+            .unwrap_or(0)
+    }
+}
+
+pub struct OutstandingGuard(Arc<AtomicUsize>);
+
+impl Drop for OutstandingGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
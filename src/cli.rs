@@ -0,0 +1,102 @@
+use crate::config::{Config, LogFormat};
+use crate::listen::ListenAddr;
+use clap::{Parser, Subcommand};
+use std::path::{Path, PathBuf};
+
+/// Routes OpenAI-compatible requests to the backend serving the requested model.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Configuration file. Falls back to the same name with a `.yml` or
+    /// `.yaml` extension.
+    #[arg(
+        short,
+        long,
+        global = true,
+        env = "LLM_ROUTER_CONFIG",
+        default_value = "config.yml"
+    )]
+    pub config: PathBuf,
+
+    /// Configuration file, in place of `--config`.
+    #[arg(value_name = "CONFIG")]
+    pub config_file: Option<PathBuf>,
+
+    /// Validate the configuration file and exit, like `check-config`.
+    #[arg(long, global = true)]
+    pub check_config: bool,
+
+    /// Address to listen on: `host:port`, `[ipv6]:port` or `unix:/path`.
+    /// Repeat or separate with commas for several. Overrides `server.listen`.
+    #[arg(
+        short,
+        long,
+        global = true,
+        env = "LLM_ROUTER_LISTEN",
+        value_delimiter = ','
+    )]
+    pub listen: Vec<ListenAddr>,
+
+    /// Log filter, e.g. `info` or `llm_router=debug`. Overrides
+    /// `server.log_level` and `RUST_LOG`.
+    #[arg(long, global = true, env = "LLM_ROUTER_LOG_LEVEL")]
+    pub log_level: Option<String>,
+
+    /// Log output format. Overrides `server.log_format`.
+    #[arg(long, global = true, env = "LLM_ROUTER_LOG_FORMAT", value_enum)]
+    pub log_format: Option<LogFormat>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Subcommand)]
+pub enum Command {
+    /// Run the router (the default).
+    Serve,
+    /// Validate the configuration file and exit.
+    CheckConfig,
+    /// Discover the models of every backend, print them and exit.
+    ListModels,
+}
+
+impl Cli {
+    pub fn command(&self) -> Command {
+        match self.command {
+            _ if self.check_config => Command::CheckConfig,
+            Some(command) => command,
+            None => Command::Serve,
+        }
+    }
+
+    /// The configuration file: the positional argument, then `--config`.
+    pub fn config_path(&self) -> &Path {
+        self.config_file.as_deref().unwrap_or(&self.config)
+    }
+
+    /// Addresses to listen on: the command line, then the configuration,
+    /// then `0.0.0.0:8080`.
+    pub fn listen_addrs(&self, config: &Config) -> Vec<ListenAddr> {
+        if !self.listen.is_empty() {
+            self.listen.clone()
+        } else if !config.server.listen.is_empty() {
+            config.server.listen.clone()
+        } else {
+            vec![ListenAddr::default()]
+        }
+    }
+
+    /// The log filter from the command line or the configuration. `None`
+    /// leaves it to `RUST_LOG`.
+    pub fn log_level(&self, config: &Config) -> Option<String> {
+        self.log_level
+            .clone()
+            .or_else(|| config.server.log_level.clone())
+    }
+
+    pub fn log_format(&self, config: &Config) -> LogFormat {
+        self.log_format
+            .or(config.server.log_format)
+            .unwrap_or_default()
+    }
+}
@@ -1,60 +1,1181 @@
+use crate::auth::glob_match;
+use crate::listen::ListenAddr;
+use axum::http::{HeaderName, HeaderValue};
+use regex::Regex;
 use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
 
 #[derive(Debug, Deserialize)]
 pub struct Config {
     pub refresh_interval: u64,
+    /// Seconds a backend's last known models keep being served while its
+    /// discovery keeps failing. Unset means they are kept until it recovers.
+    pub max_staleness: Option<u64>,
+    /// Seconds to wait for a backend's model list before giving up on it.
+    #[serde(default = "default_discovery_timeout")]
+    pub discovery_timeout: u64,
+    /// Seconds between checks of the configuration file for changes.
+    /// `0` disables watching; SIGHUP and `POST /admin/reload` still work.
+    #[serde(default = "default_watch_interval")]
+    pub watch_interval: u64,
     pub backends: Vec<BackendConfig>,
+    #[serde(default)]
+    pub load_balancing: LoadBalancingConfig,
+    #[serde(default)]
+    pub retry: RetryConfig,
+    #[serde(default)]
+    pub server: ServerConfig,
+    #[serde(default)]
+    pub client_auth: ClientAuthConfig,
+    #[serde(default)]
+    pub rate_limits: RateLimitsConfig,
+    /// Public model names served by models of specific backends.
+    #[serde(default)]
+    pub aliases: HashMap<String, Vec<AliasTarget>>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            refresh_interval: 0,
+            max_staleness: None,
+            discovery_timeout: default_discovery_timeout(),
+            watch_interval: default_watch_interval(),
+            backends: Vec::new(),
+            load_balancing: LoadBalancingConfig::default(),
+            retry: RetryConfig::default(),
+            server: ServerConfig::default(),
+            client_auth: ClientAuthConfig::default(),
+            rate_limits: RateLimitsConfig::default(),
+            aliases: HashMap::new(),
+        }
+    }
+}
+
+impl Config {
+    pub fn max_staleness(&self) -> Option<Duration> {
+        self.max_staleness.map(Duration::from_secs)
+    }
+
+    pub fn discovery_timeout_for(&self, backend: &BackendConfig) -> Duration {
+        Duration::from_secs(backend.timeouts.discovery.unwrap_or(self.discovery_timeout))
+    }
+
+    /// Checks invariants that cannot be expressed in the YAML schema and
+    /// reports every violation found.
+    pub fn validate(&self) -> Result<(), Vec<ValidationIssue>> {
+        let mut issues = Vec::new();
+        let mut issue = |field: String, message: String| {
+            issues.push(ValidationIssue { field, message });
+        };
+
+        if self.refresh_interval == 0 {
+            issue("refresh_interval".into(), "must be greater than 0".into());
+        }
+        if let Some(endpoint) = &self.server.tracing.endpoint
+            && let Some(message) = url_issue(endpoint)
+        {
+            issue("server.tracing.endpoint".into(), message);
+        }
+        if let Some(recorder) = &self.server.recorder {
+            if !(0.0..=1.0).contains(&recorder.sample_rate) {
+                issue(
+                    "server.recorder.sample_rate".into(),
+                    "must be between 0.0 and 1.0".into(),
+                );
+            }
+            if recorder.max_bytes == Some(0) {
+                issue(
+                    "server.recorder.max_bytes".into(),
+                    "must be greater than 0; omit it to never rotate".into(),
+                );
+            }
+            for (i, path) in recorder.redact.iter().enumerate() {
+                if path.split('.').any(str::is_empty) {
+                    issue(
+                        format!("server.recorder.redact[{}]", i),
+                        format!("invalid field path '{}'", path),
+                    );
+                }
+            }
+        }
+        if self.retry.max_attempts == 0 {
+            issue("retry.max_attempts".into(), "must be at least 1".into());
+        }
+
+        let mut names = HashSet::new();
+        for (i, backend) in self.backends.iter().enumerate() {
+            let field = |name: &str| format!("backends[{}].{}", i, name);
+
+            if backend.name.trim().is_empty() {
+                issue(field("name"), "must not be empty".into());
+            } else if !names.insert(backend.name.as_str()) {
+                issue(
+                    field("name"),
+                    format!("duplicate backend name '{}'", backend.name),
+                );
+            }
+
+            if let Some(message) = url_issue(&backend.url) {
+                issue(field("url"), message);
+            }
+            if backend.weight == 0 {
+                issue(field("weight"), "must be at least 1".into());
+            }
+
+            if backend.kind != BackendKind::Azure {
+                if backend.api_version.is_some() {
+                    issue(
+                        field("api_version"),
+                        "is only used by azure backends".into(),
+                    );
+                }
+                if !backend.deployments.is_empty() {
+                    issue(
+                        field("deployments"),
+                        "is only used by azure backends".into(),
+                    );
+                }
+            }
+            for (model, deployment) in &backend.deployments {
+                if deployment.trim().is_empty() {
+                    issue(
+                        field(&format!("deployments.{}", model)),
+                        "must not be empty".into(),
+                    );
+                }
+            }
+
+            for (i, model) in backend.models.iter().enumerate() {
+                if model.trim().is_empty() {
+                    issue(field(&format!("models[{}]", i)), "must not be empty".into());
+                }
+            }
+            let invalid_value = "contains characters not allowed in a header value";
+            match &backend.auth {
+                Some(AuthConfig::Bearer { token }) if HeaderValue::from_str(token).is_err() => {
+                    issue(field("auth.token"), invalid_value.into());
+                }
+                Some(AuthConfig::Basic { username, password }) => {
+                    if HeaderValue::from_str(username).is_err() {
+                        issue(field("auth.username"), invalid_value.into());
+                    } else if username.contains(':') {
+                        issue(field("auth.username"), "must not contain ':'".into());
+                    }
+                    if HeaderValue::from_str(password).is_err() {
+                        issue(field("auth.password"), invalid_value.into());
+                    }
+                }
+                Some(AuthConfig::CustomHeader { name, value }) => {
+                    if HeaderName::from_bytes(name.as_bytes()).is_err() {
+                        issue(
+                            field("auth.name"),
+                            format!("invalid header name '{}'", name),
+                        );
+                    }
+                    if HeaderValue::from_str(value).is_err() {
+                        issue(field("auth.value"), invalid_value.into());
+                    }
+                }
+                _ => {}
+            }
+
+            backend
+                .headers
+                .request
+                .validate(&field("headers.request"), &mut issue);
+            backend
+                .headers
+                .response
+                .validate(&field("headers.response"), &mut issue);
+
+            if let Some(rate_limit) = &backend.rate_limit {
+                rate_limit.validate(&field("rate_limit"), &mut issue);
+                if rate_limit.tokens_per_minute.is_some() {
+                    issue(
+                        field("rate_limit.tokens_per_minute"),
+                        "is only supported for client keys and models".into(),
+                    );
+                }
+            }
+
+            let timeouts = [
+                ("connect", backend.timeouts.connect),
+                ("request", backend.timeouts.request),
+                ("first_byte", backend.timeouts.first_byte),
+                ("idle", backend.timeouts.idle),
+                ("discovery", backend.timeouts.discovery),
+            ];
+            for (name, value) in timeouts {
+                if value == Some(0) {
+                    issue(
+                        field(&format!("timeouts.{}", name)),
+                        "must be greater than 0; omit it for no limit".into(),
+                    );
+                }
+            }
+        }
+
+        if let Some(digest) = &self.client_auth.admin_key_sha256
+            && !is_sha256_digest(digest)
+        {
+            issue(
+                "client_auth.admin_key_sha256".into(),
+                "must be a hex-encoded SHA-256 digest (64 characters)".into(),
+            );
+        }
+        let mut key_names = HashSet::new();
+        for (i, key) in self.client_auth.keys.iter().enumerate() {
+            let field = |name: &str| format!("client_auth.keys[{}].{}", i, name);
+
+            if key.name.trim().is_empty() {
+                issue(field("name"), "must not be empty".into());
+            } else if !key_names.insert(key.name.as_str()) {
+                issue(field("name"), format!("duplicate key name '{}'", key.name));
+            }
+            if !is_sha256_digest(&key.key_sha256) {
+                issue(
+                    field("key_sha256"),
+                    "must be a hex-encoded SHA-256 digest (64 characters)".into(),
+                );
+            }
+            if let Some(rate_limit) = &key.rate_limit {
+                rate_limit.validate(&field("rate_limit"), &mut issue);
+            }
+        }
+
+        for (model, rate_limit) in &self.rate_limits.models {
+            rate_limit.validate(&format!("rate_limits.models.{}", model), &mut issue);
+        }
+        for (alias, targets) in &self.aliases {
+            if targets.is_empty() {
+                issue(
+                    format!("aliases.{}", alias),
+                    "must have at least one target".into(),
+                );
+            }
+            for (i, target) in targets.iter().enumerate() {
+                let field = |name: &str| format!("aliases.{}[{}].{}", alias, i, name);
+                if !self.backends.iter().any(|b| b.name == target.backend) {
+                    issue(
+                        field("backend"),
+                        format!("unknown backend '{}'", target.backend),
+                    );
+                }
+                if target.model.trim().is_empty() {
+                    issue(field("model"), "must not be empty".into());
+                }
+            }
+        }
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
+        }
+    }
+}
+
+/// Describes why `url` is not a usable http or https URL.
+fn url_issue(url: &str) -> Option<String> {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if parsed.scheme() == "http" || parsed.scheme() == "https" => None,
+        Ok(parsed) => Some(format!(
+            "unsupported scheme '{}', expected http or https",
+            parsed.scheme()
+        )),
+        Err(err) => Some(format!("invalid URL '{}': {}", url, err)),
+    }
+}
+
+/// A semantic problem with a configuration that parsed successfully.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+    /// Path of the offending field, e.g. `backends[1].url`.
+    pub field: String,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+fn default_discovery_timeout() -> u64 {
+    10
+}
+
+fn default_watch_interval() -> u64 {
+    5
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct BackendConfig {
     pub name: String,
     pub url: String,
+    /// Protocol the backend speaks. Clients always speak OpenAI.
+    #[serde(default)]
+    pub kind: BackendKind,
+    /// `api-version` query parameter of `azure` backends.
+    pub api_version: Option<String>,
+    /// Model name -> deployment of `azure` backends. When set, the models
+    /// are served without discovery.
+    #[serde(default)]
+    pub deployments: HashMap<String, String>,
+    /// Models served without discovery, for backends that cannot list them.
+    #[serde(default)]
+    pub models: Vec<String>,
+    /// Which of the backend's models are exposed.
+    #[serde(default)]
+    pub model_filter: ModelFilter,
     pub auth: Option<AuthConfig>,
+    /// Relative share of traffic under the `weighted` strategy.
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+    #[serde(default)]
+    pub timeouts: TimeoutConfig,
+    #[serde(default)]
+    pub headers: HeaderPolicy,
+    /// Limits on the traffic sent to this backend.
+    pub rate_limit: Option<RateLimit>,
+}
+
+impl Default for BackendConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            url: String::new(),
+            kind: BackendKind::default(),
+            api_version: None,
+            deployments: HashMap::new(),
+            models: Vec::new(),
+            model_filter: ModelFilter::default(),
+            auth: None,
+            weight: default_weight(),
+            timeouts: TimeoutConfig::default(),
+            headers: HeaderPolicy::default(),
+            rate_limit: None,
+        }
+    }
+}
+
+/// A backend and the model it serves an alias with.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct AliasTarget {
+    pub backend: String,
+    pub model: String,
+}
+
+/// Selects the models a backend exposes. Patterns are globs (`*`, `?`) or,
+/// between slashes, regular expressions (`/^gpt-4o(-mini)?$/`).
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+pub struct ModelFilter {
+    /// Only models matching one of these are exposed (empty exposes all).
+    #[serde(default)]
+    pub include: Vec<ModelPattern>,
+    /// Models matching one of these are never exposed.
+    #[serde(default)]
+    pub exclude: Vec<ModelPattern>,
+}
+
+impl ModelFilter {
+    /// Whether `model` passes the filter.
+    pub fn allows(&self, model: &str) -> bool {
+        let matches = |pattern: &ModelPattern| pattern.matches(model);
+        (self.include.is_empty() || self.include.iter().any(matches))
+            && !self.exclude.iter().any(matches)
+    }
 }
 
+/// A glob, or a regular expression compiled when the configuration is loaded.
 #[derive(Debug, Deserialize, Clone)]
-#[serde(tag = "type")]
+#[serde(try_from = "String")]
+pub enum ModelPattern {
+    Glob(String),
+    Regex(Regex),
+}
+
+impl ModelPattern {
+    pub fn matches(&self, model: &str) -> bool {
+        match self {
+            ModelPattern::Glob(pattern) => glob_match(pattern, model),
+            ModelPattern::Regex(regex) => regex.is_match(model),
+        }
+    }
+}
+
+impl PartialEq for ModelPattern {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (ModelPattern::Glob(a), ModelPattern::Glob(b)) => a == b,
+            (ModelPattern::Regex(a), ModelPattern::Regex(b)) => a.as_str() == b.as_str(),
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for ModelPattern {
+    type Err = String;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        match pattern
+            .strip_prefix('/')
+            .and_then(|rest| rest.strip_suffix('/'))
+        {
+            Some(regex) => Regex::new(regex)
+                .map(ModelPattern::Regex)
+                .map_err(|err| format!("invalid regular expression '{}': {}", pattern, err)),
+            None => Ok(ModelPattern::Glob(pattern.to_string())),
+        }
+    }
+}
+
+impl TryFrom<String> for ModelPattern {
+    type Error = String;
+
+    fn try_from(pattern: String) -> Result<Self, Self::Error> {
+        pattern.parse()
+    }
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+/// Upstream protocols a backend can speak.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BackendKind {
+    /// OpenAI-compatible servers such as vLLM or llama.cpp.
+    #[default]
+    #[serde(rename = "openai")]
+    OpenAi,
+    /// Ollama's native `/api` endpoints.
+    #[serde(rename = "ollama")]
+    Ollama,
+    /// The Anthropic Messages API.
+    #[serde(rename = "anthropic")]
+    Anthropic,
+    /// The Gemini API (`generateContent`).
+    #[serde(rename = "gemini")]
+    Gemini,
+    /// Hugging Face Text Generation Inference.
+    #[serde(rename = "tgi")]
+    Tgi,
+    /// Azure OpenAI, addressed by deployment.
+    #[serde(rename = "azure")]
+    Azure,
+}
+
+/// Which headers pass between clients and a backend. Hop-by-hop headers are
+/// always dropped in both directions.
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+pub struct HeaderPolicy {
+    /// Forward the client's `Authorization`, `x-api-key` and `Cookie`
+    /// headers. Off by default so router keys do not reach backends.
+    #[serde(default)]
+    pub forward_client_credentials: bool,
+    #[serde(default)]
+    pub request: HeaderRules,
+    #[serde(default)]
+    pub response: HeaderRules,
+}
+
+/// Header rules for one direction, applied in field order.
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+pub struct HeaderRules {
+    /// Only forward these headers. Empty forwards everything.
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Never forward these headers.
+    #[serde(default)]
+    pub deny: Vec<String>,
+    #[serde(default)]
+    pub remove: Vec<String>,
+    /// Headers to set, replacing any existing values.
+    #[serde(default)]
+    pub set: HashMap<String, String>,
+    /// Headers to append alongside any existing values.
+    #[serde(default)]
+    pub add: HashMap<String, String>,
+}
+
+impl HeaderRules {
+    fn validate(&self, field: &str, issue: &mut impl FnMut(String, String)) {
+        let lists = [
+            ("allow", &self.allow),
+            ("deny", &self.deny),
+            ("remove", &self.remove),
+        ];
+        for (list, names) in lists {
+            for name in names {
+                if HeaderName::from_bytes(name.as_bytes()).is_err() {
+                    issue(
+                        format!("{}.{}", field, list),
+                        format!("invalid header name '{}'", name),
+                    );
+                }
+            }
+        }
+        for (map, pairs) in [("set", &self.set), ("add", &self.add)] {
+            for (name, value) in pairs {
+                if HeaderName::from_bytes(name.as_bytes()).is_err() {
+                    issue(
+                        format!("{}.{}", field, map),
+                        format!("invalid header name '{}'", name),
+                    );
+                } else if HeaderValue::from_str(value).is_err() {
+                    issue(
+                        format!("{}.{}.{}", field, map, name),
+                        "contains characters not allowed in a header value".into(),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Per-backend timeouts, in seconds. Unset values mean no limit.
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+pub struct TimeoutConfig {
+    /// Establishing the TCP/TLS connection.
+    pub connect: Option<u64>,
+    /// The whole exchange, including streaming the response body.
+    pub request: Option<u64>,
+    /// Until the backend sends response headers.
+    pub first_byte: Option<u64>,
+    /// Maximum gap between two chunks of a response body.
+    pub idle: Option<u64>,
+    /// Fetching the model list, overriding the router-wide `discovery_timeout`.
+    pub discovery: Option<u64>,
+}
+
+impl TimeoutConfig {
+    pub fn connect(&self) -> Option<Duration> {
+        self.connect.map(Duration::from_secs)
+    }
+
+    pub fn request(&self) -> Option<Duration> {
+        self.request.map(Duration::from_secs)
+    }
+
+    pub fn first_byte(&self) -> Option<Duration> {
+        self.first_byte.map(Duration::from_secs)
+    }
+
+    pub fn idle(&self) -> Option<Duration> {
+        self.idle.map(Duration::from_secs)
+    }
+}
+
+/// How requests are spread over backends serving the same model.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Strategy {
+    #[default]
+    RoundRobin,
+    Weighted,
+    Random,
+    LeastOutstanding,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct LoadBalancingConfig {
+    /// Router-wide default strategy.
+    #[serde(default)]
+    pub strategy: Strategy,
+    /// Per-model overrides, keyed by model id.
+    #[serde(default)]
+    pub models: HashMap<String, Strategy>,
+}
+
+impl LoadBalancingConfig {
+    pub fn strategy_for(&self, model: &str) -> Strategy {
+        self.models.get(model).copied().unwrap_or(self.strategy)
+    }
+}
+
+/// API keys clients must present to use the router. Authentication is
+/// enabled as soon as at least one key is configured.
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+pub struct ClientAuthConfig {
+    #[serde(default)]
+    pub keys: Vec<ClientKeyConfig>,
+    /// Hex-encoded SHA-256 digest of the key of the `/admin` endpoints, which
+    /// are disabled while it is unset.
+    pub admin_key_sha256: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct ClientKeyConfig {
+    /// Identifies the key in logs; never sent to clients.
+    pub name: String,
+    /// Hex-encoded SHA-256 digest of the key, so the key itself is not
+    /// stored in the configuration.
+    pub key_sha256: String,
+    /// Model ids this key may use. `*` matches any run of characters and `?`
+    /// any single character.
+    #[serde(default = "default_allowed_models")]
+    pub models: Vec<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Limits on the traffic of this key, across all models.
+    pub rate_limit: Option<RateLimit>,
+}
+
+impl Default for ClientKeyConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            key_sha256: String::new(),
+            models: default_allowed_models(),
+            enabled: true,
+            rate_limit: None,
+        }
+    }
+}
+
+fn is_sha256_digest(digest: &str) -> bool {
+    digest.len() == 64 && digest.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn default_allowed_models() -> Vec<String> {
+    vec!["*".to_string()]
+}
+
+/// Limits on requests and tokens. Unset values mean no limit.
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+pub struct RateLimit {
+    pub requests_per_minute: Option<u32>,
+    /// Prompt and completion tokens per minute. Requests are charged an
+    /// estimate up front, corrected once the backend reports its `usage`.
+    /// Only supported for keys and models.
+    pub tokens_per_minute: Option<u32>,
+    /// Requests in flight at the same time, including open streams.
+    pub max_concurrent: Option<u32>,
+}
+
+impl RateLimit {
+    fn validate(&self, field: &str, issue: &mut impl FnMut(String, String)) {
+        let values = [
+            ("requests_per_minute", self.requests_per_minute),
+            ("tokens_per_minute", self.tokens_per_minute),
+            ("max_concurrent", self.max_concurrent),
+        ];
+        for (name, value) in values {
+            if value == Some(0) {
+                issue(
+                    format!("{}.{}", field, name),
+                    "must be greater than 0; omit it for no limit".into(),
+                );
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+pub struct RateLimitsConfig {
+    /// Limits per model id, shared by all clients.
+    #[serde(default)]
+    pub models: HashMap<String, RateLimit>,
+}
+
+/// Process-level settings. Command-line flags and their environment
+/// variables take precedence; changes only apply after a restart.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct ServerConfig {
+    /// Addresses to accept connections on. Defaults to `0.0.0.0:8080`.
+    #[serde(default)]
+    pub listen: Vec<ListenAddr>,
+    /// A `tracing` filter directive such as `info` or `llm_router=debug`.
+    pub log_level: Option<String>,
+    pub log_format: Option<LogFormat>,
+    /// Whether to write the access log. It is always JSON and not affected
+    /// by `log_level` or `log_format`.
+    #[serde(default = "default_true")]
+    pub access_log: bool,
+    #[serde(default)]
+    pub tracing: TracingConfig,
+    /// Request/response capture; disabled unless configured.
+    pub recorder: Option<RecorderConfig>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            listen: Vec::new(),
+            log_level: None,
+            log_format: None,
+            access_log: true,
+            tracing: TracingConfig::default(),
+            recorder: None,
+        }
+    }
+}
+
+/// Appends sampled requests and the responses they got to a JSONL file.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct RecorderConfig {
+    pub path: PathBuf,
+    /// Fraction of requests recorded, from `0.0` to `1.0`.
+    #[serde(default = "default_sample_rate")]
+    pub sample_rate: f64,
+    /// Size after which the file is rotated to `<path>.1`. Unset means the
+    /// file grows without limit.
+    pub max_bytes: Option<u64>,
+    /// Rotated files kept besides the current one.
+    #[serde(default = "default_max_files")]
+    pub max_files: u32,
+    /// Fields replaced with `"[REDACTED]"`, as dotted paths into the record
+    /// where `*` matches every array element or object value, e.g.
+    /// `request.messages.*.content`.
+    #[serde(default)]
+    pub redact: Vec<String>,
+}
+
+fn default_sample_rate() -> f64 {
+    1.0
+}
+
+fn default_max_files() -> u32 {
+    5
+}
+
+/// OpenTelemetry trace export. Nothing is exported unless `endpoint` is set.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct TracingConfig {
+    /// OTLP collector endpoint, e.g. `http://otel-collector:4317` for gRPC
+    /// or `http://otel-collector:4318` for HTTP.
+    pub endpoint: Option<String>,
+    #[serde(default)]
+    pub protocol: OtlpProtocol,
+    /// `service.name` resource attribute of exported spans.
+    #[serde(default = "default_service_name")]
+    pub service_name: String,
+}
+
+impl Default for TracingConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            protocol: OtlpProtocol::default(),
+            service_name: default_service_name(),
+        }
+    }
+}
+
+fn default_service_name() -> String {
+    "llm-router".to_string()
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OtlpProtocol {
+    /// OTLP over gRPC.
+    #[default]
+    Grpc,
+    /// OTLP over HTTP with protobuf payloads.
+    Http,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    /// Human-readable lines.
+    #[default]
+    Text,
+    /// One JSON object per line.
+    Json,
+}
+
+/// Controls re-dispatching a failed request to another backend serving the
+/// same model.
+#[derive(Debug, Deserialize, Clone)]
+pub struct RetryConfig {
+    /// Total number of attempts, including the first one.
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled on every further attempt.
+    #[serde(default = "default_backoff_ms")]
+    pub backoff_ms: u64,
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    /// Upstream status codes that trigger a retry.
+    #[serde(default = "default_retry_on_status")]
+    pub retry_on_status: Vec<u16>,
+    /// Whether failures to connect to a backend trigger a retry.
+    #[serde(default = "default_true")]
+    pub retry_on_connect_error: bool,
+    /// Whether tripped backend timeouts trigger a retry.
+    #[serde(default = "default_true")]
+    pub retry_on_timeout: bool,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_max_attempts(),
+            backoff_ms: default_backoff_ms(),
+            max_backoff_ms: default_max_backoff_ms(),
+            retry_on_status: default_retry_on_status(),
+            retry_on_connect_error: default_true(),
+            retry_on_timeout: default_true(),
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Delay to wait before attempt number `attempt` (1-based).
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u64 << attempt.saturating_sub(2).min(16);
+        Duration::from_millis(
+            self.backoff_ms
+                .saturating_mul(factor)
+                .min(self.max_backoff_ms),
+        )
+    }
+}
+
+fn default_max_attempts() -> u32 {
+    1
+}
+
+fn default_backoff_ms() -> u64 {
+    100
+}
+
+fn default_max_backoff_ms() -> u64 {
+    2000
+}
+
+fn default_retry_on_status() -> Vec<u16> {
+    vec![502, 503, 504]
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Credentials the router sends to a backend.
+///
+/// Secret values may reference environment variables as `${VAR}` (or
+/// `${VAR:-default}`) and can be read from files with `token_file`,
+/// `password_file` or `value_file`, e.g. mounted Kubernetes secrets.
+#[derive(Deserialize, Clone, PartialEq)]
+#[serde(try_from = "RawAuthConfig")]
 pub enum AuthConfig {
-    #[serde(rename = "bearer")]
     Bearer { token: String },
-    #[serde(rename = "basic")]
     Basic { username: String, password: String },
-    #[serde(rename = "header")]
     CustomHeader { name: String, value: String },
 }
 
-pub fn try_load_config<P: AsRef<Path>>(path: P) -> Option<Config> {
-    let path = path.as_ref();
+impl fmt::Debug for AuthConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthConfig::Bearer { .. } => f.debug_struct("Bearer").finish_non_exhaustive(),
+            AuthConfig::Basic { username, .. } => f
+                .debug_struct("Basic")
+                .field("username", username)
+                .finish_non_exhaustive(),
+            AuthConfig::CustomHeader { name, .. } => f
+                .debug_struct("CustomHeader")
+                .field("name", name)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum RawAuthConfig {
+    #[serde(rename = "bearer")]
+    Bearer {
+        token: Option<String>,
+        token_file: Option<String>,
+    },
+    #[serde(rename = "basic")]
+    Basic {
+        username: String,
+        password: Option<String>,
+        password_file: Option<String>,
+    },
+    #[serde(rename = "header")]
+    CustomHeader {
+        name: String,
+        value: Option<String>,
+        value_file: Option<String>,
+    },
+}
 
-    if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
-        if extension != "yml" && extension != "yaml" {
-            return None;
+impl TryFrom<RawAuthConfig> for AuthConfig {
+    type Error = String;
+
+    fn try_from(raw: RawAuthConfig) -> Result<Self, Self::Error> {
+        Ok(match raw {
+            RawAuthConfig::Bearer { token, token_file } => AuthConfig::Bearer {
+                token: header_safe("token", resolve_secret("token", token, token_file)?)?,
+            },
+            RawAuthConfig::Basic {
+                username,
+                password,
+                password_file,
+            } => {
+                let username = header_safe("username", interpolate_env(&username)?)?;
+                if username.contains(':') {
+                    return Err("`username` must not contain ':'".to_string());
+                }
+                AuthConfig::Basic {
+                    username,
+                    password: header_safe(
+                        "password",
+                        resolve_secret("password", password, password_file)?,
+                    )?,
+                }
+            }
+            RawAuthConfig::CustomHeader {
+                name,
+                value,
+                value_file,
+            } => AuthConfig::CustomHeader {
+                name,
+                value: resolve_secret("value", value, value_file)?,
+            },
+        })
+    }
+}
+
+fn resolve_secret(
+    field: &str,
+    value: Option<String>,
+    file: Option<String>,
+) -> Result<String, String> {
+    match (value, file) {
+        (Some(value), None) => interpolate_env(&value),
+        (None, Some(file)) => {
+            let path = interpolate_env(&file)?;
+            let contents = fs::read_to_string(&path)
+                .map_err(|err| format!("cannot read {}_file '{}': {}", field, path, err))?;
+            Ok(contents.trim_end_matches(['\r', '\n']).to_string())
         }
+        (Some(_), Some(_)) => Err(format!(
+            "only one of `{}` and `{}_file` may be set",
+            field, field
+        )),
+        (None, None) => Err(format!("missing field `{}` or `{}_file`", field, field)),
     }
+}
 
-    fs::read_to_string(path)
-        .ok()
-        .and_then(|contents| serde_yml::from_str(&contents).ok())
+/// Rejects a resolved credential that could not be sent in a header, such as
+/// one with an embedded newline.
+fn header_safe(field: &str, secret: String) -> Result<String, String> {
+    match HeaderValue::from_str(&secret) {
+        Ok(_) => Ok(secret),
+        Err(_) => Err(format!(
+            "`{}` contains characters not allowed in a header value",
+            field
+        )),
+    }
 }
 
-pub fn load_config(filename: &str) -> Config {
-    if let Some(config) = try_load_config(filename) {
-        return config;
+/// Replaces `${VAR}` and `${VAR:-default}` with values from the environment.
+/// `$$` produces a literal `$`.
+pub fn interpolate_env(input: &str) -> Result<String, String> {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find('$') {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+
+        if let Some(escaped) = after.strip_prefix('$') {
+            output.push('$');
+            rest = escaped;
+        } else if let Some(reference) = after.strip_prefix('{') {
+            let end = reference
+                .find('}')
+                .ok_or_else(|| format!("unterminated variable reference in '{}'", input))?;
+            let (name, default) = match reference[..end].split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (&reference[..end], None),
+            };
+            match (std::env::var(name), default) {
+                (Ok(value), _) => output.push_str(&value),
+                (Err(_), Some(default)) => output.push_str(default),
+                (Err(_), None) => {
+                    return Err(format!("environment variable {} is not set", name));
+                }
+            }
+            rest = &reference[end + 1..];
+        } else {
+            output.push('$');
+            rest = after;
+        }
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+/// Why a configuration file could not be loaded.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// No configuration file exists at the path or its fallbacks.
+    NotFound { path: PathBuf },
+    /// The file is not a `.yml` or `.yaml` file.
+    UnsupportedExtension { path: PathBuf },
+    /// The file exists but cannot be read.
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// The file is not valid YAML or does not match the configuration schema.
+    Parse {
+        path: PathBuf,
+        line: Option<usize>,
+        column: Option<usize>,
+        message: String,
+    },
+    /// The file parsed but failed [`Config::validate`].
+    Invalid {
+        path: PathBuf,
+        issues: Vec<ValidationIssue>,
+    },
+}
+
+impl ConfigError {
+    fn parse(path: &Path, err: serde_yml::Error) -> Self {
+        let location = err.location();
+        let message = err.to_string();
+        // The location is reported separately, so drop serde_yml's suffix.
+        let message = match message.rfind(" at line ") {
+            Some(index) if location.is_some() => message[..index].to_string(),
+            _ => message,
+        };
+        // Field paths come back as e.g. `backends.\[1\]`; print `backends[1]`.
+        let message = message
+            .replace(".\\[", "[")
+            .replace("\\[", "[")
+            .replace("\\]", "]");
+        ConfigError::Parse {
+            path: path.to_path_buf(),
+            line: location.as_ref().map(|l| l.line()),
+            column: location.as_ref().map(|l| l.column()),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::NotFound { path } => write!(
+                f,
+                "Configuration file not found. Check if config.yml or config.yaml file exists ({})",
+                path.display()
+            ),
+            ConfigError::UnsupportedExtension { path } => write!(
+                f,
+                "{}: configuration files must have a .yml or .yaml extension",
+                path.display()
+            ),
+            ConfigError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            ConfigError::Parse {
+                path,
+                line,
+                column,
+                message,
+            } => match (line, column) {
+                (Some(line), Some(column)) => {
+                    write!(f, "{}:{}:{}: {}", path.display(), line, column, message)
+                }
+                _ => write!(f, "{}: {}", path.display(), message),
+            },
+            ConfigError::Invalid { path, issues } => {
+                write!(f, "{}: invalid configuration", path.display())?;
+                for issue in issues {
+                    write!(f, "\n  {}", issue)?;
+                }
+                Ok(())
+            }
+        }
     }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Loads and validates the configuration file at `path`.
+pub fn try_load_config<P: AsRef<Path>>(path: P) -> Result<Config, ConfigError> {
+    let path = path.as_ref();
 
-    let extensions = ["yml", "yaml"];
+    if let Some(extension) = path.extension().and_then(|e| e.to_str())
+        && extension != "yml"
+        && extension != "yaml"
+    {
+        return Err(ConfigError::UnsupportedExtension {
+            path: path.to_path_buf(),
+        });
+    }
 
-    if let Some(stem) = Path::new(filename).file_stem().and_then(|s| s.to_str()) {
-        for ext in extensions {
-            let path = format!("{}.{}", stem, ext);
-            if let Some(config) = try_load_config(&path) {
-                return config;
+    let contents = fs::read_to_string(path).map_err(|source| {
+        if source.kind() == std::io::ErrorKind::NotFound {
+            ConfigError::NotFound {
+                path: path.to_path_buf(),
+            }
+        } else {
+            ConfigError::Io {
+                path: path.to_path_buf(),
+                source,
+            }
+        }
+    })?;
+    let config: Config =
+        serde_yml::from_str(&contents).map_err(|err| ConfigError::parse(path, err))?;
+    config.validate().map_err(|issues| ConfigError::Invalid {
+        path: path.to_path_buf(),
+        issues,
+    })?;
+    Ok(config)
+}
+
+/// Loads `filename`, falling back to the same name with a `.yml` or `.yaml`
+/// extension when it does not exist. Returns the path that was loaded, which
+/// later reloads must read.
+pub fn load_config<P: AsRef<Path>>(filename: P) -> Result<(Config, PathBuf), ConfigError> {
+    let path = filename.as_ref();
+    let err = match try_load_config(path) {
+        Ok(config) => return Ok((config, path.to_path_buf())),
+        Err(err @ (ConfigError::NotFound { .. } | ConfigError::UnsupportedExtension { .. })) => err,
+        Err(err) => return Err(err),
+    };
+
+    if path.file_stem().is_some() {
+        for ext in ["yml", "yaml"] {
+            let candidate = path.with_extension(ext);
+            if candidate == path {
+                continue;
+            }
+            match try_load_config(&candidate) {
+                Err(ConfigError::NotFound { .. }) => {}
+                result => return result.map(|config| (config, candidate)),
             }
         }
     }
 
-    panic!("Configuration file not found. Check if config.yml or config.yaml file exists");
+    Err(match err {
+        ConfigError::UnsupportedExtension { .. } => err,
+        _ => ConfigError::NotFound {
+            path: path.to_path_buf(),
+        },
+    })
 }
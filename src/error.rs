@@ -0,0 +1,191 @@
+use axum::{
+    Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use std::fmt;
+
+/// Errors returned to clients in the OpenAI error format:
+/// `{"error": {"message", "type", "param", "code"}}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouterError {
+    /// The request body is not valid JSON.
+    InvalidJson(String),
+    /// The request body has no `model` field.
+    MissingModel,
+    /// No backend serves the requested model.
+    ModelNotFound(String),
+    /// The backends serving the model cannot serve the endpoint.
+    UnsupportedEndpoint(String),
+    /// The backend could not be reached or returned an unusable response.
+    Upstream(String),
+    /// A backend timeout tripped.
+    Timeout(String),
+    /// The configuration file could not be reloaded; the message lists the
+    /// problems found in it.
+    InvalidConfig(String),
+    /// Client authentication is enabled and the request carries no API key.
+    MissingApiKey,
+    /// The API key is unknown or disabled.
+    InvalidApiKey,
+    /// The API key may not use the requested model.
+    ModelNotAllowed(String),
+    /// No admin key is configured, so the admin endpoints are disabled.
+    AdminDisabled,
+    /// A requests-per-minute or concurrency limit rejected the request.
+    RateLimited(String),
+    /// A tokens-per-minute limit rejected the request.
+    TokenRateLimited(String),
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErrorBody {
+    pub error: ErrorDetail,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErrorDetail {
+    pub message: String,
+    #[serde(rename = "type")]
+    pub error_type: &'static str,
+    pub param: Option<&'static str>,
+    pub code: &'static str,
+}
+
+impl RouterError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            RouterError::InvalidJson(_)
+            | RouterError::MissingModel
+            | RouterError::UnsupportedEndpoint(_) => StatusCode::BAD_REQUEST,
+            RouterError::MissingApiKey | RouterError::InvalidApiKey => StatusCode::UNAUTHORIZED,
+            RouterError::ModelNotAllowed(_) | RouterError::AdminDisabled => StatusCode::FORBIDDEN,
+            RouterError::ModelNotFound(_) => StatusCode::NOT_FOUND,
+            RouterError::RateLimited(_) | RouterError::TokenRateLimited(_) => {
+                StatusCode::TOO_MANY_REQUESTS
+            }
+            RouterError::Upstream(_) => StatusCode::BAD_GATEWAY,
+            RouterError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            RouterError::InvalidConfig(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn error_type(&self) -> &'static str {
+        match self {
+            RouterError::InvalidJson(_)
+            | RouterError::MissingModel
+            | RouterError::ModelNotFound(_)
+            | RouterError::UnsupportedEndpoint(_)
+            | RouterError::MissingApiKey
+            | RouterError::InvalidApiKey => "invalid_request_error",
+            RouterError::ModelNotAllowed(_) | RouterError::AdminDisabled => "permission_error",
+            RouterError::RateLimited(_) => "requests",
+            RouterError::TokenRateLimited(_) => "tokens",
+            RouterError::Upstream(_) | RouterError::Timeout(_) => "api_error",
+            RouterError::InvalidConfig(_) => "server_error",
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            RouterError::InvalidJson(_) => "invalid_json",
+            RouterError::MissingModel => "missing_model",
+            RouterError::ModelNotFound(_) => "model_not_found",
+            RouterError::UnsupportedEndpoint(_) => "unsupported_endpoint",
+            RouterError::Upstream(_) => "upstream_error",
+            RouterError::Timeout(_) => "timeout",
+            RouterError::InvalidConfig(_) => "invalid_config",
+            RouterError::MissingApiKey => "missing_api_key",
+            RouterError::InvalidApiKey => "invalid_api_key",
+            RouterError::ModelNotAllowed(_) => "model_not_allowed",
+            RouterError::AdminDisabled => "admin_disabled",
+            RouterError::RateLimited(_) | RouterError::TokenRateLimited(_) => "rate_limit_exceeded",
+        }
+    }
+
+    pub fn param(&self) -> Option<&'static str> {
+        match self {
+            RouterError::MissingModel
+            | RouterError::ModelNotFound(_)
+            | RouterError::UnsupportedEndpoint(_)
+            | RouterError::ModelNotAllowed(_) => Some("model"),
+            _ => None,
+        }
+    }
+
+    pub fn body(&self) -> ErrorBody {
+        ErrorBody {
+            error: ErrorDetail {
+                message: self.to_string(),
+                error_type: self.error_type(),
+                param: self.param(),
+                code: self.code(),
+            },
+        }
+    }
+
+    /// The error as a single SSE `data:` event, for failures that happen
+    /// after a streaming response has started.
+    pub fn to_sse_event(&self) -> String {
+        format!(
+            "data: {}\n\n",
+            serde_json::to_string(&self.body()).unwrap_or_default()
+        )
+    }
+}
+
+impl fmt::Display for RouterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RouterError::InvalidJson(err) => {
+                write!(f, "Could not parse the request body as JSON: {}", err)
+            }
+            RouterError::MissingModel => write!(f, "You must provide a model parameter."),
+            RouterError::ModelNotFound(model) => {
+                write!(f, "The model `{}` does not exist.", model)
+            }
+            RouterError::UnsupportedEndpoint(model) => {
+                write!(f, "The model `{}` does not support this endpoint.", model)
+            }
+            RouterError::MissingApiKey => write!(
+                f,
+                "You didn't provide an API key. Pass it as `Authorization: Bearer <key>`."
+            ),
+            RouterError::InvalidApiKey => write!(f, "Incorrect API key provided."),
+            RouterError::ModelNotAllowed(model) => {
+                write!(
+                    f,
+                    "This API key is not allowed to use the model `{}`.",
+                    model
+                )
+            }
+            RouterError::AdminDisabled => write!(
+                f,
+                "The admin API is disabled. Set `client_auth.admin_key_sha256` to enable it."
+            ),
+            RouterError::Upstream(message)
+            | RouterError::Timeout(message)
+            | RouterError::InvalidConfig(message)
+            | RouterError::RateLimited(message)
+            | RouterError::TokenRateLimited(message) => {
+                write!(f, "{}", message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RouterError {}
+
+/// Response extension carrying the [`RouterError::code`] of an error
+/// response, so it can be counted after the fact.
+#[derive(Debug, Clone, Copy)]
+pub struct ErrorCode(pub &'static str);
+
+impl IntoResponse for RouterError {
+    fn into_response(self) -> Response {
+        let mut response = (self.status(), Json(self.body())).into_response();
+        response.extensions_mut().insert(ErrorCode(self.code()));
+        response
+    }
+}
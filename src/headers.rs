@@ -0,0 +1,110 @@
+use crate::config::{BackendConfig, HeaderRules};
+use axum::http::{HeaderMap, HeaderName, HeaderValue, header};
+
+/// Connection-specific headers that must not be forwarded by a proxy
+/// (RFC 9110, section 7.6.1), plus `Host` and `Content-Length`, which the
+/// HTTP client sets for the outgoing message.
+const HOP_BY_HOP: [HeaderName; 11] = [
+    header::CONNECTION,
+    HeaderName::from_static("keep-alive"),
+    HeaderName::from_static("proxy-connection"),
+    header::PROXY_AUTHENTICATE,
+    header::PROXY_AUTHORIZATION,
+    header::TE,
+    header::TRAILER,
+    header::TRANSFER_ENCODING,
+    header::UPGRADE,
+    header::HOST,
+    header::CONTENT_LENGTH,
+];
+
+/// Headers that carry the client's own credentials for the router.
+const CLIENT_CREDENTIALS: [HeaderName; 3] = [
+    header::AUTHORIZATION,
+    HeaderName::from_static("x-api-key"),
+    header::COOKIE,
+];
+
+/// Headers to send to `backend` for a client request, before the backend's
+/// own `auth` is applied. `Accept-Encoding` is always dropped: the router
+/// reads every response for its usage, so it must not come back compressed.
+pub fn request_headers(inbound: &HeaderMap, backend: &BackendConfig) -> HeaderMap {
+    let policy = &backend.headers;
+    let mut headers = strip_hop_by_hop(inbound);
+    if !policy.forward_client_credentials {
+        for name in &CLIENT_CREDENTIALS {
+            headers.remove(name);
+        }
+    }
+    apply_rules(&mut headers, &policy.request);
+    headers.remove(header::ACCEPT_ENCODING);
+    headers
+}
+
+/// Headers to return to the client for a response from `backend`.
+pub fn response_headers(upstream: &HeaderMap, backend: &BackendConfig) -> HeaderMap {
+    let mut headers = strip_hop_by_hop(upstream);
+    apply_rules(&mut headers, &backend.headers.response);
+    headers
+}
+
+fn strip_hop_by_hop(source: &HeaderMap) -> HeaderMap {
+    // `Connection` may name further headers that only apply to this hop.
+    let listed: Vec<String> = source
+        .get_all(header::CONNECTION)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|name| name.trim().to_ascii_lowercase())
+        .collect();
+
+    let mut headers = source.clone();
+    for name in &HOP_BY_HOP {
+        headers.remove(name);
+    }
+    for name in listed {
+        headers.remove(name.as_str());
+    }
+    headers
+}
+
+/// Applies `allow`/`deny`, then `remove`, `set` and `add`, in that order.
+fn apply_rules(headers: &mut HeaderMap, rules: &HeaderRules) {
+    if !rules.allow.is_empty() {
+        let allowed: Vec<HeaderName> = parse_names(&rules.allow).collect();
+        let names: Vec<HeaderName> = headers.keys().cloned().collect();
+        for name in names {
+            if !allowed.contains(&name) {
+                headers.remove(&name);
+            }
+        }
+    }
+    for name in parse_names(&rules.deny).chain(parse_names(&rules.remove)) {
+        headers.remove(&name);
+    }
+    for (name, value) in parse_pairs(&rules.set) {
+        headers.insert(name, value);
+    }
+    for (name, value) in parse_pairs(&rules.add) {
+        headers.append(name, value);
+    }
+}
+
+// Names and values are checked by `Config::validate`, so invalid ones can
+// only come from configurations built in code; they are skipped.
+fn parse_names(names: &[String]) -> impl Iterator<Item = HeaderName> + '_ {
+    names
+        .iter()
+        .filter_map(|name| HeaderName::from_bytes(name.as_bytes()).ok())
+}
+
+fn parse_pairs<'a>(
+    pairs: impl IntoIterator<Item = (&'a String, &'a String)>,
+) -> impl Iterator<Item = (HeaderName, HeaderValue)> {
+    pairs.into_iter().filter_map(|(name, value)| {
+        Some((
+            HeaderName::from_bytes(name.as_bytes()).ok()?,
+            HeaderValue::from_str(value).ok()?,
+        ))
+    })
+}
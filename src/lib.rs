@@ -1,7 +1,26 @@
+pub mod access_log;
+pub mod adapter;
+pub mod admin;
+pub mod alias;
+pub mod anthropic;
+pub mod auth;
+pub mod balancer;
+pub mod cli;
 pub mod config;
+pub mod error;
+pub mod headers;
+pub mod listen;
+pub mod metrics;
 pub mod model;
+pub mod ollama;
+pub mod ratelimit;
+pub mod recorder;
+pub mod reload;
 pub mod router;
+pub mod stream;
+pub mod telemetry;
 
-pub use config::{AuthConfig, BackendConfig, Config};
+pub use config::{AuthConfig, BackendConfig, Config, ConfigError, Strategy, ValidationIssue};
+pub use error::RouterError;
 pub use model::{AppState, ModelInfo};
-pub use router::{forward_completion, forward_request, healthz, list_models};
+pub use router::{forward_completion, forward_request, healthz, list_models, metrics};
@@ -0,0 +1,118 @@
+use axum::Router;
+use futures_util::future::try_join_all;
+use serde::Deserialize;
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use tracing::info;
+
+/// An address the router accepts connections on: `host:port` (IPv6 hosts in
+/// brackets, e.g. `[::]:8080`) or `unix:/path/to/socket`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum ListenAddr {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+}
+
+impl Default for ListenAddr {
+    fn default() -> Self {
+        ListenAddr::Tcp(SocketAddr::from(([0, 0, 0, 0], 8080)))
+    }
+}
+
+impl FromStr for ListenAddr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(path) = s.strip_prefix("unix:") {
+            if path.is_empty() {
+                return Err("missing socket path after `unix:`".to_string());
+            }
+            return Ok(ListenAddr::Unix(PathBuf::from(path)));
+        }
+        s.parse().map(ListenAddr::Tcp).map_err(|_| {
+            format!(
+                "invalid listen address '{}', expected host:port, [ipv6]:port or unix:/path",
+                s
+            )
+        })
+    }
+}
+
+impl TryFrom<String> for ListenAddr {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for ListenAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListenAddr::Tcp(addr) => write!(f, "{}", addr),
+            ListenAddr::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+/// Binds every address, then serves `app` on all of them until one fails.
+///
+/// Binding happens up front so a bad address aborts start-up before any
+/// listener accepts traffic. A stale Unix socket left behind by a previous
+/// run is replaced.
+pub async fn serve(app: Router, addrs: &[ListenAddr]) -> io::Result<()> {
+    let mut servers = Vec::new();
+    for addr in addrs {
+        let app = app.clone();
+        let server = match addr {
+            ListenAddr::Tcp(socket_addr) => {
+                let listener = tokio::net::TcpListener::bind(socket_addr)
+                    .await
+                    .map_err(|err| bind_error(addr, err))?;
+                tokio::spawn(async move { axum::serve(listener, app).await })
+            }
+            #[cfg(unix)]
+            ListenAddr::Unix(path) => {
+                remove_stale_socket(path)?;
+                let listener =
+                    tokio::net::UnixListener::bind(path).map_err(|err| bind_error(addr, err))?;
+                tokio::spawn(async move { axum::serve(listener, app).await })
+            }
+            #[cfg(not(unix))]
+            ListenAddr::Unix(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "Unix domain sockets are not supported on this platform",
+                ));
+            }
+        };
+        info!("Listening on {}", addr);
+        servers.push(server);
+    }
+
+    try_join_all(
+        servers
+            .into_iter()
+            .map(|server| async move { server.await.map_err(io::Error::other)? }),
+    )
+    .await?;
+    Ok(())
+}
+
+fn bind_error(addr: &ListenAddr, err: io::Error) -> io::Error {
+    io::Error::new(err.kind(), format!("cannot listen on {}: {}", addr, err))
+}
+
+#[cfg(unix)]
+fn remove_stale_socket(path: &std::path::Path) -> io::Result<()> {
+    use std::os::unix::fs::FileTypeExt;
+
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path),
+        _ => Ok(()),
+    }
+}
@@ -1,38 +1,175 @@
-mod config;
-mod model;
-mod router;
-
 use axum::{Router, routing::get, routing::post};
-use config::load_config;
-use model::{AppState, refresh_models_loop};
-use router::{forward_completion, forward_request, healthz, list_models, main_page};
-use std::net::SocketAddr;
-use tracing::info;
+use clap::Parser;
+use llm_router::access_log;
+use llm_router::admin::{list_backends, reload};
+use llm_router::anthropic;
+use llm_router::cli::{Cli, Command};
+use llm_router::config::{Config, LogFormat, load_config};
+use llm_router::listen::serve;
+use llm_router::model::{AppState, refresh_models, refresh_models_loop};
+use llm_router::ollama;
+#[cfg(unix)]
+use llm_router::reload::reload_on_sighup;
+use llm_router::reload::watch_config;
+use llm_router::router::{
+    forward_completion, forward_request, healthz, list_models, main_page, metrics,
+};
+use llm_router::telemetry::{self, init_tracer};
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use tracing::error;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::{EnvFilter, Layer, filter::LevelFilter, prelude::*};
 
 #[tokio::main]
-async fn main() {
-    tracing_subscriber::fmt::init();
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
 
-    let config = load_config("config.yml");
-    let state = AppState::new(config);
+    let (config, config_path) = match load_config(cli.config_path()) {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let command = cli.command();
+    let tracer = match init_logging(&cli, &config, command) {
+        Ok(tracer) => tracer,
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let code = match command {
+        Command::Serve => run_server(&cli, config, config_path).await,
+        Command::CheckConfig => {
+            println!(
+                "{}: configuration is valid ({} backends)",
+                config_path.display(),
+                config.backends.len()
+            );
+            ExitCode::SUCCESS
+        }
+        Command::ListModels => print_models(config).await,
+    };
+    if let Some(tracer) = tracer {
+        // Flushes spans still waiting in the batch.
+        let _ = tracer.shutdown();
+    }
+    code
+}
+
+/// Logs go to stdout while serving and to stderr for the one-shot commands,
+/// whose output on stdout is meant to be consumed. The access log has a layer
+/// of its own, so it stays JSON whatever the log format and level. Spans are exported over
+/// OTLP while serving if a collector is configured, regardless of the log
+/// level.
+fn init_logging(
+    cli: &Cli,
+    config: &Config,
+    command: Command,
+) -> Result<Option<SdkTracerProvider>, String> {
+    let (default_level, writer) = match command {
+        Command::Serve => ("info", BoxMakeWriter::new(std::io::stdout)),
+        _ => ("warn", BoxMakeWriter::new(std::io::stderr)),
+    };
+    let filter = match cli.log_level(config) {
+        Some(level) => EnvFilter::try_new(&level)
+            .map_err(|err| format!("invalid log level '{}': {}", level, err))?,
+        None => EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_level)),
+    };
+    let filter = access_log::exclude(filter);
+    let access_log = (command == Command::Serve && config.server.access_log)
+        .then(|| access_log::layer(std::io::stdout));
+
+    let fmt = tracing_subscriber::fmt::layer().with_writer(writer);
+    let fmt = match cli.log_format(config) {
+        LogFormat::Text => fmt.boxed(),
+        LogFormat::Json => fmt.json().boxed(),
+    };
+
+    let tracer = match command {
+        Command::Serve => init_tracer(&config.server.tracing)?,
+        _ => None,
+    };
+    let otel = tracer
+        .as_ref()
+        .map(|tracer| telemetry::layer(tracer).with_filter(LevelFilter::INFO));
+
+    tracing_subscriber::registry()
+        .with(fmt.with_filter(filter))
+        .with(access_log)
+        .with(otel)
+        .init();
+    Ok(tracer)
+}
+
+async fn run_server(cli: &Cli, config: Config, config_path: PathBuf) -> ExitCode {
+    let addrs = cli.listen_addrs(&config);
+    let state = AppState::new(config).with_config_path(config_path);
+
+    // Discover models before accepting traffic so the first requests can be routed.
+    refresh_models(&state).await;
 
     let state_clone = state.clone();
     tokio::spawn(async move {
         refresh_models_loop(state_clone).await;
     });
+    tokio::spawn(watch_config(state.clone()));
+    #[cfg(unix)]
+    tokio::spawn(reload_on_sighup(state.clone()));
 
     let app = Router::new()
         .route("/v1/models", get(list_models))
         .route("/v1/chat/completions", post(forward_request))
         .route("/v1/completions", post(forward_completion))
+        .route("/v1/messages", post(anthropic::messages))
         .route("/healthz", get(healthz))
+        .route("/metrics", get(metrics))
+        .route("/api/tags", get(ollama::list_tags))
+        .route("/api/show", post(ollama::show))
+        .route("/api/chat", post(ollama::chat))
+        .route("/api/generate", post(ollama::generate))
+        .route("/api/version", get(ollama::version))
+        .route("/admin/backends", get(list_backends))
+        .route("/admin/reload", post(reload))
         .route("/", get(main_page))
         .with_state(state);
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], 8080));
-    info!("Starting server on {}", addr);
+    match serve(app, &addrs).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            error!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Prints one line per model with the backends serving it. Fails when any
+/// backend could not be queried.
+async fn print_models(config: Config) -> ExitCode {
+    let state = AppState::new(config);
+    refresh_models(&state).await;
+
+    let routing_table = state.routing_table.read().await;
+    for model in state.model_cache.read().await.iter() {
+        let backends = routing_table.get(&model.id).cloned().unwrap_or_default();
+        println!("{}\t{}", model.id, backends.join(","));
+    }
 
-    axum::serve(tokio::net::TcpListener::bind(addr).await.unwrap(), app)
-        .await
-        .unwrap();
+    let mut failed = false;
+    for (name, status) in state.backend_status.read().await.iter() {
+        if let Some(err) = &status.last_error {
+            eprintln!("{}: {}", name, err);
+            failed = true;
+        }
+    }
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
 }
@@ -0,0 +1,202 @@
+use crate::ratelimit::Usage;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use std::time::Duration;
+
+const LATENCY_BUCKETS: [f64; 12] = [
+    0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0,
+];
+const FIRST_TOKEN_BUCKETS: [f64; 12] = [
+    0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0,
+];
+
+/// Prometheus metrics of the router, served at `/metrics`.
+///
+/// Requests are labelled by model and by the name of the backend that
+/// served them; both are empty when a request fails before that is known.
+pub struct Metrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    request_duration: HistogramVec,
+    time_to_first_token: HistogramVec,
+    tokens: IntCounterVec,
+    upstream_responses: IntCounterVec,
+    errors: IntCounterVec,
+    in_flight: IntGaugeVec,
+    discoveries: IntCounterVec,
+    discovered_models: IntGaugeVec,
+    recorder_dropped: IntCounter,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        let registry = Registry::new_custom(Some("llm_router".to_string()), None).unwrap();
+        let counter = |name: &str, help: &str, labels: &[&str]| {
+            let counter = IntCounterVec::new(Opts::new(name, help), labels).unwrap();
+            registry.register(Box::new(counter.clone())).unwrap();
+            counter
+        };
+        let gauge = |name: &str, help: &str, labels: &[&str]| {
+            let gauge = IntGaugeVec::new(Opts::new(name, help), labels).unwrap();
+            registry.register(Box::new(gauge.clone())).unwrap();
+            gauge
+        };
+        let histogram = |name: &str, help: &str, buckets: &[f64]| {
+            let opts = HistogramOpts::new(name, help).buckets(buckets.to_vec());
+            let histogram = HistogramVec::new(opts, &["model", "backend"]).unwrap();
+            registry.register(Box::new(histogram.clone())).unwrap();
+            histogram
+        };
+
+        let recorder_dropped = IntCounter::new(
+            "recorder_dropped_total",
+            "Records the recorder dropped because its queue was full.",
+        )
+        .unwrap();
+        registry
+            .register(Box::new(recorder_dropped.clone()))
+            .unwrap();
+
+        Self {
+            requests: counter(
+                "requests_total",
+                "Client requests by response status.",
+                &["model", "backend", "status"],
+            ),
+            request_duration: histogram(
+                "request_duration_seconds",
+                "Time from receiving a request until its response body ends.",
+                &LATENCY_BUCKETS,
+            ),
+            time_to_first_token: histogram(
+                "time_to_first_token_seconds",
+                "Time from receiving a streaming request until its first event.",
+                &FIRST_TOKEN_BUCKETS,
+            ),
+            tokens: counter(
+                "tokens_total",
+                "Tokens reported in response usage, by direction (input or output).",
+                &["model", "backend", "direction"],
+            ),
+            upstream_responses: counter(
+                "upstream_responses_total",
+                "Backend attempts by HTTP status, or timeout, connect_error or error.",
+                &["model", "backend", "status"],
+            ),
+            errors: counter(
+                "errors_total",
+                "Error responses returned to clients, by error code.",
+                &["model", "backend", "kind"],
+            ),
+            in_flight: gauge(
+                "in_flight_requests",
+                "Requests currently being served by a backend.",
+                &["model", "backend"],
+            ),
+            discoveries: counter(
+                "discovery_total",
+                "Model discovery runs by result (success or failure).",
+                &["backend", "result"],
+            ),
+            discovered_models: gauge(
+                "discovered_models",
+                "Models currently routed to a backend.",
+                &["backend"],
+            ),
+            recorder_dropped,
+            registry,
+        }
+    }
+}
+
+impl Metrics {
+    /// All metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    /// Counts a response returned to a client, and its error code if it is
+    /// an error produced by the router.
+    pub fn response(&self, model: &str, backend: &str, status: &str, error: Option<&str>) {
+        self.requests
+            .with_label_values(&[model, backend, status])
+            .inc();
+        if let Some(code) = error {
+            self.errors.with_label_values(&[model, backend, code]).inc();
+        }
+    }
+
+    pub fn first_token(&self, model: &str, backend: &str, elapsed: Duration) {
+        self.time_to_first_token
+            .with_label_values(&[model, backend])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Records the duration and token usage of a request whose response has
+    /// been sent.
+    pub fn completed(&self, model: &str, backend: &str, duration: Duration, usage: Option<Usage>) {
+        self.request_duration
+            .with_label_values(&[model, backend])
+            .observe(duration.as_secs_f64());
+        if let Some(usage) = usage {
+            for (direction, tokens) in [
+                ("input", usage.prompt_tokens),
+                ("output", usage.completion_tokens),
+            ] {
+                self.tokens
+                    .with_label_values(&[model, backend, direction])
+                    .inc_by(tokens);
+            }
+        }
+    }
+
+    /// Counts one attempt against a backend, by status code or failure kind.
+    pub fn upstream_response(&self, model: &str, backend: &str, status: &str) {
+        self.upstream_responses
+            .with_label_values(&[model, backend, status])
+            .inc();
+    }
+
+    /// Counts a request as in flight to `backend` until the guard is dropped.
+    pub fn in_flight(&self, model: &str, backend: &str) -> InFlight {
+        let gauge = self.in_flight.with_label_values(&[model, backend]);
+        gauge.inc();
+        InFlight(gauge)
+    }
+
+    /// Records the outcome of discovering the models of `backend`, along
+    /// with the number of models it is routed for afterwards.
+    pub fn discovery(&self, backend: &str, success: bool, models: usize) {
+        let result = if success { "success" } else { "failure" };
+        self.discoveries.with_label_values(&[backend, result]).inc();
+        self.discovered_models
+            .with_label_values(&[backend])
+            .set(models as i64);
+    }
+
+    /// Drops the discovered-models gauge of a backend that was removed from
+    /// the configuration.
+    pub fn remove_backend(&self, backend: &str) {
+        let _ = self.discovered_models.remove_label_values(&[backend]);
+    }
+
+    /// Counts a record the recorder could not queue.
+    pub fn recorder_dropped(&self) {
+        self.recorder_dropped.inc();
+    }
+}
+
+/// Decrements the in-flight gauge of a backend when dropped.
+pub struct InFlight(IntGauge);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.dec();
+    }
+}
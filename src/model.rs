@@ -1,9 +1,23 @@
-use crate::config::Config;
+use crate::adapter;
+use crate::balancer::Balancer;
+use crate::config::{BackendConfig, Config};
+use crate::metrics::Metrics;
+use crate::ratelimit::RateLimiter;
+use crate::recorder::Recorder;
+use arc_swap::ArcSwap;
+use axum::http::HeaderMap;
+use futures_util::future::join_all;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, sync::Arc, time::Duration};
-use tokio::{sync::RwLock, time::interval};
-use tracing::{error, info};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::{Mutex, RwLock};
+use tracing::{Instrument, Span, error, field::Empty, info, info_span, warn};
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ModelInfo {
@@ -13,65 +27,289 @@ pub struct ModelInfo {
     pub owned_by: String,
 }
 
-#[derive(Debug, Deserialize)]
-struct ModelsResponse {
-    object: String,
-    data: Vec<ModelInfo>,
+/// Result of the most recent model discovery for one backend.
+#[derive(Debug, Clone, Default)]
+pub struct BackendStatus {
+    /// Last successfully discovered models, kept across failed refreshes.
+    pub models: Vec<ModelInfo>,
+    pub last_success: Option<Instant>,
+    pub last_error: Option<String>,
+}
+
+impl BackendStatus {
+    /// Time since the models were last refreshed successfully.
+    pub fn age(&self) -> Option<Duration> {
+        self.last_success.map(|at| at.elapsed())
+    }
+
+    /// Whether the models come from an earlier refresh than the latest one.
+    pub fn is_stale(&self) -> bool {
+        self.last_error.is_some() && !self.models.is_empty()
+    }
 }
 
 #[derive(Clone)]
 pub struct AppState {
-    pub config: Arc<Config>,
-    pub routing_table: Arc<RwLock<HashMap<String, String>>>,
+    /// Current configuration. Swapped atomically on reload; requests keep
+    /// the snapshot they started with.
+    pub config: Arc<ArcSwap<Config>>,
+    /// File the configuration was loaded from, used for reloads.
+    pub config_path: Option<Arc<PathBuf>>,
+    /// Model id -> names of every backend advertising it.
+    pub routing_table: Arc<RwLock<HashMap<String, Vec<String>>>>,
     pub model_cache: Arc<RwLock<Vec<ModelInfo>>>,
+    /// Backend name -> discovery state.
+    pub backend_status: Arc<RwLock<HashMap<String, BackendStatus>>>,
     pub client: Client,
+    /// Clients for backends that need their own connection settings.
+    pub backend_clients: Arc<ArcSwap<HashMap<String, Client>>>,
+    pub balancer: Arc<Balancer>,
+    pub rate_limiter: Arc<RateLimiter>,
+    pub metrics: Arc<Metrics>,
+    /// Writes request/response records, if enabled.
+    pub recorder: Option<Arc<Recorder>>,
+    /// Serializes configuration reloads.
+    pub reload_lock: Arc<Mutex<()>>,
 }
 
 impl AppState {
     pub fn new(config: Config) -> Self {
+        let backend_clients = build_backend_clients(&config, &HashMap::new(), &[]);
+        let metrics = Arc::new(Metrics::default());
+        let recorder = config
+            .server
+            .recorder
+            .clone()
+            .map(|recorder| Recorder::new(recorder, metrics.clone()));
+
         Self {
-            config: Arc::new(config),
+            config: Arc::new(ArcSwap::from_pointee(config)),
+            config_path: None,
             routing_table: Arc::new(RwLock::new(HashMap::new())),
             model_cache: Arc::new(RwLock::new(Vec::new())),
+            backend_status: Arc::new(RwLock::new(HashMap::new())),
             client: Client::new(),
+            backend_clients: Arc::new(ArcSwap::from_pointee(backend_clients)),
+            balancer: Arc::new(Balancer::default()),
+            rate_limiter: Arc::new(RateLimiter::default()),
+            metrics,
+            recorder: recorder.map(Arc::new),
+            reload_lock: Arc::new(Mutex::new(())),
         }
     }
+
+    pub fn with_config_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config_path = Some(Arc::new(path.into()));
+        self
+    }
+
+    pub fn client_for(&self, backend: &BackendConfig) -> Client {
+        self.backend_clients
+            .load()
+            .get(&backend.name)
+            .cloned()
+            .unwrap_or_else(|| self.client.clone())
+    }
 }
 
-pub async fn refresh_models_loop(state: AppState) {
-    let mut interval = interval(Duration::from_secs(state.config.refresh_interval));
+/// Builds clients for backends with custom connection settings, reusing the
+/// existing client (and its connection pool) of every backend not listed in
+/// `changed`.
+pub fn build_backend_clients(
+    config: &Config,
+    current: &HashMap<String, Client>,
+    changed: &[String],
+) -> HashMap<String, Client> {
+    config
+        .backends
+        .iter()
+        .filter_map(|backend| {
+            if !changed.contains(&backend.name)
+                && let Some(client) = current.get(&backend.name)
+            {
+                return Some((backend.name.clone(), client.clone()));
+            }
+            let connect_timeout = backend.timeouts.connect()?;
+            match Client::builder().connect_timeout(connect_timeout).build() {
+                Ok(client) => Some((backend.name.clone(), client)),
+                Err(err) => {
+                    error!(
+                        "Cannot build a client for backend {}, its connect timeout is not applied: {}",
+                        backend.name, err
+                    );
+                    None
+                }
+            }
+        })
+        .collect()
+}
 
+/// Periodically refreshes the routing table. The first refresh happens one
+/// interval after start; run [`refresh_models`] beforehand to serve traffic
+/// right away. The interval is re-read after every refresh so reloads apply.
+pub async fn refresh_models_loop(state: AppState) {
     loop {
-        interval.tick().await;
-        let mut model_cache = Vec::new();
-        let mut routing_table = HashMap::new();
-
-        for backend in &state.config.backends {
-            match state
-                .client
-                .get(format!("{}/v1/models", backend.url))
-                .send()
-                .await
-            {
-                Ok(resp) => match resp.json::<ModelsResponse>().await {
-                    Ok(models_response) => {
-                        for model in models_response.data {
-                            routing_table.insert(model.id.clone(), backend.url.clone());
-                            model_cache.push(model);
-                        }
-                    }
-                    Err(err) => error!("Failed to parse models from {}: {}", backend.name, err),
-                },
-                Err(err) => error!("Failed to reach backend {}: {}", backend.name, err),
+        let period = Duration::from_secs(state.config.load().refresh_interval);
+        tokio::time::sleep(period).await;
+        refresh_models(&state)
+            .instrument(info_span!("refresh_models"))
+            .await;
+    }
+}
+
+/// Re-discovers the models of every backend and rebuilds the routing table.
+///
+/// Backends are queried concurrently, each bounded by its discovery timeout.
+/// A backend whose discovery fails keeps serving its last known models until
+/// they are older than `max_staleness`.
+pub async fn refresh_models(state: &AppState) {
+    let config = state.config.load_full();
+    let names: Vec<String> = config.backends.iter().map(|b| b.name.clone()).collect();
+    refresh_backends(state, &names).await;
+}
+
+/// Re-discovers the models of the named backends only, keeping the cached
+/// models of all others, and rebuilds the routing table.
+pub async fn refresh_backends(state: &AppState, names: &[String]) {
+    let config = state.config.load_full();
+    let targets = config.backends.iter().filter(|b| names.contains(&b.name));
+    let config_ref = &config;
+    let results = join_all(targets.map(|backend| {
+        let span = info_span!(
+            "discover_models",
+            llm_router.backend = %backend.name,
+            otel.status_code = Empty,
+            otel.status_description = Empty,
+        );
+        async move {
+            let timeout = config_ref.discovery_timeout_for(backend);
+            let result = match tokio::time::timeout(timeout, fetch_models(state, backend)).await {
+                Ok(result) => result,
+                Err(_) => Err(format!(
+                    "discovery timed out after {}s",
+                    timeout.as_secs_f64()
+                )),
+            };
+            if let Err(err) = &result {
+                let span = Span::current();
+                span.record("otel.status_code", "ERROR");
+                span.record("otel.status_description", err.as_str());
             }
+            (backend, result)
         }
+        .instrument(span)
+    }))
+    .await;
 
-        *state.routing_table.write().await = routing_table;
-        let model_count = model_cache.len();
-        *state.model_cache.write().await = model_cache;
-        info!(
-            "Model routing table refreshed. {} models available.",
-            model_count
-        );
+    let mut statuses = state.backend_status.write().await;
+    statuses.retain(|name, _| config.backends.iter().any(|b| b.name == *name));
+
+    for (backend, result) in results {
+        let status = statuses.entry(backend.name.clone()).or_default();
+        let success = result.is_ok();
+        match result {
+            Ok(models) => {
+                status.models = models
+                    .into_iter()
+                    .filter(|model| backend.model_filter.allows(&model.id))
+                    .collect();
+                status.last_success = Some(Instant::now());
+                status.last_error = None;
+            }
+            Err(err) => {
+                error!("Failed to refresh models from {}: {}", backend.name, err);
+                status.last_error = Some(err);
+                expire_stale_models(&config, backend, status);
+            }
+        }
+        state
+            .metrics
+            .discovery(&backend.name, success, status.models.len());
+    }
+
+    let mut model_cache = Vec::new();
+    let mut routing_table: HashMap<String, Vec<String>> = HashMap::new();
+    for backend in &config.backends {
+        let Some(status) = statuses.get(&backend.name) else {
+            continue;
+        };
+        for model in &status.models {
+            let backends = routing_table.entry(model.id.clone()).or_default();
+            if backends.is_empty() {
+                model_cache.push(model.clone());
+            }
+            backends.push(backend.name.clone());
+        }
+    }
+    drop(statuses);
+
+    *state.routing_table.write().await = routing_table;
+    let model_count = model_cache.len();
+    *state.model_cache.write().await = model_cache;
+    info!(
+        "Model routing table refreshed. {} models available.",
+        model_count
+    );
+}
+
+fn expire_stale_models(config: &Config, backend: &BackendConfig, status: &mut BackendStatus) {
+    if status.models.is_empty() {
+        return;
+    }
+    let age = status.age().unwrap_or_default();
+    match config.max_staleness() {
+        Some(max_staleness) if age > max_staleness => {
+            warn!(
+                "Evicting {} stale models from {} (last refreshed {}s ago)",
+                status.models.len(),
+                backend.name,
+                age.as_secs()
+            );
+            status.models.clear();
+        }
+        _ => warn!(
+            "Serving {} cached models from {} (last refreshed {}s ago)",
+            status.models.len(),
+            backend.name,
+            age.as_secs()
+        ),
+    }
+}
+
+async fn fetch_models(state: &AppState, backend: &BackendConfig) -> Result<Vec<ModelInfo>, String> {
+    if !backend.models.is_empty() {
+        return Ok(backend
+            .models
+            .iter()
+            .map(|id| ModelInfo {
+                id: id.clone(),
+                object: "model".to_string(),
+                created: 0,
+                owned_by: backend.name.clone(),
+            })
+            .collect());
+    }
+    let adapter = adapter::for_backend(backend);
+    if let Some(models) = adapter.configured_models(backend) {
+        return Ok(models);
     }
+    let mut headers = HeaderMap::new();
+    adapter
+        .apply_auth(&mut headers, backend)
+        .map_err(|err| format!("invalid credentials: {}", err))?;
+    let response = state
+        .client_for(backend)
+        .get(format!("{}{}", backend.url, adapter.models_path(backend)))
+        .headers(headers)
+        .send()
+        .await
+        .and_then(|resp| resp.error_for_status())
+        .map_err(|err| format!("failed to reach backend: {}", err))?;
+    let body = response
+        .json::<Value>()
+        .await
+        .map_err(|err| format!("failed to parse models: {}", err))?;
+    adapter
+        .parse_models(&body)
+        .map_err(|err| format!("failed to parse models: {}", err))
 }
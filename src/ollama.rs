@@ -0,0 +1,602 @@
+use crate::alias;
+use crate::auth;
+use crate::error::RouterError;
+use crate::model::{AppState, ModelInfo};
+use crate::router;
+use crate::stream::{self, SseFramer};
+use axum::{
+    Json,
+    body::Body,
+    extract::State,
+    http::{HeaderMap, HeaderValue, Response, StatusCode, header},
+    response::IntoResponse,
+};
+use bytes::Bytes;
+use chrono::{DateTime, SecondsFormat, Utc};
+use futures_util::StreamExt;
+use http_body_util::BodyExt;
+use serde_json::{Map, Value, json};
+use std::time::Instant;
+
+/// Ollama version reported by `/api/version`. Clients use it to decide which
+/// API features they may use.
+pub const OLLAMA_VERSION: &str = "0.9.0";
+
+/// Ollama `options` passed on under their OpenAI names.
+const OPTIONS: [(&str, &str); 7] = [
+    ("temperature", "temperature"),
+    ("top_p", "top_p"),
+    ("seed", "seed"),
+    ("stop", "stop"),
+    ("num_predict", "max_tokens"),
+    ("frequency_penalty", "frequency_penalty"),
+    ("presence_penalty", "presence_penalty"),
+];
+
+const NDJSON: &str = "application/x-ndjson";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Api {
+    Chat,
+    Generate,
+}
+
+pub async fn version() -> Json<Value> {
+    Json(json!({"version": OLLAMA_VERSION}))
+}
+
+/// Lists the models the caller's API key may use, in the `/api/tags` format.
+pub async fn list_tags(State(state): State<AppState>, headers: HeaderMap) -> Response<Body> {
+    let config = state.config.load();
+    let key = match auth::authenticate(&config, &headers) {
+        Ok(key) => key,
+        Err(err) => return router_error(err),
+    };
+    let models: Vec<Value> = alias::with_models(&config, &state.model_cache.read().await)
+        .iter()
+        .filter(|model| key.as_ref().is_none_or(|key| key.allows(&model.id)))
+        .map(|model| {
+            json!({
+                "name": model.id,
+                "model": model.id,
+                "modified_at": modified_at(model),
+                "size": 0,
+                "digest": "",
+                "details": details(),
+            })
+        })
+        .collect();
+    Json(json!({"models": models})).into_response()
+}
+
+/// Describes one model. Only what the router knows is filled in; the
+/// Modelfile, template and parameters stay empty.
+pub async fn show(State(state): State<AppState>, headers: HeaderMap, body: Body) -> Response<Body> {
+    let request = match read_json(body).await {
+        Ok(request) => request,
+        Err(err) => return router_error(err),
+    };
+    let Some(name) = request
+        .get("model")
+        .or_else(|| request.get("name"))
+        .and_then(Value::as_str)
+    else {
+        return router_error(RouterError::MissingModel);
+    };
+    let config = state.config.load();
+    let key = match auth::authenticate(&config, &headers) {
+        Ok(key) => key,
+        Err(err) => return router_error(err),
+    };
+    if let Err(err) = auth::authorize_model(key.as_ref(), name) {
+        return router_error(err);
+    }
+    let models = alias::with_models(&config, &state.model_cache.read().await);
+    let Some(model) = models.iter().find(|model| model.id == name) else {
+        return model_not_found(name);
+    };
+    Json(json!({
+        "modelfile": "",
+        "parameters": "",
+        "template": "",
+        "details": details(),
+        "model_info": {},
+        "capabilities": ["completion"],
+        "modified_at": modified_at(model),
+    }))
+    .into_response()
+}
+
+pub async fn chat(State(state): State<AppState>, headers: HeaderMap, body: Body) -> Response<Body> {
+    translate(state, headers, body, Api::Chat).await
+}
+
+pub async fn generate(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Body,
+) -> Response<Body> {
+    translate(state, headers, body, Api::Generate).await
+}
+
+/// Serves an Ollama chat or generate request through the OpenAI chat
+/// completions endpoint and translates the response back, turning a
+/// Server-Sent Events stream into Ollama's newline-delimited JSON.
+async fn translate(
+    state: AppState,
+    mut headers: HeaderMap,
+    body: Body,
+    api: Api,
+) -> Response<Body> {
+    let started = Instant::now();
+    let request = match read_json(body).await {
+        Ok(request) => request,
+        Err(err) => return router_error(err),
+    };
+    let model = request
+        .get("model")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let streaming = request
+        .get("stream")
+        .and_then(Value::as_bool)
+        .unwrap_or(true);
+    let mut translator = Translator::new(api, model, started);
+
+    // Ollama clients load a model by sending it nothing to respond to.
+    if is_load_request(api, &request) {
+        return load(&state, &headers, translator).await;
+    }
+
+    let openai = match api {
+        Api::Chat => chat_request(&request),
+        Api::Generate => generate_request(&request),
+    };
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    let response = router::forward(
+        state,
+        headers,
+        Body::from(openai.to_string()),
+        "/v1/chat/completions",
+    )
+    .await;
+    if !response.status().is_success() {
+        return error_response(response).await;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    if stream::is_event_stream(&parts.headers) {
+        parts.headers.remove("x-accel-buffering");
+        parts
+            .headers
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static(NDJSON));
+        let lines = futures_util::stream::unfold(
+            Some((body.into_data_stream(), translator)),
+            |state| async move {
+                let (mut events, mut translator) = state?;
+                match events.next().await {
+                    Some(Ok(chunk)) => {
+                        Some((Ok(translator.push(&chunk)), Some((events, translator))))
+                    }
+                    Some(Err(err)) => Some((Err(err), None)),
+                    None => Some((Ok(translator.finish()), None)),
+                }
+            },
+        );
+        return Response::from_parts(parts, Body::from_stream(lines));
+    }
+
+    let bytes = body.collect().await.unwrap_or_default().to_bytes();
+    let completion: Value = serde_json::from_slice(&bytes).unwrap_or_default();
+    let content_type = if streaming {
+        NDJSON
+    } else {
+        "application/json"
+    };
+    parts
+        .headers
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    Response::from_parts(parts, Body::from(translator.complete(&completion)))
+}
+
+fn is_load_request(api: Api, request: &Value) -> bool {
+    match api {
+        Api::Chat => request
+            .get("messages")
+            .and_then(Value::as_array)
+            .is_none_or(Vec::is_empty),
+        Api::Generate => {
+            request
+                .get("prompt")
+                .and_then(Value::as_str)
+                .is_none_or(str::is_empty)
+                && request
+                    .get("images")
+                    .and_then(Value::as_array)
+                    .is_none_or(Vec::is_empty)
+        }
+    }
+}
+
+/// Answers a load request for a routable model without involving a backend.
+async fn load(state: &AppState, headers: &HeaderMap, translator: Translator) -> Response<Body> {
+    let model = translator.model.as_str();
+    if model.is_empty() {
+        return router_error(RouterError::MissingModel);
+    }
+    let config = state.config.load();
+    let key = match auth::authenticate(&config, headers) {
+        Ok(key) => key,
+        Err(err) => return router_error(err),
+    };
+    if let Err(err) = auth::authorize_model(key.as_ref(), model) {
+        return router_error(err);
+    }
+    if alias::targets(&config, model).is_none()
+        && !state.routing_table.read().await.contains_key(model)
+    {
+        return model_not_found(model);
+    }
+    let mut done = translator.line(translator.empty(), true);
+    done["done_reason"] = json!("load");
+    Json(done).into_response()
+}
+
+fn chat_request(request: &Value) -> Value {
+    let messages = request
+        .get("messages")
+        .and_then(Value::as_array)
+        .map(|messages| messages.iter().map(openai_message).collect())
+        .unwrap_or_default();
+    openai_request(request, messages)
+}
+
+fn generate_request(request: &Value) -> Value {
+    let mut messages = Vec::new();
+    if let Some(system) = request.get("system").and_then(Value::as_str) {
+        messages.push(json!({"role": "system", "content": system}));
+    }
+    messages.push(openai_message(&json!({
+        "role": "user",
+        "content": request.get("prompt").cloned().unwrap_or_else(|| json!("")),
+        "images": request.get("images").cloned().unwrap_or_default(),
+    })));
+    openai_request(request, messages)
+}
+
+/// The OpenAI chat completion request for an Ollama request with the given
+/// `messages`. Streams ask for usage so the final line can report counts.
+fn openai_request(request: &Value, messages: Vec<Value>) -> Value {
+    let streaming = request
+        .get("stream")
+        .and_then(Value::as_bool)
+        .unwrap_or(true);
+    let mut body = json!({
+        "model": request.get("model").cloned().unwrap_or_default(),
+        "messages": messages,
+        "stream": streaming,
+    });
+    if streaming {
+        body["stream_options"] = json!({"include_usage": true});
+    }
+    if let Some(options) = request.get("options").and_then(Value::as_object) {
+        for (option, field) in OPTIONS {
+            if let Some(value) = options.get(option) {
+                body[field] = value.clone();
+            }
+        }
+    }
+    match request.get("format") {
+        Some(Value::String(format)) if format == "json" => {
+            body["response_format"] = json!({"type": "json_object"});
+        }
+        Some(schema @ Value::Object(_)) => {
+            body["response_format"] = json!({
+                "type": "json_schema",
+                "json_schema": {"name": "response", "schema": schema},
+            });
+        }
+        _ => {}
+    }
+    if let Some(tools) = request.get("tools") {
+        body["tools"] = tools.clone();
+    }
+    body
+}
+
+/// Converts an Ollama message: `images` become image content parts and tool
+/// call arguments are serialized, as OpenAI expects them as a JSON string.
+fn openai_message(message: &Value) -> Value {
+    let mut message = message.clone();
+    let Some(fields) = message.as_object_mut() else {
+        return message;
+    };
+    fields.remove("thinking");
+    if let Some(Value::Array(images)) = fields.remove("images")
+        && !images.is_empty()
+    {
+        let mut parts = Vec::new();
+        if let Some(Value::String(text)) = fields.get("content")
+            && !text.is_empty()
+        {
+            parts.push(json!({"type": "text", "text": text}));
+        }
+        for image in images.iter().filter_map(Value::as_str) {
+            parts.push(json!({"type": "image_url", "image_url": {"url": data_url(image)}}));
+        }
+        fields.insert("content".to_string(), Value::Array(parts));
+    }
+    if let Some(Value::Array(calls)) = fields.get_mut("tool_calls") {
+        for (i, call) in calls.iter_mut().enumerate() {
+            let Some(call) = call.as_object_mut() else {
+                continue;
+            };
+            call.entry("id")
+                .or_insert_with(|| json!(format!("call_{}", i)));
+            call.insert("type".to_string(), json!("function"));
+            if let Some(Value::Object(function)) = call.get_mut("function")
+                && let Some(arguments) = function.get_mut("arguments")
+                && !arguments.is_string()
+            {
+                *arguments = Value::String(arguments.to_string());
+            }
+        }
+    }
+    message
+}
+
+/// Ollama sends bare base64 images; OpenAI wants data URLs.
+fn data_url(image: &str) -> String {
+    let mime = match image.get(..4) {
+        Some("/9j/") => "image/jpeg",
+        Some("R0lG") => "image/gif",
+        Some("UklG") => "image/webp",
+        _ => "image/png",
+    };
+    format!("data:{};base64,{}", mime, image)
+}
+
+/// Builds Ollama responses from OpenAI completions and completion chunks.
+struct Translator {
+    api: Api,
+    model: String,
+    started: Instant,
+    framer: SseFramer,
+    /// Tool calls streamed so far, by index; OpenAI streams their arguments
+    /// in pieces while Ollama sends each call whole.
+    tool_calls: Vec<Value>,
+    done_reason: Option<String>,
+    usage: Option<Value>,
+    failed: bool,
+}
+
+impl Translator {
+    fn new(api: Api, model: String, started: Instant) -> Self {
+        Self {
+            api,
+            model,
+            started,
+            framer: SseFramer::default(),
+            tool_calls: Vec::new(),
+            done_reason: None,
+            usage: None,
+            failed: false,
+        }
+    }
+
+    /// Translates a non-streamed completion into the final Ollama response.
+    fn complete(&mut self, completion: &Value) -> Bytes {
+        let choice = &completion["choices"][0];
+        let message = &choice["message"];
+        self.done_reason = choice["finish_reason"].as_str().map(str::to_string);
+        self.usage = completion.get("usage").cloned();
+        let content = message["content"].as_str().unwrap_or_default();
+        let tool_calls = message["tool_calls"]
+            .as_array()
+            .map(|calls| calls.iter().map(ollama_tool_call).collect())
+            .unwrap_or_default();
+        let mut response = self.line(self.output(content, tool_calls), true);
+        self.add_stats(&mut response);
+        Bytes::from(format!("{}\n", response))
+    }
+
+    /// Translates the Server-Sent Events in `chunk` into NDJSON lines.
+    fn push(&mut self, chunk: &[u8]) -> Bytes {
+        let mut lines = String::new();
+        for event in self.framer.push(chunk) {
+            self.translate_event(&event, &mut lines);
+        }
+        Bytes::from(lines)
+    }
+
+    /// The lines ending a stream: any tool calls, then the `done` line.
+    fn finish(&mut self) -> Bytes {
+        let mut lines = String::new();
+        if let Some(rest) = self.framer.finish() {
+            self.translate_event(&rest, &mut lines);
+        }
+        if self.failed {
+            return Bytes::from(lines);
+        }
+        if !self.tool_calls.is_empty() {
+            let tool_calls = self.tool_calls.iter().map(ollama_tool_call).collect();
+            let line = self.line(self.output("", tool_calls), false);
+            lines.push_str(&format!("{}\n", line));
+        }
+        let mut done = self.line(self.empty(), true);
+        self.add_stats(&mut done);
+        lines.push_str(&format!("{}\n", done));
+        Bytes::from(lines)
+    }
+
+    fn translate_event(&mut self, event: &[u8], lines: &mut String) {
+        let text = String::from_utf8_lossy(event);
+        for data in text.lines().filter_map(|line| line.strip_prefix("data:")) {
+            let Ok(chunk) = serde_json::from_str::<Value>(data.trim()) else {
+                continue;
+            };
+            if let Some(error) = chunk.get("error") {
+                let message = error
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .unwrap_or("The backend stream failed.");
+                lines.push_str(&format!("{}\n", json!({"error": message})));
+                self.failed = true;
+                continue;
+            }
+            if let Some(usage) = chunk.get("usage").filter(|usage| !usage.is_null()) {
+                self.usage = Some(usage.clone());
+            }
+            let choice = &chunk["choices"][0];
+            if let Some(reason) = choice["finish_reason"].as_str() {
+                self.done_reason = Some(reason.to_string());
+            }
+            let delta = &choice["delta"];
+            if let Some(calls) = delta["tool_calls"].as_array() {
+                for call in calls {
+                    self.merge_tool_call(call);
+                }
+            }
+            if let Some(content) = delta["content"].as_str()
+                && !content.is_empty()
+            {
+                let line = self.line(self.output(content, Vec::new()), false);
+                lines.push_str(&format!("{}\n", line));
+            }
+        }
+    }
+
+    fn merge_tool_call(&mut self, delta: &Value) {
+        let index = delta["index"].as_u64().unwrap_or(0) as usize;
+        while self.tool_calls.len() <= index {
+            self.tool_calls
+                .push(json!({"function": {"name": "", "arguments": ""}}));
+        }
+        let function = &mut self.tool_calls[index]["function"];
+        for field in ["name", "arguments"] {
+            if let Some(piece) = delta["function"][field].as_str()
+                && let Some(Value::String(value)) = function.get_mut(field)
+            {
+                value.push_str(piece);
+            }
+        }
+    }
+
+    /// The generated output in the shape of the endpoint: a `message` for
+    /// chat, a `response` string for generate.
+    fn output(&self, content: &str, tool_calls: Vec<Value>) -> (&'static str, Value) {
+        match self.api {
+            Api::Chat => {
+                let mut message = json!({"role": "assistant", "content": content});
+                if !tool_calls.is_empty() {
+                    message["tool_calls"] = Value::Array(tool_calls);
+                }
+                ("message", message)
+            }
+            Api::Generate => ("response", json!(content)),
+        }
+    }
+
+    fn empty(&self) -> (&'static str, Value) {
+        self.output("", Vec::new())
+    }
+
+    fn line(&self, (field, output): (&'static str, Value), done: bool) -> Value {
+        let mut line = Map::new();
+        line.insert("model".to_string(), json!(self.model));
+        line.insert("created_at".to_string(), json!(now()));
+        line.insert(field.to_string(), output);
+        line.insert("done".to_string(), json!(done));
+        Value::Object(line)
+    }
+
+    fn add_stats(&self, line: &mut Value) {
+        let done_reason = match self.done_reason.as_deref() {
+            Some("length") => "length",
+            _ => "stop",
+        };
+        line["done_reason"] = json!(done_reason);
+        line["total_duration"] = json!(self.started.elapsed().as_nanos() as u64);
+        if let Some(usage) = &self.usage {
+            line["prompt_eval_count"] = usage["prompt_tokens"].clone();
+            line["eval_count"] = usage["completion_tokens"].clone();
+        }
+    }
+}
+
+/// Converts an OpenAI tool call to Ollama's, whose arguments are an object.
+fn ollama_tool_call(call: &Value) -> Value {
+    let function = &call["function"];
+    let arguments = match &function["arguments"] {
+        Value::String(arguments) => serde_json::from_str(arguments).unwrap_or_else(|_| json!({})),
+        arguments => arguments.clone(),
+    };
+    json!({"function": {"name": function["name"], "arguments": arguments}})
+}
+
+fn details() -> Value {
+    json!({
+        "parent_model": "",
+        "format": "",
+        "family": "",
+        "families": null,
+        "parameter_size": "",
+        "quantization_level": "",
+    })
+}
+
+fn modified_at(model: &ModelInfo) -> String {
+    DateTime::from_timestamp(model.created, 0)
+        .unwrap_or_default()
+        .to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+async fn read_json(body: Body) -> Result<Value, RouterError> {
+    let bytes = body.collect().await.unwrap_or_default().to_bytes();
+    serde_json::from_slice(&bytes).map_err(|err| RouterError::InvalidJson(err.to_string()))
+}
+
+/// Ollama reports errors as `{"error": "<message>"}`.
+fn error(status: StatusCode, message: &str) -> Response<Body> {
+    (status, Json(json!({"error": message}))).into_response()
+}
+
+fn router_error(err: RouterError) -> Response<Body> {
+    error(err.status(), &err.to_string())
+}
+
+fn model_not_found(model: &str) -> Response<Body> {
+    error(
+        StatusCode::NOT_FOUND,
+        &format!("model '{}' not found", model),
+    )
+}
+
+/// Rewrites an OpenAI-format error response, from the router or a backend,
+/// into Ollama's format, keeping its status and headers.
+async fn error_response(response: Response<Body>) -> Response<Body> {
+    let (mut parts, body) = response.into_parts();
+    let bytes = body.collect().await.unwrap_or_default().to_bytes();
+    let body: Value = serde_json::from_slice(&bytes).unwrap_or_default();
+    let message = match &body["error"] {
+        Value::String(message) => message.clone(),
+        error => error["message"]
+            .as_str()
+            .map(str::to_string)
+            .unwrap_or_else(|| String::from_utf8_lossy(&bytes).into_owned()),
+    };
+    parts.headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    let body = json!({"error": message}).to_string();
+    Response::from_parts(parts, Body::from(body))
+}
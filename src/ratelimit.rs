@@ -0,0 +1,469 @@
+use crate::config::RateLimit;
+use crate::error::RouterError;
+use axum::body::Body;
+use axum::http::{HeaderName, HeaderValue, Response, header};
+use axum::response::IntoResponse;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// What a rate limit applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scope {
+    /// A client API key, by name.
+    Key,
+    /// A model id, across all clients.
+    Model,
+    /// A backend, by name.
+    Backend,
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Scope::Key => write!(f, "API key"),
+            Scope::Model => write!(f, "model"),
+            Scope::Backend => write!(f, "backend"),
+        }
+    }
+}
+
+type Entries = Arc<Mutex<HashMap<(Scope, String), Entry>>>;
+
+/// Enforces requests-per-minute and tokens-per-minute limits (as token
+/// buckets refilled continuously) and concurrent-request limits.
+#[derive(Default)]
+pub struct RateLimiter {
+    entries: Entries,
+}
+
+struct Entry {
+    requests: Bucket,
+    tokens: Bucket,
+    in_flight: u32,
+}
+
+/// A token bucket holding up to one minute's worth of tokens. The level goes
+/// negative when a request turns out to use more tokens than estimated.
+struct Bucket {
+    level: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn full(per_minute: u32) -> Self {
+        Self {
+            level: per_minute as f64,
+            updated: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, per_minute: u32) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.level = (self.level + elapsed * per_minute as f64 / 60.0).min(per_minute as f64);
+        self.updated = now;
+    }
+
+    /// Time until `amount` tokens are available.
+    fn wait_for(&self, amount: f64, per_minute: u32) -> Duration {
+        let missing = (amount - self.level).max(0.0);
+        Duration::from_secs_f64(missing * 60.0 / per_minute.max(1) as f64)
+    }
+
+    fn quota(&self, resource: &'static str, per_minute: u32) -> Quota {
+        Quota {
+            resource,
+            limit: per_minute,
+            remaining: self.level.max(0.0) as u32,
+            reset: self.wait_for(per_minute as f64, per_minute),
+        }
+    }
+}
+
+/// Quota left in the most constrained scope, reported to clients as OpenAI's
+/// `x-ratelimit-*-requests` or `x-ratelimit-*-tokens` headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    /// `requests` or `tokens`.
+    pub resource: &'static str,
+    pub limit: u32,
+    pub remaining: u32,
+    /// Time until the bucket is full again.
+    pub reset: Duration,
+}
+
+impl Quota {
+    pub fn apply_to(&self, response: &mut Response<Body>) {
+        let name = |kind: &str| {
+            HeaderName::try_from(format!("x-ratelimit-{}-{}", kind, self.resource))
+                .expect("valid header name")
+        };
+        let headers = response.headers_mut();
+        headers.insert(name("limit"), HeaderValue::from(self.limit));
+        headers.insert(name("remaining"), HeaderValue::from(self.remaining));
+        if let Ok(value) = HeaderValue::from_str(&format_reset(self.reset)) {
+            headers.insert(name("reset"), value);
+        }
+    }
+}
+
+/// A rejected request.
+#[derive(Debug, Clone)]
+pub struct Limited {
+    pub scope: Scope,
+    pub id: String,
+    pub retry_after: Duration,
+    pub quota: Option<Quota>,
+    error: RouterError,
+}
+
+impl IntoResponse for Limited {
+    fn into_response(self) -> Response<Body> {
+        let mut response = self.error.into_response();
+        let seconds = self.retry_after.as_secs_f64().ceil().max(1.0) as u64;
+        response
+            .headers_mut()
+            .insert(header::RETRY_AFTER, HeaderValue::from(seconds));
+        if let Some(quota) = self.quota {
+            quota.apply_to(&mut response);
+        }
+        response
+    }
+}
+
+/// Holds the concurrency slots and token charge of a request until it is
+/// dropped. The charge is then corrected to the usage the backend reported.
+pub struct Permit {
+    entries: Entries,
+    held: Vec<(Scope, String)>,
+    /// Scopes charged one request, with their requests-per-minute limit.
+    requested: Vec<((Scope, String), u32)>,
+    /// Scopes charged `estimate` tokens, with their tokens-per-minute limit.
+    charged: Vec<((Scope, String), u32)>,
+    estimate: u64,
+    usage: UsageRecorder,
+    /// Quotas of the most constrained requests- and tokens-per-minute limits.
+    pub quotas: Vec<Quota>,
+}
+
+impl Permit {
+    /// Where the backend's reported token usage is recorded. Any token
+    /// charge is reconciled against it when the permit is dropped.
+    pub fn usage(&self) -> UsageRecorder {
+        self.usage.clone()
+    }
+
+    /// Gives back the request and token charge, for a request that never
+    /// reached a backend. Returns the quotas left afterwards.
+    pub fn refund(mut self) -> Vec<Quota> {
+        let mut entries = self.entries.lock().unwrap();
+        let mut request_quota: Option<Quota> = None;
+        let mut token_quota: Option<Quota> = None;
+        for (id, per_minute) in self.requested.drain(..) {
+            if let Some(entry) = entries.get_mut(&id) {
+                entry.requests.level = (entry.requests.level + 1.0).min(per_minute as f64);
+                tightest(
+                    &mut request_quota,
+                    entry.requests.quota("requests", per_minute),
+                );
+            }
+        }
+        for (id, per_minute) in self.charged.drain(..) {
+            if let Some(entry) = entries.get_mut(&id) {
+                let level = entry.tokens.level + self.estimate as f64;
+                entry.tokens.level = level.min(per_minute as f64);
+                tightest(&mut token_quota, entry.tokens.quota("tokens", per_minute));
+            }
+        }
+        request_quota.into_iter().chain(token_quota).collect()
+    }
+}
+
+/// Keeps the quota with the least remaining.
+fn tightest(best: &mut Option<Quota>, current: Quota) {
+    if best.is_none_or(|q| current.remaining < q.remaining) {
+        *best = Some(current);
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let actual = self.usage.get();
+        if self.held.is_empty() && (self.charged.is_empty() || actual.is_none()) {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        for id in &self.held {
+            if let Some(entry) = entries.get_mut(id) {
+                entry.in_flight = entry.in_flight.saturating_sub(1);
+            }
+        }
+        if let Some(actual) = actual {
+            for (id, per_minute) in &self.charged {
+                if let Some(entry) = entries.get_mut(id) {
+                    let level = entry.tokens.level + self.estimate as f64 - actual as f64;
+                    entry.tokens.level = level.min(*per_minute as f64);
+                }
+            }
+        }
+    }
+}
+
+impl RateLimiter {
+    /// Admits a request expected to use `tokens` tokens against every given
+    /// limit, or against none of them: when one limit rejects it, nothing is
+    /// consumed from the others.
+    pub fn try_acquire(
+        &self,
+        limits: &[(Scope, &str, &RateLimit)],
+        tokens: u64,
+    ) -> Result<Permit, Limited> {
+        let mut entries = self.entries.lock().unwrap();
+
+        for (scope, id, limit) in limits {
+            let entry = entries
+                .entry((*scope, id.to_string()))
+                .or_insert_with(|| Entry {
+                    requests: Bucket::full(limit.requests_per_minute.unwrap_or(0)),
+                    tokens: Bucket::full(limit.tokens_per_minute.unwrap_or(0)),
+                    in_flight: 0,
+                });
+
+            if let Some(max_concurrent) = limit.max_concurrent
+                && entry.in_flight >= max_concurrent
+            {
+                return Err(Limited {
+                    scope: *scope,
+                    id: id.to_string(),
+                    retry_after: Duration::from_secs(1),
+                    quota: None,
+                    error: RouterError::RateLimited(format!(
+                        "Too many concurrent requests for {} `{}`: limit {}. Please try again shortly.",
+                        scope, id, max_concurrent
+                    )),
+                });
+            }
+            if let Some(per_minute) = limit.requests_per_minute {
+                entry.requests.refill(per_minute);
+                if entry.requests.level < 1.0 {
+                    let retry_after = entry.requests.wait_for(1.0, per_minute);
+                    return Err(Limited {
+                        scope: *scope,
+                        id: id.to_string(),
+                        retry_after,
+                        quota: Some(entry.requests.quota("requests", per_minute)),
+                        error: RouterError::RateLimited(format!(
+                            "Rate limit reached for {} `{}` on requests per min (RPM): limit {}. Please try again in {}.",
+                            scope,
+                            id,
+                            per_minute,
+                            format_reset(retry_after)
+                        )),
+                    });
+                }
+            }
+            if let Some(per_minute) = limit.tokens_per_minute {
+                entry.tokens.refill(per_minute);
+                // A request larger than the whole limit runs once the bucket is full.
+                let needed = (tokens as f64).min(per_minute as f64);
+                if entry.tokens.level < needed || entry.tokens.level <= 0.0 {
+                    let retry_after = entry.tokens.wait_for(needed.max(1.0), per_minute);
+                    return Err(Limited {
+                        scope: *scope,
+                        id: id.to_string(),
+                        retry_after,
+                        quota: Some(entry.tokens.quota("tokens", per_minute)),
+                        error: RouterError::TokenRateLimited(format!(
+                            "Rate limit reached for {} `{}` on tokens per min (TPM): limit {}, requested {}. Please try again in {}.",
+                            scope,
+                            id,
+                            per_minute,
+                            tokens,
+                            format_reset(retry_after)
+                        )),
+                    });
+                }
+            }
+        }
+
+        let mut held = Vec::new();
+        let mut requested = Vec::new();
+        let mut charged = Vec::new();
+        let mut request_quota: Option<Quota> = None;
+        let mut token_quota: Option<Quota> = None;
+        for (scope, id, limit) in limits {
+            let key = (*scope, id.to_string());
+            let entry = entries.get_mut(&key).expect("entry created above");
+            if let Some(per_minute) = limit.requests_per_minute {
+                entry.requests.level -= 1.0;
+                tightest(
+                    &mut request_quota,
+                    entry.requests.quota("requests", per_minute),
+                );
+                requested.push((key.clone(), per_minute));
+            }
+            if let Some(per_minute) = limit.tokens_per_minute {
+                entry.tokens.level -= tokens as f64;
+                tightest(&mut token_quota, entry.tokens.quota("tokens", per_minute));
+                charged.push((key.clone(), per_minute));
+            }
+            if limit.max_concurrent.is_some() {
+                entry.in_flight += 1;
+                held.push(key);
+            }
+        }
+
+        Ok(Permit {
+            entries: self.entries.clone(),
+            held,
+            requested,
+            charged,
+            estimate: tokens,
+            usage: UsageRecorder::default(),
+            quotas: request_quota.into_iter().chain(token_quota).collect(),
+        })
+    }
+}
+
+/// Token counts from the `usage` object of a response.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+}
+
+/// Collects the token usage a backend reports for one request, either in a
+/// JSON response body or in the `usage` object of a streamed chunk.
+#[derive(Debug, Clone, Default)]
+pub struct UsageRecorder(Arc<Mutex<Option<Usage>>>);
+
+impl UsageRecorder {
+    /// Total tokens used, if the backend reported usage.
+    pub fn get(&self) -> Option<u64> {
+        self.usage().map(|usage| usage.total_tokens)
+    }
+
+    pub fn usage(&self) -> Option<Usage> {
+        *self.0.lock().unwrap()
+    }
+
+    pub fn record_json(&self, body: &[u8]) {
+        if let Some(usage) = serde_json::from_slice::<Value>(body)
+            .ok()
+            .as_ref()
+            .and_then(parse_usage)
+        {
+            *self.0.lock().unwrap() = Some(usage);
+        }
+    }
+
+    /// Inspects one Server-Sent Event. Only events mentioning `usage` are
+    /// parsed, so regular content chunks cost a substring search.
+    pub fn record_sse_event(&self, event: &[u8]) {
+        if !event.windows(7).any(|w| w == b"\"usage\"") {
+            return;
+        }
+        let text = String::from_utf8_lossy(event);
+        for line in text.lines() {
+            if let Some(data) = line.strip_prefix("data:") {
+                self.record_json(data.trim().as_bytes());
+            }
+        }
+    }
+}
+
+/// Whether a Server-Sent Event is the chunk that only carries `usage`, sent
+/// when a request sets `stream_options.include_usage`.
+pub fn is_usage_event(event: &[u8]) -> bool {
+    if !event.windows(7).any(|w| w == b"\"usage\"") {
+        return false;
+    }
+    String::from_utf8_lossy(event).lines().any(|line| {
+        line.strip_prefix("data:")
+            .and_then(|data| serde_json::from_str::<Value>(data.trim()).ok())
+            .is_some_and(|data| {
+                data["choices"].as_array().is_some_and(Vec::is_empty) && !data["usage"].is_null()
+            })
+    })
+}
+
+/// Asks for the usage chunk of a streamed response when the client did not,
+/// so that token limits and metrics see the usage. Returns whether `request`
+/// was changed.
+pub fn request_stream_usage(request: &mut Value) -> bool {
+    if request["stream"] != true || request["stream_options"]["include_usage"] == true {
+        return false;
+    }
+    match request.get_mut("stream_options") {
+        Some(Value::Object(options)) => {
+            options.insert("include_usage".to_string(), Value::Bool(true));
+        }
+        None | Some(Value::Null) => {
+            request["stream_options"] = serde_json::json!({"include_usage": true});
+        }
+        // Left for the backend to reject.
+        Some(_) => return false,
+    }
+    true
+}
+
+fn parse_usage(body: &Value) -> Option<Usage> {
+    let usage = body.get("usage")?;
+    let count = |field: &str| usage.get(field).and_then(Value::as_u64);
+    let (prompt, completion) = (count("prompt_tokens"), count("completion_tokens"));
+    let total = count("total_tokens").or_else(|| match (prompt, completion) {
+        (None, None) => None,
+        (prompt, completion) => Some(prompt.unwrap_or(0) + completion.unwrap_or(0)),
+    })?;
+    Some(Usage {
+        prompt_tokens: prompt.unwrap_or(0),
+        completion_tokens: completion.unwrap_or(0),
+        total_tokens: total,
+    })
+}
+
+/// Estimates the tokens a request will use before it is sent: roughly one
+/// token per four characters of prompt text plus the completion budget
+/// (`max_tokens` or `max_completion_tokens`).
+pub fn estimate_tokens(request: &Value) -> u64 {
+    let prompt_chars: usize = ["messages", "prompt", "input"]
+        .iter()
+        .filter_map(|field| request.get(field))
+        .map(text_len)
+        .sum();
+    let completion = request
+        .get("max_tokens")
+        .or_else(|| request.get("max_completion_tokens"))
+        .and_then(Value::as_u64)
+        .unwrap_or(0);
+    prompt_chars.div_ceil(4) as u64 + completion
+}
+
+fn text_len(value: &Value) -> usize {
+    match value {
+        Value::String(text) => text.chars().count(),
+        Value::Array(items) => items.iter().map(text_len).sum(),
+        Value::Object(fields) => fields.values().map(text_len).sum(),
+        _ => 0,
+    }
+}
+
+/// Formats a duration the way OpenAI's `x-ratelimit-reset-*` headers do,
+/// e.g. `250ms`, `6s` or `1m30s`.
+fn format_reset(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    if millis < 1000 {
+        format!("{}ms", millis)
+    } else {
+        let seconds = duration.as_secs_f64().ceil() as u64;
+        if seconds < 60 {
+            format!("{}s", seconds)
+        } else {
+            format!("{}m{}s", seconds / 60, seconds % 60)
+        }
+    }
+}
@@ -0,0 +1,313 @@
+use crate::config::RecorderConfig;
+use crate::metrics::Metrics;
+use serde_json::{Map, Value, json};
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+use tracing::error;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Records waiting to be written before new ones are dropped.
+const QUEUE_CAPACITY: usize = 1024;
+
+/// Streamed string fields that are replaced rather than concatenated.
+const REPLACED: [&str; 7] = [
+    "id",
+    "object",
+    "model",
+    "role",
+    "type",
+    "finish_reason",
+    "system_fingerprint",
+];
+
+/// Appends sampled requests and their responses to a JSONL file. Records are
+/// written and the file rotated on a background thread, so requests never
+/// wait for the disk. When the disk falls behind, records are dropped and
+/// counted in `llm_router_recorder_dropped_total` rather than queued without
+/// bound.
+pub struct Recorder {
+    config: RecorderConfig,
+    sender: mpsc::SyncSender<Message>,
+    metrics: Arc<Metrics>,
+}
+
+enum Message {
+    Record(String),
+    Flush(mpsc::Sender<()>),
+}
+
+impl Recorder {
+    pub fn new(config: RecorderConfig, metrics: Arc<Metrics>) -> Self {
+        let (sender, receiver) = mpsc::sync_channel(QUEUE_CAPACITY);
+        let writer = Writer {
+            path: config.path.clone(),
+            max_bytes: config.max_bytes,
+            max_files: config.max_files,
+            file: None,
+            size: 0,
+        };
+        thread::Builder::new()
+            .name("recorder".to_string())
+            .spawn(move || writer.run(receiver))
+            .expect("failed to start the recorder thread");
+        Self {
+            config,
+            sender,
+            metrics,
+        }
+    }
+
+    /// Starts capturing a request if it is sampled.
+    pub fn sample(self: &Arc<Self>, endpoint: &str, request: &Value) -> Option<Capture> {
+        if self.config.sample_rate < 1.0 && rand::random::<f64>() >= self.config.sample_rate {
+            return None;
+        }
+        Some(Capture {
+            recorder: self.clone(),
+            endpoint: endpoint.to_string(),
+            request: Arc::new(request.clone()),
+            response: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    /// Waits until every record captured so far has been written.
+    pub fn flush(&self) {
+        let (done, wait) = mpsc::channel();
+        if self.sender.send(Message::Flush(done)).is_ok() {
+            let _ = wait.recv();
+        }
+    }
+
+    fn write(&self, mut record: Value) {
+        for path in &self.config.redact {
+            let path: Vec<&str> = path.split('.').collect();
+            redact(&mut record, &path);
+        }
+        if let Err(mpsc::TrySendError::Full(_)) =
+            self.sender.try_send(Message::Record(record.to_string()))
+        {
+            self.metrics.recorder_dropped();
+        }
+    }
+}
+
+/// A sampled request and the response it is getting. Streamed responses are
+/// reassembled into the object a non-streaming request would have returned.
+#[derive(Clone)]
+pub struct Capture {
+    recorder: Arc<Recorder>,
+    endpoint: String,
+    request: Arc<Value>,
+    response: Arc<Mutex<Option<Value>>>,
+}
+
+impl Capture {
+    /// Records a complete response body, as JSON if it parses.
+    pub fn record_body(&self, body: &[u8]) {
+        let body = serde_json::from_slice(body)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(body).into_owned()));
+        *self.response.lock().unwrap() = Some(body);
+    }
+
+    /// Merges the chunk in one Server-Sent Event into the response.
+    pub fn record_sse_event(&self, event: &[u8]) {
+        let text = String::from_utf8_lossy(event);
+        let mut response = self.response.lock().unwrap();
+        for line in text.lines() {
+            let Some(data) = line.strip_prefix("data:") else {
+                continue;
+            };
+            if let Ok(Value::Object(chunk)) = serde_json::from_str::<Value>(data.trim()) {
+                let assembled = response.get_or_insert_with(|| json!({}));
+                if let Value::Object(assembled) = assembled {
+                    merge_chunk(assembled, chunk);
+                }
+            }
+        }
+    }
+
+    /// Adds the endpoint, request and response to `record`, an object, and
+    /// queues it for writing.
+    pub fn finish(self, mut record: Value) {
+        let mut response = self.response.lock().unwrap().take();
+        if let Some(Value::Object(fields)) = &mut response
+            && let Some(Value::String(object)) = fields.get_mut("object")
+            && let Some(complete) = object.strip_suffix(".chunk")
+        {
+            *object = complete.to_string();
+        }
+        record["endpoint"] = Value::String(self.endpoint);
+        record["request"] = (*self.request).clone();
+        record["response"] = response.unwrap_or(Value::Null);
+        self.recorder.write(record);
+    }
+}
+
+/// Merges a streamed chunk into the response assembled so far: strings are
+/// concatenated, objects merged, `choices` and `tool_calls` matched by
+/// `index`, and anything else replaced.
+fn merge_chunk(target: &mut Map<String, Value>, chunk: Map<String, Value>) {
+    for (key, value) in chunk {
+        match value {
+            Value::Null => {}
+            Value::String(text) if !REPLACED.contains(&key.as_str()) => {
+                match target.get_mut(&key) {
+                    Some(Value::String(existing)) => existing.push_str(&text),
+                    _ => {
+                        target.insert(key, Value::String(text));
+                    }
+                }
+            }
+            Value::Object(fields) => {
+                if let Value::Object(entry) = target.entry(key).or_insert_with(|| json!({})) {
+                    merge_chunk(entry, fields);
+                }
+            }
+            Value::Array(items) if key == "choices" || key == "tool_calls" => {
+                let entry = target.entry(key).or_insert_with(|| json!([]));
+                if let Value::Array(entries) = entry {
+                    for item in items {
+                        merge_indexed(entries, item);
+                    }
+                }
+            }
+            value => {
+                target.insert(key, value);
+            }
+        }
+    }
+}
+
+fn merge_indexed(entries: &mut Vec<Value>, item: Value) {
+    let Value::Object(mut item) = item else {
+        return;
+    };
+    // A choice's `delta` builds up its `message`.
+    if let Some(delta) = item.remove("delta") {
+        item.insert("message".to_string(), delta);
+    }
+    let index = item.get("index").cloned();
+    let position = entries
+        .iter()
+        .position(|entry| entry.get("index") == index.as_ref());
+    let position = position.unwrap_or_else(|| {
+        entries.push(json!({}));
+        entries.len() - 1
+    });
+    if let Value::Object(entry) = &mut entries[position] {
+        merge_chunk(entry, item);
+    }
+}
+
+/// Replaces the values at `path` in `value`; `*` matches every array element
+/// or object value.
+fn redact(value: &mut Value, path: &[&str]) {
+    let Some((segment, rest)) = path.split_first() else {
+        return;
+    };
+    let children: Vec<&mut Value> = match (value, *segment) {
+        (Value::Object(fields), "*") => fields.values_mut().collect(),
+        (Value::Array(items), "*") => items.iter_mut().collect(),
+        (Value::Object(fields), key) => fields.get_mut(key).into_iter().collect(),
+        (Value::Array(items), index) => index
+            .parse::<usize>()
+            .ok()
+            .and_then(|i| items.get_mut(i))
+            .into_iter()
+            .collect(),
+        _ => Vec::new(),
+    };
+    for child in children {
+        if rest.is_empty() {
+            *child = Value::String(REDACTED.to_string());
+        } else {
+            redact(child, rest);
+        }
+    }
+}
+
+struct Writer {
+    path: PathBuf,
+    max_bytes: Option<u64>,
+    max_files: u32,
+    file: Option<File>,
+    size: u64,
+}
+
+impl Writer {
+    fn run(mut self, receiver: mpsc::Receiver<Message>) {
+        for message in receiver {
+            match message {
+                Message::Record(line) => {
+                    if let Err(err) = self.write(&line) {
+                        error!(
+                            "Failed to write request record to {}: {}",
+                            self.path.display(),
+                            err
+                        );
+                        self.file = None;
+                    }
+                }
+                Message::Flush(done) => {
+                    let _ = done.send(());
+                }
+            }
+        }
+    }
+
+    fn write(&mut self, line: &str) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.file.is_none() {
+            self.open()?;
+        }
+        if let Some(max_bytes) = self.max_bytes
+            && self.size > 0
+            && self.size + len > max_bytes
+        {
+            self.rotate()?;
+        }
+        let file = self.file.as_mut().expect("file opened above");
+        file.write_all(format!("{}\n", line).as_bytes())?;
+        self.size += len;
+        Ok(())
+    }
+
+    fn open(&mut self) -> io::Result<()> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = file.metadata()?.len();
+        self.file = Some(file);
+        Ok(())
+    }
+
+    /// Shifts `<path>.N` to `<path>.N+1`, dropping the oldest, and starts a
+    /// new file.
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file = None;
+        if self.max_files == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            for i in (1..self.max_files).rev() {
+                let from = rotated(&self.path, i);
+                if from.exists() {
+                    fs::rename(from, rotated(&self.path, i + 1))?;
+                }
+            }
+            fs::rename(&self.path, rotated(&self.path, 1))?;
+        }
+        self.open()
+    }
+}
+
+fn rotated(path: &Path, index: u32) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
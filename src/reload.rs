@@ -0,0 +1,135 @@
+use crate::config::{Config, try_load_config};
+use crate::model::{AppState, build_backend_clients, refresh_backends};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tracing::{error, info};
+
+/// Backends affected by a configuration reload.
+#[derive(Debug, Default, Serialize, PartialEq, Eq)]
+pub struct ReloadSummary {
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl ReloadSummary {
+    fn between(old: &Config, new: &Config) -> Self {
+        let mut summary = Self::default();
+        for backend in &new.backends {
+            match old.backends.iter().find(|b| b.name == backend.name) {
+                None => summary.added.push(backend.name.clone()),
+                Some(previous) if previous != backend => summary.changed.push(backend.name.clone()),
+                Some(_) => {}
+            }
+        }
+        for backend in &old.backends {
+            if !new.backends.iter().any(|b| b.name == backend.name) {
+                summary.removed.push(backend.name.clone());
+            }
+        }
+        summary
+    }
+}
+
+/// Reloads the configuration from `state.config_path`. The current
+/// configuration stays in place when the file cannot be loaded or is invalid.
+pub async fn reload_config(state: &AppState) -> Result<ReloadSummary, String> {
+    let Some(path) = state.config_path.clone() else {
+        return Err("the router was not started from a configuration file".to_string());
+    };
+    let config = try_load_config(path.as_path()).map_err(|err| err.to_string())?;
+
+    Ok(apply_config(state, config).await)
+}
+
+/// Atomically swaps in `config` and re-discovers added or changed backends.
+///
+/// Requests already in flight keep the configuration and HTTP client they
+/// started with, so open streams are not interrupted.
+pub async fn apply_config(state: &AppState, config: Config) -> ReloadSummary {
+    let _guard = state.reload_lock.lock().await;
+
+    let previous = state.config.load_full();
+    let summary = ReloadSummary::between(&previous, &config);
+    let rediscover: Vec<String> = summary
+        .added
+        .iter()
+        .chain(&summary.changed)
+        .cloned()
+        .collect();
+
+    let clients = build_backend_clients(&config, &state.backend_clients.load(), &rediscover);
+    state.backend_clients.store(Arc::new(clients));
+    state.config.store(Arc::new(config));
+
+    info!(
+        "Configuration reloaded: {} added, {} changed, {} removed backends",
+        summary.added.len(),
+        summary.changed.len(),
+        summary.removed.len()
+    );
+    if !rediscover.is_empty() || !summary.removed.is_empty() {
+        refresh_backends(state, &rediscover).await;
+    }
+    for backend in &summary.removed {
+        state.metrics.remove_backend(backend);
+    }
+    summary
+}
+
+/// Polls the configuration file and reloads it whenever it changes.
+pub async fn watch_config(state: AppState) {
+    let Some(path) = state.config_path.clone() else {
+        return;
+    };
+    let mut last_seen = fingerprint(&path);
+
+    loop {
+        let watch_interval = state.config.load().watch_interval;
+        if watch_interval == 0 {
+            // Watching is disabled; check again later in case a reload enables it.
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            continue;
+        }
+        tokio::time::sleep(Duration::from_secs(watch_interval)).await;
+
+        let current = fingerprint(&path);
+        if current == last_seen {
+            continue;
+        }
+        last_seen = current;
+
+        info!("{} changed, reloading configuration", path.display());
+        if let Err(err) = reload_config(&state).await {
+            error!("Keeping the current configuration: {}", err);
+        }
+    }
+}
+
+/// Reloads the configuration whenever the process receives SIGHUP.
+#[cfg(unix)]
+pub async fn reload_on_sighup(state: AppState) {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(err) => {
+            error!("Cannot listen for SIGHUP: {}", err);
+            return;
+        }
+    };
+
+    while hangup.recv().await.is_some() {
+        info!("Received SIGHUP, reloading configuration");
+        if let Err(err) = reload_config(&state).await {
+            error!("Keeping the current configuration: {}", err);
+        }
+    }
+}
+
+fn fingerprint(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
@@ -1,22 +1,44 @@
-use crate::config::AuthConfig;
+use crate::access_log::{REQUEST_ID, RequestLog};
+use crate::adapter::{self, Adapter};
+use crate::alias;
+use crate::auth;
+use crate::config::{BackendConfig, Strategy};
+use crate::error::RouterError;
+use crate::headers::{request_headers, response_headers};
 use crate::model::{AppState, ModelInfo};
+use crate::ratelimit::{
+    Limited, Permit, Scope, UsageRecorder, estimate_tokens, is_usage_event, request_stream_usage,
+};
+use crate::recorder::Capture;
+use crate::stream;
+use crate::telemetry::{self, UsageAttributes};
 use axum::{
     Json,
     body::Body,
     extract::State,
-    http::{HeaderMap, Response, StatusCode, header},
+    http::{self, HeaderMap, HeaderValue, Response, header},
+    response::IntoResponse,
 };
-use base64::Engine;
+use bytes::{Bytes, BytesMut};
+use futures_util::StreamExt;
 use http_body_util::BodyExt;
 use serde_json::Value;
 use std::collections::HashMap;
-use tracing::error;
+use std::time::Duration;
+use tracing::{Instrument, Span, error, warn};
 
+/// Lists the models the caller's API key may use.
 pub async fn list_models(
     State(state): State<AppState>,
-) -> Json<HashMap<&'static str, Vec<ModelInfo>>> {
-    let models = state.model_cache.read().await.clone();
-    Json(HashMap::from([("data", models)]))
+    headers: HeaderMap,
+) -> Result<Json<HashMap<&'static str, Vec<ModelInfo>>>, RouterError> {
+    let config = state.config.load();
+    let key = auth::authenticate(&config, &headers)?;
+    let models = alias::with_models(&config, &state.model_cache.read().await)
+        .into_iter()
+        .filter(|model| key.as_ref().is_none_or(|key| key.allows(&model.id)))
+        .collect();
+    Ok(Json(HashMap::from([("data", models)])))
 }
 
 pub async fn forward_request(
@@ -35,90 +57,539 @@ pub async fn forward_completion(
     forward(state, headers, req_body, "/v1/completions").await
 }
 
-async fn error_response(status: StatusCode, message: &str) -> Response<Body> {
-    Response::builder()
-        .status(status)
-        .header("Content-Type", "text/plain")
-        .body(Body::from(message.to_string()))
-        .unwrap()
+/// Routes a request for an OpenAI `endpoint` to a backend.
+pub(crate) async fn forward(
+    state: AppState,
+    headers: HeaderMap,
+    req_body: Body,
+    endpoint: &str,
+) -> Response<Body> {
+    let span = telemetry::request_span(&headers, telemetry::operation_name(endpoint));
+    let mut request = RequestLog::start(&state.metrics, &headers);
+    let response = route(&state, headers, req_body, endpoint, &mut request)
+        .instrument(span.clone())
+        .await;
+    telemetry::record_response(&span, &response);
+    request.finish(response)
 }
 
-async fn forward(
-    state: AppState,
-    mut headers: HeaderMap,
+async fn route(
+    state: &AppState,
+    headers: HeaderMap,
     req_body: Body,
     endpoint: &str,
+    request: &mut RequestLog,
 ) -> Response<Body> {
+    let config = state.config.load_full();
+    let key = match auth::authenticate(&config, &headers) {
+        Ok(key) => key,
+        Err(err) => return err.into_response(),
+    };
+    request.key = key.as_ref().map(|key| key.name.clone());
+
     let collected = req_body.collect().await.unwrap_or_default();
     let body_bytes = collected.to_bytes();
-    let json: Value = serde_json::from_slice(&body_bytes).unwrap_or_default();
-    let model = json.get("model").and_then(|v| v.as_str()).unwrap_or("");
-
-    let routing_table = state.routing_table.read().await;
-    if let Some(backend_url) = routing_table.get(model) {
-        let url = format!("{}{}", backend_url, endpoint);
-
-        // Find backend config to get auth settings
-        let backend_config = state.config.backends.iter().find(|b| b.url == *backend_url);
-
-        // Apply authentication if configured
-        if let Some(backend) = backend_config {
-            if let Some(auth) = &backend.auth {
-                match auth {
-                    AuthConfig::Bearer { token } => {
-                        headers.insert(
-                            header::AUTHORIZATION,
-                            format!("Bearer {}", token).parse().unwrap(),
-                        );
-                    }
-                    AuthConfig::Basic { username, password } => {
-                        let credentials = base64::engine::general_purpose::STANDARD
-                            .encode(format!("{}:{}", username, password));
-                        headers.insert(
-                            header::AUTHORIZATION,
-                            format!("Basic {}", credentials).parse().unwrap(),
-                        );
-                    }
-                    AuthConfig::CustomHeader { name, value } => {
-                        headers.insert(
-                            header::HeaderName::from_bytes(name.as_bytes()).unwrap(),
-                            value.parse().unwrap(),
-                        );
-                    }
+    request.request_bytes = body_bytes.len();
+    let mut json: Value = match serde_json::from_slice(&body_bytes) {
+        Ok(json) => json,
+        Err(err) => return RouterError::InvalidJson(err.to_string()).into_response(),
+    };
+    if let Some(recorder) = &state.recorder {
+        request.capture = recorder.sample(endpoint, &json);
+    }
+    // Token limits and metrics need the usage of streamed responses; the
+    // client only receives it if it asked for it.
+    let strip_usage = request_stream_usage(&mut json);
+    let body_bytes = if strip_usage {
+        Bytes::from(json.to_string())
+    } else {
+        body_bytes
+    };
+    let Some(model) = json.get("model").and_then(|v| v.as_str()) else {
+        return RouterError::MissingModel.into_response();
+    };
+    let span = Span::current();
+    telemetry::record_request(&span, telemetry::operation_name(endpoint), &json);
+    if let Err(err) = auth::authorize_model(key.as_ref(), model) {
+        return err.into_response();
+    }
+
+    // Aliases take precedence over discovered models of the same name.
+    let targets = alias::targets(&config, model);
+    let backend_names = match &targets {
+        Some(targets) => targets.keys().map(|name| name.to_string()).collect(),
+        None => state
+            .routing_table
+            .read()
+            .await
+            .get(model)
+            .cloned()
+            .unwrap_or_default(),
+    };
+    let candidates: Vec<&BackendConfig> = config
+        .backends
+        .iter()
+        .filter(|b| backend_names.contains(&b.name))
+        .collect();
+    if candidates.is_empty() {
+        return RouterError::ModelNotFound(model.to_string()).into_response();
+    }
+    let candidates: Vec<&BackendConfig> = candidates
+        .into_iter()
+        .filter(|b| adapter::for_backend(b).supports(endpoint))
+        .collect();
+    if candidates.is_empty() {
+        return RouterError::UnsupportedEndpoint(model.to_string()).into_response();
+    }
+    request.model = model.to_string();
+
+    let mut limits = Vec::new();
+    if let Some(key) = &key
+        && let Some(limit) = &key.rate_limit
+    {
+        limits.push((Scope::Key, key.name.as_str(), limit));
+    }
+    if let Some(limit) = config.rate_limits.models.get(model) {
+        limits.push((Scope::Model, model, limit));
+    }
+    let tokens = if limits.iter().any(|(_, _, l)| l.tokens_per_minute.is_some()) {
+        estimate_tokens(&json)
+    } else {
+        0
+    };
+    let permit = match state.rate_limiter.try_acquire(&limits, tokens) {
+        Ok(permit) => permit,
+        Err(limited) => {
+            warn!("Rate limit reached for {} {}", limited.scope, limited.id);
+            return limited.into_response();
+        }
+    };
+    let quotas = permit.quotas.clone();
+    let usage = permit.usage();
+    request.usage = Some(usage.clone());
+
+    let strategy = config.load_balancing.strategy_for(model);
+    let retry = &config.retry;
+    let max_attempts = retry.max_attempts.max(1);
+    let mut history = AttemptHistory::default();
+    let mut timed_out = false;
+
+    for attempt in 1..=max_attempts {
+        if attempt > 1 {
+            tokio::time::sleep(retry.backoff(attempt)).await;
+        }
+
+        // Prefer backends that have not failed this request yet.
+        let untried: Vec<&BackendConfig> = candidates
+            .iter()
+            .filter(|b| !history.contains(&b.name))
+            .copied()
+            .collect();
+        let pool = if untried.is_empty() {
+            candidates.clone()
+        } else {
+            untried
+        };
+        let (backend, backend_permit) = match pick_backend(state, model, strategy, pool) {
+            Ok(picked) => picked,
+            Err(Some(limited)) if history.is_empty() => {
+                warn!("Every backend serving {} is rate limited", model);
+                let mut response = limited.into_response();
+                for quota in &permit.refund() {
+                    quota.apply_to(&mut response);
                 }
+                return response;
             }
+            Err(_) => break,
+        };
+
+        let adapter = adapter::for_backend(backend);
+        let upstream_model = targets
+            .as_ref()
+            .and_then(|targets| targets.get(backend.name.as_str()));
+        let backend_json = upstream_model.map(|upstream| alias::rewrite_request(&json, upstream));
+        let backend_request = backend_json.as_ref().unwrap_or(&json);
+        let url = adapter.url(backend, endpoint, backend_request);
+        let in_flight = (
+            state.balancer.begin(&backend.name),
+            state.metrics.in_flight(model, &backend.name),
+        );
+        let mut backend_headers = request_headers(&headers, backend);
+        let backend_body = match adapter.translate_request(endpoint, backend_request) {
+            Some(translated) => Bytes::from(translated.to_string()),
+            None => match &backend_json {
+                Some(rewritten) => Bytes::from(rewritten.to_string()),
+                None => body_bytes.clone(),
+            },
+        };
+        if let Err(err) = adapter.apply_auth(&mut backend_headers, backend) {
+            error!("Invalid credentials for backend {}: {}", backend.name, err);
+            history.push(&backend.name, "error");
+            request.attempt(&backend.name, "error");
+            break;
         }
+        backend_headers.insert(REQUEST_ID, HeaderValue::from_str(&request.id).unwrap());
+        telemetry::record_backend(&span, &backend.name, &backend.url);
+        telemetry::inject_context(&span, &mut backend_headers);
 
-        match state
-            .client
-            .post(url)
-            .headers(headers.clone())
-            .body(body_bytes)
-            .send()
-            .await
-        {
+        let is_last = attempt == max_attempts;
+        match send_to_backend(state, backend, url, backend_headers, backend_body).await {
+            Ok(response)
+                if !is_last && retry.retry_on_status.contains(&response.status().as_u16()) =>
+            {
+                warn!(
+                    "Backend {} returned {} for model {}, retrying",
+                    backend.name,
+                    response.status(),
+                    model
+                );
+                history.push(&backend.name, response.status().as_str());
+                request.attempt(&backend.name, response.status().as_str());
+            }
             Ok(response) => {
-                let mut builder = Response::builder().status(response.status());
-                for (k, v) in response.headers() {
-                    builder = builder.header(k, v);
+                history.push(&backend.name, response.status().as_str());
+                request.attempt(&backend.name, response.status().as_str());
+                let attributes = UsageAttributes {
+                    span: span.clone(),
+                    usage: usage.clone(),
+                };
+                let guards = (in_flight, backend_permit, permit, attributes);
+                let capture = request.capture.clone();
+                let target = Target {
+                    backend,
+                    adapter,
+                    endpoint,
+                    request: backend_request,
+                    alias: upstream_model.map(|_| model),
+                    strip_usage,
+                };
+                let mut response =
+                    proxy_response(response, &target, &history, usage, capture, guards).await;
+                for quota in &quotas {
+                    quota.apply_to(&mut response);
                 }
-                let bytes = response.bytes().await.unwrap_or_default();
-                builder.body(Body::from(bytes)).unwrap()
+                return response;
             }
             Err(err) => {
-                error!("Forwarding failed: {}", err);
-                error_response(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Internal forwarding error",
-                )
-                .await
+                let retryable = match &err {
+                    UpstreamError::Timeout => retry.retry_on_timeout,
+                    UpstreamError::Connect(_) => retry.retry_on_connect_error,
+                    UpstreamError::Other(_) => false,
+                };
+                history.push(&backend.name, err.outcome());
+                request.attempt(&backend.name, err.outcome());
+                timed_out = matches!(err, UpstreamError::Timeout);
+                if is_last || !retryable {
+                    error!("Forwarding to {} failed: {}", backend.name, err);
+                    break;
+                }
+                warn!("Forwarding to {} failed: {}, retrying", backend.name, err);
             }
         }
+    }
+
+    let err = if timed_out {
+        RouterError::Timeout("The backend did not respond in time.".to_string())
     } else {
-        error_response(StatusCode::BAD_REQUEST, "Unknown model").await
+        RouterError::Upstream("The backend could not process the request.".to_string())
+    };
+    let mut response = history.apply_to(err.into_response());
+    for quota in &quotas {
+        quota.apply_to(&mut response);
+    }
+    response
+}
+
+/// Picks a backend from `pool` that is within its rate limit. Fails with the
+/// limit that frees up first when every backend is saturated.
+fn pick_backend<'a>(
+    state: &AppState,
+    model: &str,
+    strategy: Strategy,
+    mut pool: Vec<&'a BackendConfig>,
+) -> Result<(&'a BackendConfig, Permit), Option<Limited>> {
+    let mut limited: Option<Limited> = None;
+    while let Some(backend) = state.balancer.pick(model, strategy, &pool) {
+        let limits: Vec<_> = backend
+            .rate_limit
+            .iter()
+            .map(|limit| (Scope::Backend, backend.name.as_str(), limit))
+            .collect();
+        match state.rate_limiter.try_acquire(&limits, 0) {
+            Ok(permit) => return Ok((backend, permit)),
+            Err(err) => {
+                if limited
+                    .as_ref()
+                    .is_none_or(|l| err.retry_after < l.retry_after)
+                {
+                    limited = Some(err);
+                }
+                pool.retain(|b| b.name != backend.name);
+            }
+        }
+    }
+    Err(limited)
+}
+
+enum UpstreamError {
+    Timeout,
+    Connect(reqwest::Error),
+    Other(reqwest::Error),
+}
+
+impl UpstreamError {
+    fn outcome(&self) -> &'static str {
+        match self {
+            UpstreamError::Timeout => "timeout",
+            UpstreamError::Connect(_) => "connect_error",
+            UpstreamError::Other(_) => "error",
+        }
     }
 }
 
+impl From<reqwest::Error> for UpstreamError {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_timeout() {
+            UpstreamError::Timeout
+        } else if err.is_connect() {
+            UpstreamError::Connect(err)
+        } else {
+            UpstreamError::Other(err)
+        }
+    }
+}
+
+impl std::fmt::Display for UpstreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpstreamError::Timeout => write!(f, "timed out"),
+            UpstreamError::Connect(err) | UpstreamError::Other(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+/// Sends the request and waits for response headers, honouring the
+/// backend's request and first-byte timeouts.
+async fn send_to_backend(
+    state: &AppState,
+    backend: &BackendConfig,
+    url: String,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<reqwest::Response, UpstreamError> {
+    let mut request = state
+        .client_for(backend)
+        .post(url)
+        .headers(headers)
+        .body(body);
+    if let Some(timeout) = backend.timeouts.request() {
+        request = request.timeout(timeout);
+    }
+
+    match backend.timeouts.first_byte() {
+        Some(timeout) => match tokio::time::timeout(timeout, request.send()).await {
+            Ok(result) => Ok(result?),
+            Err(_) => Err(UpstreamError::Timeout),
+        },
+        None => Ok(request.send().await?),
+    }
+}
+
+/// The backend a request was sent to and what it was asked for.
+struct Target<'a> {
+    backend: &'a BackendConfig,
+    adapter: &'static dyn Adapter,
+    endpoint: &'a str,
+    request: &'a Value,
+    /// The alias the client asked for, if any.
+    alias: Option<&'a str>,
+    /// Whether to drop the usage chunk of a stream.
+    strip_usage: bool,
+}
+
+async fn proxy_response(
+    response: reqwest::Response,
+    target: &Target<'_>,
+    history: &AttemptHistory,
+    usage: UsageRecorder,
+    capture: Option<Capture>,
+    guard: impl Send + 'static,
+) -> Response<Body> {
+    let backend = target.backend;
+    let status = response.status();
+    let mut headers = response_headers(response.headers(), backend);
+    let idle_timeout = backend.timeouts.idle();
+
+    if target.adapter.is_stream(response.headers()) {
+        let mut upstream = response.bytes_stream().boxed();
+        if let Some(translator) = target
+            .adapter
+            .stream_translator(target.endpoint, target.request)
+        {
+            upstream = adapter::translate_stream(upstream, translator);
+            headers.insert(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("text/event-stream"),
+            );
+        }
+        if let Some(alias) = target.alias {
+            upstream =
+                adapter::translate_stream(upstream, Box::new(alias::StreamRewriter::new(alias)));
+        }
+        let strip_usage = target.strip_usage;
+        return history
+            .apply(response_builder(status, &headers))
+            .header("X-Accel-Buffering", "no")
+            .body(stream::sse_body(
+                upstream,
+                idle_timeout,
+                move |event: &[u8]| {
+                    usage.record_sse_event(event);
+                    if let Some(capture) = &capture {
+                        capture.record_sse_event(event);
+                    }
+                    !(strip_usage && is_usage_event(event))
+                },
+                guard,
+            ))
+            .unwrap();
+    }
+
+    match read_body(response, idle_timeout).await {
+        Ok(bytes) => {
+            let translated = if status.is_success() {
+                target
+                    .adapter
+                    .translate_response(target.endpoint, target.request, &bytes)
+            } else {
+                target.adapter.translate_error(&bytes)
+            };
+            let bytes = match translated {
+                Some(translated) => {
+                    headers.insert(
+                        header::CONTENT_TYPE,
+                        HeaderValue::from_static("application/json"),
+                    );
+                    translated
+                }
+                None => bytes,
+            };
+            let bytes = match target.alias {
+                Some(alias) if status.is_success() => {
+                    alias::rewrite_response(&bytes, alias).unwrap_or(bytes)
+                }
+                _ => bytes,
+            };
+            usage.record_json(&bytes);
+            if let Some(capture) = &capture {
+                capture.record_body(&bytes);
+            }
+            history
+                .apply(response_builder(status, &headers))
+                .body(Body::from(bytes))
+                .unwrap()
+        }
+        Err(UpstreamError::Timeout) => {
+            error!("Reading response from {} timed out", backend.name);
+            let err = RouterError::Timeout(
+                "The backend timed out while sending the response.".to_string(),
+            );
+            history.apply_to(err.into_response())
+        }
+        Err(err) => {
+            error!("Reading response from {} failed: {}", backend.name, err);
+            let err = RouterError::Upstream("The backend response could not be read.".to_string());
+            history.apply_to(err.into_response())
+        }
+    }
+}
+
+async fn read_body(
+    response: reqwest::Response,
+    idle_timeout: Option<Duration>,
+) -> Result<Bytes, UpstreamError> {
+    let Some(idle_timeout) = idle_timeout else {
+        return Ok(response.bytes().await?);
+    };
+
+    let mut body = BytesMut::new();
+    let mut chunks = response.bytes_stream();
+    loop {
+        match tokio::time::timeout(idle_timeout, chunks.next()).await {
+            Ok(Some(chunk)) => body.extend_from_slice(&chunk?),
+            Ok(None) => return Ok(body.freeze()),
+            Err(_) => return Err(UpstreamError::Timeout),
+        }
+    }
+}
+
+fn response_builder(status: http::StatusCode, headers: &HeaderMap) -> http::response::Builder {
+    let mut builder = Response::builder().status(status);
+    for (k, v) in headers {
+        builder = builder.header(k, v);
+    }
+    builder
+}
+
+/// Backends tried for a single request and how each attempt ended, exposed
+/// to clients as `x-router-attempts` / `x-router-attempt-history`.
+#[derive(Default)]
+struct AttemptHistory {
+    attempts: Vec<(String, String)>,
+}
+
+impl AttemptHistory {
+    fn push(&mut self, backend: &str, outcome: &str) {
+        self.attempts
+            .push((backend.to_string(), outcome.to_string()));
+    }
+
+    fn is_empty(&self) -> bool {
+        self.attempts.is_empty()
+    }
+
+    fn contains(&self, backend: &str) -> bool {
+        self.attempts.iter().any(|(name, _)| name == backend)
+    }
+
+    fn headers(&self) -> Vec<(&'static str, HeaderValue)> {
+        let history = self
+            .attempts
+            .iter()
+            .map(|(backend, outcome)| format!("{}={}", backend, outcome))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut headers = vec![("x-router-attempts", HeaderValue::from(self.attempts.len()))];
+        if let Ok(value) = HeaderValue::from_str(&history) {
+            headers.push(("x-router-attempt-history", value));
+        }
+        headers
+    }
+
+    fn apply(&self, mut builder: http::response::Builder) -> http::response::Builder {
+        for (name, value) in self.headers() {
+            builder = builder.header(name, value);
+        }
+        builder
+    }
+
+    fn apply_to(&self, mut response: Response<Body>) -> Response<Body> {
+        for (name, value) in self.headers() {
+            response.headers_mut().insert(name, value);
+        }
+        response
+    }
+}
+
+/// Serves the Prometheus metrics.
+pub async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)],
+        state.metrics.render(),
+    )
+}
+
 pub async fn healthz() -> &'static str {
     "OK"
 }
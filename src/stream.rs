@@ -0,0 +1,200 @@
+use crate::error::RouterError;
+use axum::body::Body;
+use axum::http::{HeaderMap, header};
+use bytes::{Bytes, BytesMut};
+use futures_util::stream::{BoxStream, Stream, StreamExt};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::time::{Instant, Sleep};
+
+pub fn is_event_stream(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim_start().starts_with("text/event-stream"))
+        .unwrap_or(false)
+}
+
+/// Splits an incoming byte stream into complete Server-Sent Events.
+///
+/// Every returned chunk holds exactly one event including its terminating
+/// blank line, so downstream consumers never see a half-written `data:` line.
+#[derive(Debug, Default)]
+pub struct SseFramer {
+    buf: BytesMut,
+}
+
+impl SseFramer {
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<Bytes> {
+        self.buf.extend_from_slice(chunk);
+
+        let mut events = Vec::new();
+        while let Some(end) = find_event_end(&self.buf) {
+            events.push(self.buf.split_to(end).freeze());
+        }
+        events
+    }
+
+    /// Returns whatever is left once the upstream body has ended.
+    pub fn finish(&mut self) -> Option<Bytes> {
+        if self.buf.is_empty() {
+            None
+        } else {
+            Some(self.buf.split().freeze())
+        }
+    }
+}
+
+fn find_event_end(buf: &[u8]) -> Option<usize> {
+    let mut i = 0;
+    while i < buf.len() {
+        match buf[i] {
+            b'\n' if buf.get(i + 1) == Some(&b'\n') => return Some(i + 2),
+            b'\n' if buf[i + 1..].starts_with(b"\r\n") => return Some(i + 3),
+            b'\r' if buf[i + 1..].starts_with(b"\n\r\n") => return Some(i + 4),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+type Inspector = Box<dyn FnMut(&[u8]) -> bool + Send>;
+
+/// Re-frames an upstream byte stream on SSE event boundaries.
+pub struct SseStream {
+    inner: BoxStream<'static, Result<Bytes, reqwest::Error>>,
+    guard: Option<Box<dyn Send>>,
+    inspector: Option<Inspector>,
+    idle_timeout: Option<Duration>,
+    idle: Option<Pin<Box<Sleep>>>,
+    framer: SseFramer,
+    pending: VecDeque<Bytes>,
+    done: bool,
+}
+
+impl SseStream {
+    pub fn new(inner: BoxStream<'static, Result<Bytes, reqwest::Error>>) -> Self {
+        Self {
+            inner,
+            guard: None,
+            inspector: None,
+            idle_timeout: None,
+            idle: None,
+            framer: SseFramer::default(),
+            pending: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Keeps `guard` alive until the stream is finished or dropped.
+    pub fn with_guard(mut self, guard: impl Send + 'static) -> Self {
+        self.guard = Some(Box::new(guard));
+        self
+    }
+
+    /// Calls `inspector` with every event, which is passed on only if it
+    /// returns `true`.
+    pub fn with_inspector(mut self, inspector: impl FnMut(&[u8]) -> bool + Send + 'static) -> Self {
+        self.inspector = Some(Box::new(inspector));
+        self
+    }
+
+    /// Ends the stream with an error event when the upstream stays silent
+    /// for longer than `timeout`.
+    pub fn with_idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    fn inspect_events(&mut self, events: Vec<Bytes>) {
+        for event in events {
+            if self
+                .inspector
+                .as_mut()
+                .is_none_or(|inspector| inspector(&event))
+            {
+                self.pending.push_back(event);
+            }
+        }
+    }
+
+    fn idle_expired(&mut self, cx: &mut Context<'_>) -> bool {
+        let Some(timeout) = self.idle_timeout else {
+            return false;
+        };
+        let idle = self
+            .idle
+            .get_or_insert_with(|| Box::pin(tokio::time::sleep(timeout)));
+        idle.as_mut().poll(cx).is_ready()
+    }
+
+    fn reset_idle(&mut self) {
+        if let (Some(timeout), Some(idle)) = (self.idle_timeout, self.idle.as_mut()) {
+            idle.as_mut().reset(Instant::now() + timeout);
+        }
+    }
+}
+
+impl Stream for SseStream {
+    type Item = Result<Bytes, reqwest::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Poll::Ready(Some(Ok(event)));
+            }
+            if self.done {
+                self.guard = None;
+                return Poll::Ready(None);
+            }
+
+            match self.inner.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(chunk))) => {
+                    self.reset_idle();
+                    let events = self.framer.push(&chunk);
+                    self.inspect_events(events);
+                }
+                Poll::Ready(Some(Err(err))) => {
+                    self.done = true;
+                    return Poll::Ready(Some(Err(err)));
+                }
+                Poll::Ready(None) => {
+                    self.done = true;
+                    if let Some(rest) = self.framer.finish() {
+                        self.inspect_events(vec![rest]);
+                    }
+                }
+                Poll::Pending if self.idle_expired(cx) => {
+                    self.done = true;
+                    let err = RouterError::Timeout(
+                        "The backend stream was idle for too long.".to_string(),
+                    );
+                    self.pending.push_back(Bytes::from(err.to_sse_event()));
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// Streams an upstream `text/event-stream` body to the client event by
+/// event. Dropping the returned body (e.g. when the client disconnects) drops
+/// the upstream response and with it the backend connection. `inspector`
+/// sees every event and drops it by returning `false`; `guard` is released
+/// once the stream ends.
+pub fn sse_body(
+    upstream: BoxStream<'static, Result<Bytes, reqwest::Error>>,
+    idle_timeout: Option<Duration>,
+    inspector: impl FnMut(&[u8]) -> bool + Send + 'static,
+    guard: impl Send + 'static,
+) -> Body {
+    Body::from_stream(
+        SseStream::new(upstream)
+            .with_idle_timeout(idle_timeout)
+            .with_inspector(inspector)
+            .with_guard(guard),
+    )
+}
@@ -0,0 +1,215 @@
+use crate::config::{OtlpProtocol, TracingConfig};
+use crate::error::ErrorCode;
+use crate::ratelimit::UsageRecorder;
+use axum::body::Body;
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Response};
+use opentelemetry::propagation::{Extractor, Injector, TextMapPropagator};
+use opentelemetry::trace::{TraceContextExt, TracerProvider};
+use opentelemetry_otlp::{Protocol, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{SdkTracer, SdkTracerProvider};
+use serde_json::Value;
+use tracing::field::Empty;
+use tracing::{Span, Subscriber, info_span};
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Builds the tracer provider exporting spans to the configured OTLP
+/// collector, or `None` when no endpoint is set. Must be called from within
+/// a Tokio runtime.
+pub fn init_tracer(config: &TracingConfig) -> Result<Option<SdkTracerProvider>, String> {
+    let Some(endpoint) = &config.endpoint else {
+        return Ok(None);
+    };
+    let exporter = match config.protocol {
+        OtlpProtocol::Grpc => SpanExporter::builder()
+            .with_tonic()
+            .with_endpoint(endpoint)
+            .build(),
+        OtlpProtocol::Http => SpanExporter::builder()
+            .with_http()
+            .with_protocol(Protocol::HttpBinary)
+            .with_endpoint(traces_url(endpoint))
+            .build(),
+    }
+    .map_err(|err| {
+        format!(
+            "failed to create the OTLP exporter for {}: {}",
+            endpoint, err
+        )
+    })?;
+
+    let resource = Resource::builder()
+        .with_service_name(config.service_name.clone())
+        .build();
+    Ok(Some(
+        SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(resource)
+            .build(),
+    ))
+}
+
+/// OTLP/HTTP collectors accept traces at `/v1/traces` below their base URL.
+fn traces_url(endpoint: &str) -> String {
+    if endpoint.ends_with("/v1/traces") {
+        endpoint.to_string()
+    } else {
+        format!("{}/v1/traces", endpoint.trim_end_matches('/'))
+    }
+}
+
+/// A `tracing` layer turning spans into OpenTelemetry spans of `provider`.
+pub fn layer<S>(provider: &SdkTracerProvider) -> OpenTelemetryLayer<S, SdkTracer>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    tracing_opentelemetry::layer().with_tracer(provider.tracer("llm-router"))
+}
+
+/// The GenAI operation name for an OpenAI-compatible endpoint.
+pub fn operation_name(endpoint: &str) -> &'static str {
+    match endpoint {
+        "/v1/chat/completions" => "chat",
+        _ => "text_completion",
+    }
+}
+
+/// Span covering one client request until its response body ends, with the
+/// GenAI semantic-convention attributes left to be recorded. It continues
+/// the trace of the client's `traceparent` header, if any.
+pub fn request_span(headers: &HeaderMap, operation: &'static str) -> Span {
+    let span = info_span!(
+        "request",
+        otel.name = operation,
+        otel.kind = "server",
+        otel.status_code = Empty,
+        gen_ai.operation.name = operation,
+        gen_ai.provider.name = "openai",
+        gen_ai.request.model = Empty,
+        gen_ai.request.max_tokens = Empty,
+        gen_ai.request.temperature = Empty,
+        gen_ai.request.top_p = Empty,
+        gen_ai.usage.input_tokens = Empty,
+        gen_ai.usage.output_tokens = Empty,
+        llm_router.backend = Empty,
+        server.address = Empty,
+        server.port = Empty,
+        http.response.status_code = Empty,
+        error.type = Empty,
+    );
+    let parent = TraceContextPropagator::new().extract(&HeaderExtractor(headers));
+    // Only fails when no OpenTelemetry layer is installed.
+    let _ = span.set_parent(parent);
+    span
+}
+
+/// Records the requested model and sampling parameters, and names the span
+/// `{operation} {model}` as the conventions ask.
+pub fn record_request(span: &Span, operation: &str, request: &Value) {
+    let Some(model) = request.get("model").and_then(Value::as_str) else {
+        return;
+    };
+    // The OpenTelemetry span has started by now, so `otel.name` would no
+    // longer apply.
+    span.context()
+        .span()
+        .update_name(format!("{} {}", operation, model));
+    span.record("gen_ai.request.model", model);
+    if let Some(max_tokens) = request
+        .get("max_tokens")
+        .or_else(|| request.get("max_completion_tokens"))
+        .and_then(Value::as_u64)
+    {
+        span.record("gen_ai.request.max_tokens", max_tokens);
+    }
+    for (field, name) in [
+        ("temperature", "gen_ai.request.temperature"),
+        ("top_p", "gen_ai.request.top_p"),
+    ] {
+        if let Some(value) = request.get(field).and_then(Value::as_f64) {
+            span.record(name, value);
+        }
+    }
+}
+
+/// Records the backend an attempt is sent to.
+pub fn record_backend(span: &Span, name: &str, url: &str) {
+    span.record("llm_router.backend", name);
+    if let Ok(url) = reqwest::Url::parse(url) {
+        if let Some(host) = url.host_str() {
+            span.record("server.address", host);
+        }
+        if let Some(port) = url.port_or_known_default() {
+            span.record("server.port", port);
+        }
+    }
+}
+
+/// Records the status of the response returned to the client. Error
+/// responses mark the span as failed, with the error code as `error.type`.
+pub fn record_response(span: &Span, response: &Response<Body>) {
+    let status = response.status();
+    span.record("http.response.status_code", status.as_u16());
+    let error_type = match response.extensions().get::<ErrorCode>() {
+        Some(ErrorCode(code)) => Some(code.to_string()),
+        None if status.is_server_error() => Some(status.as_u16().to_string()),
+        None => None,
+    };
+    if let Some(error_type) = error_type {
+        span.record("error.type", error_type);
+        span.record("otel.status_code", "ERROR");
+    }
+}
+
+/// Adds the W3C `traceparent` (and `tracestate`) of `span` to the headers
+/// of an upstream request, replacing any sent by the client. Nothing is
+/// added when tracing is disabled, so the client's headers pass through.
+pub fn inject_context(span: &Span, headers: &mut HeaderMap) {
+    TraceContextPropagator::new().inject_context(&span.context(), &mut HeaderInjector(headers));
+}
+
+/// Records the token usage reported by the backend on the request span
+/// when dropped. Kept alive with the response body so the span ends
+/// with the stream.
+pub struct UsageAttributes {
+    pub span: Span,
+    pub usage: UsageRecorder,
+}
+
+impl Drop for UsageAttributes {
+    fn drop(&mut self) {
+        if let Some(usage) = self.usage.usage() {
+            self.span
+                .record("gen_ai.usage.input_tokens", usage.prompt_tokens);
+            self.span
+                .record("gen_ai.usage.output_tokens", usage.completion_tokens);
+        }
+    }
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(HeaderName::as_str).collect()
+    }
+}
+
+struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(key.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            self.0.insert(name, value);
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, Response, StatusCode},
+    routing::post,
+};
+use http_body_util::BodyExt;
+use llm_router::{
+    access_log,
+    auth::hash_key,
+    config::{BackendConfig, ClientAuthConfig, ClientKeyConfig, Config},
+    model::AppState,
+    router::forward_request,
+};
+use serde_json::{Value, json};
+use std::io;
+use std::sync::{Arc, Mutex};
+use tower::ServiceExt;
+use tracing::subscriber::DefaultGuard;
+use tracing_subscriber::{EnvFilter, Layer, prelude::*};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Collects everything the test subscriber writes.
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Captured {
+    fn access_log(&self) -> Vec<Value> {
+        let output = self.0.lock().unwrap();
+        String::from_utf8_lossy(&output)
+            .lines()
+            .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+            .filter(|record| record["target"] == "access_log")
+            .map(|record| record["fields"].clone())
+            .collect()
+    }
+}
+
+fn capture_logs() -> (Captured, DefaultGuard) {
+    let captured = Captured::default();
+    let writer = captured.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .json()
+        .with_writer(move || writer.clone())
+        .finish();
+    (captured, tracing::subscriber::set_default(subscriber))
+}
+
+async fn setup_app(backend: &MockServer) -> Router {
+    let config = Config {
+        refresh_interval: 300,
+        backends: vec![BackendConfig {
+            name: "primary".to_string(),
+            url: backend.uri(),
+            ..Default::default()
+        }],
+        client_auth: ClientAuthConfig {
+            keys: vec![ClientKeyConfig {
+                name: "team-a".to_string(),
+                key_sha256: hash_key("sk-team-a"),
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let state = AppState::new(config);
+    state
+        .routing_table
+        .write()
+        .await
+        .insert("test-model".to_string(), vec!["primary".to_string()]);
+    Router::new()
+        .route("/v1/chat/completions", post(forward_request))
+        .with_state(state)
+}
+
+async fn chat(app: Router, request_id: Option<&str>) -> Response<Body> {
+    let mut request = Request::builder()
+        .method("POST")
+        .uri("/v1/chat/completions")
+        .header("Content-Type", "application/json")
+        .header("Authorization", "Bearer sk-team-a");
+    if let Some(id) = request_id {
+        request = request.header("x-request-id", id);
+    }
+    let body = json!({"model": "test-model"}).to_string();
+    app.oneshot(request.body(Body::from(body)).unwrap())
+        .await
+        .unwrap()
+}
+
+async fn ok_backend() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "ok",
+            "usage": {"prompt_tokens": 12, "completion_tokens": 30, "total_tokens": 42}
+        })))
+        .mount(&server)
+        .await;
+    server
+}
+
+#[tokio::test]
+async fn test_client_request_id_is_forwarded_and_returned() {
+    let backend = ok_backend().await;
+    let app = setup_app(&backend).await;
+
+    let response = chat(app, Some("req-123")).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-request-id"], "req-123");
+    let received = backend.received_requests().await.unwrap();
+    assert_eq!(received[0].headers["x-request-id"], "req-123");
+}
+
+#[tokio::test]
+async fn test_request_id_is_generated() {
+    let backend = ok_backend().await;
+    let app = setup_app(&backend).await;
+
+    let response = chat(app, None).await;
+    let id = response.headers()["x-request-id"].to_str().unwrap();
+    assert_eq!(id.len(), 36);
+    let received = backend.received_requests().await.unwrap();
+    assert_eq!(received[0].headers["x-request-id"], id);
+}
+
+#[tokio::test]
+async fn test_access_log_record() {
+    let (captured, _guard) = capture_logs();
+
+    let backend = ok_backend().await;
+    let app = setup_app(&backend).await;
+    let response = chat(app, Some("req-456")).await;
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+
+    let records = captured.access_log();
+    assert_eq!(records.len(), 1);
+    let record = &records[0];
+    assert_eq!(record["request_id"], "req-456");
+    assert_eq!(record["key"], "team-a");
+    assert_eq!(record["model"], "test-model");
+    assert_eq!(record["backend"], "primary");
+    assert_eq!(record["status"], 200);
+    assert_eq!(record["attempts"], 1);
+    assert_eq!(record["prompt_tokens"], 12);
+    assert_eq!(record["completion_tokens"], 30);
+    assert_eq!(record["request_bytes"], 22);
+    assert_eq!(record["response_bytes"], body.len());
+    assert!(record["latency_ms"].as_f64().unwrap() > 0.0);
+    // Only streamed responses have a first token time.
+    assert!(record.get("ttft_ms").is_none());
+}
+
+#[tokio::test]
+async fn test_access_log_is_written_when_stream_ends() {
+    let (captured, _guard) = capture_logs();
+
+    let backend = MockServer::start().await;
+    let sse = concat!(
+        "data: {\"choices\":[{\"delta\":{\"content\":\"hi\"}}]}\n\n",
+        "data: [DONE]\n\n",
+    );
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(sse, "text/event-stream"))
+        .mount(&backend)
+        .await;
+    let app = setup_app(&backend).await;
+
+    let response = chat(app, None).await;
+    let id = response.headers()["x-request-id"].clone();
+    assert!(captured.access_log().is_empty());
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let records = captured.access_log();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["request_id"], id.to_str().unwrap());
+    assert_eq!(records[0]["response_bytes"], body.len());
+    assert!(records[0]["ttft_ms"].as_f64().is_some());
+}
+
+#[tokio::test]
+async fn test_access_log_has_its_own_layer() {
+    let (text, json) = (Captured::default(), Captured::default());
+    let (text_writer, json_writer) = (text.clone(), json.clone());
+    let subscriber = tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(move || text_writer.clone())
+                .with_filter(access_log::exclude(EnvFilter::new("warn,access_log=info"))),
+        )
+        .with(access_log::layer(move || json_writer.clone()));
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let backend = ok_backend().await;
+    let app = setup_app(&backend).await;
+    let response = chat(app, Some("req-789")).await;
+    response.into_body().collect().await.unwrap();
+
+    let records = json.access_log();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["request_id"], "req-789");
+    assert!(text.0.lock().unwrap().is_empty());
+}
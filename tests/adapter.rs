@@ -0,0 +1,630 @@
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode},
+    routing::{get, post},
+};
+use http_body_util::BodyExt;
+use llm_router::{
+    config::{AuthConfig, BackendConfig, BackendKind, Config},
+    forward_completion, forward_request, list_models,
+    model::{AppState, refresh_models},
+};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+use wiremock::matchers::{header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn backend(server: &MockServer, kind: BackendKind) -> BackendConfig {
+    BackendConfig {
+        name: "primary".to_string(),
+        url: server.uri(),
+        kind,
+        auth: Some(AuthConfig::Bearer {
+            token: "secret".to_string(),
+        }),
+        ..Default::default()
+    }
+}
+
+async fn setup_app(backend: BackendConfig) -> Router {
+    let state = AppState::new(Config {
+        refresh_interval: 300,
+        backends: vec![backend],
+        ..Default::default()
+    });
+    state
+        .routing_table
+        .write()
+        .await
+        .insert("test-model".to_string(), vec!["primary".to_string()]);
+    Router::new()
+        .route("/v1/chat/completions", post(forward_request))
+        .route("/v1/completions", post(forward_completion))
+        .with_state(state)
+}
+
+async fn send(app: Router, uri: &str, body: Value) -> (StatusCode, String, String) {
+    let request = Request::builder()
+        .method("POST")
+        .uri(uri)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get("content-type")
+        .map(|value| value.to_str().unwrap().to_string())
+        .unwrap_or_default();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (
+        status,
+        content_type,
+        String::from_utf8(body.to_vec()).unwrap(),
+    )
+}
+
+/// Parses the `data:` events of an OpenAI stream, without `[DONE]`.
+fn chunks(body: &str) -> Vec<Value> {
+    body.split("\n\n")
+        .filter_map(|event| event.strip_prefix("data: "))
+        .filter(|data| *data != "[DONE]")
+        .map(|data| serde_json::from_str(data).unwrap())
+        .collect()
+}
+
+fn streamed_content(chunks: &[Value]) -> String {
+    chunks
+        .iter()
+        .filter_map(|chunk| chunk["choices"][0]["delta"]["content"].as_str())
+        .collect()
+}
+
+async fn sent_request(server: &MockServer) -> Value {
+    let received = server.received_requests().await.unwrap();
+    serde_json::from_slice(&received[0].body).unwrap()
+}
+
+#[test]
+fn test_backend_kind_config() {
+    let backend: BackendConfig =
+        serde_yml::from_str("name: claude\nurl: https://api.anthropic.com\nkind: anthropic")
+            .unwrap();
+    assert_eq!(backend.kind, BackendKind::Anthropic);
+    let backend: BackendConfig = serde_yml::from_str("name: vllm\nurl: http://vllm:8000").unwrap();
+    assert_eq!(backend.kind, BackendKind::OpenAi);
+    assert!(serde_yml::from_str::<BackendConfig>("name: x\nurl: http://x\nkind: cohere").is_err());
+}
+
+#[tokio::test]
+async fn test_ollama_chat() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/chat"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "model": "test-model",
+            "message": {"role": "assistant", "content": "Hello!"},
+            "done": true,
+            "done_reason": "stop",
+            "prompt_eval_count": 5,
+            "eval_count": 2
+        })))
+        .mount(&server)
+        .await;
+    let app = setup_app(backend(&server, BackendKind::Ollama)).await;
+
+    let (status, content_type, body) = send(
+        app,
+        "/v1/chat/completions",
+        json!({
+            "model": "test-model",
+            "max_tokens": 10,
+            "temperature": 0.2,
+            "messages": [{"role": "user", "content": "Hi"}]
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type, "application/json");
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["object"], "chat.completion");
+    assert_eq!(body["model"], "test-model");
+    assert_eq!(body["choices"][0]["message"]["content"], "Hello!");
+    assert_eq!(body["choices"][0]["finish_reason"], "stop");
+    assert_eq!(body["usage"]["total_tokens"], 7);
+
+    let sent = sent_request(&server).await;
+    assert_eq!(sent["stream"], false);
+    assert_eq!(
+        sent["options"],
+        json!({"temperature": 0.2, "num_predict": 10})
+    );
+    assert_eq!(sent["messages"], json!([{"role": "user", "content": "Hi"}]));
+}
+
+#[tokio::test]
+async fn test_ollama_stream() {
+    let server = MockServer::start().await;
+    let ndjson = [
+        json!({"model": "test-model", "response": "Once", "done": false}),
+        json!({"model": "test-model", "response": " upon", "done": false}),
+        json!({"model": "test-model", "response": "", "done": true, "done_reason": "length", "prompt_eval_count": 3, "eval_count": 2}),
+    ]
+    .iter()
+    .map(|line| format!("{}\n", line))
+    .collect::<String>();
+    Mock::given(method("POST"))
+        .and(path("/api/generate"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(ndjson, "application/x-ndjson"))
+        .mount(&server)
+        .await;
+    let app = setup_app(backend(&server, BackendKind::Ollama)).await;
+
+    let (status, content_type, body) = send(
+        app,
+        "/v1/completions",
+        json!({"model": "test-model", "prompt": "Tell a story", "stream": true}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type, "text/event-stream");
+    assert!(body.ends_with("data: [DONE]\n\n"));
+    let chunks = chunks(&body);
+    assert!(chunks.iter().all(|c| c["object"] == "text_completion"));
+    let text: String = chunks
+        .iter()
+        .filter_map(|chunk| chunk["choices"][0]["text"].as_str())
+        .collect();
+    assert_eq!(text, "Once upon");
+    assert_eq!(
+        chunks.last().unwrap()["choices"][0]["finish_reason"],
+        "length"
+    );
+    assert_eq!(sent_request(&server).await["prompt"], "Tell a story");
+}
+
+#[tokio::test]
+async fn test_anthropic_chat() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .and(header("x-api-key", "secret"))
+        .and(header("anthropic-version", "2023-06-01"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "msg_1",
+            "type": "message",
+            "role": "assistant",
+            "content": [
+                {"type": "text", "text": "Checking."},
+                {"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {"city": "Paris"}}
+            ],
+            "stop_reason": "tool_use",
+            "usage": {"input_tokens": 12, "output_tokens": 8}
+        })))
+        .mount(&server)
+        .await;
+    let app = setup_app(backend(&server, BackendKind::Anthropic)).await;
+
+    let (status, _, body) = send(
+        app,
+        "/v1/chat/completions",
+        json!({
+            "model": "test-model",
+            "messages": [
+                {"role": "system", "content": "Be brief."},
+                {"role": "user", "content": "Weather in Paris?"}
+            ],
+            "tools": [{"type": "function", "function": {"name": "get_weather", "parameters": {"type": "object"}}}]
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let body: Value = serde_json::from_str(&body).unwrap();
+    let message = &body["choices"][0]["message"];
+    assert_eq!(message["content"], "Checking.");
+    assert_eq!(message["tool_calls"][0]["id"], "toolu_1");
+    assert_eq!(
+        message["tool_calls"][0]["function"]["arguments"],
+        "{\"city\":\"Paris\"}"
+    );
+    assert_eq!(body["choices"][0]["finish_reason"], "tool_calls");
+    assert_eq!(body["usage"]["prompt_tokens"], 12);
+
+    let sent = sent_request(&server).await;
+    assert_eq!(sent["system"], "Be brief.");
+    assert_eq!(sent["max_tokens"], 4096);
+    assert_eq!(
+        sent["messages"],
+        json!([{"role": "user", "content": [{"type": "text", "text": "Weather in Paris?"}]}])
+    );
+    assert_eq!(
+        sent["tools"],
+        json!([{"name": "get_weather", "input_schema": {"type": "object"}}])
+    );
+}
+
+#[tokio::test]
+async fn test_anthropic_stream() {
+    let server = MockServer::start().await;
+    let events = [
+        ("message_start", json!({"type": "message_start", "message": {"usage": {"input_tokens": 4, "output_tokens": 1}}})),
+        ("content_block_start", json!({"type": "content_block_start", "index": 0, "content_block": {"type": "text", "text": ""}})),
+        ("content_block_delta", json!({"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": "Hi"}})),
+        ("content_block_delta", json!({"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": " there"}})),
+        ("content_block_stop", json!({"type": "content_block_stop", "index": 0})),
+        ("message_delta", json!({"type": "message_delta", "delta": {"stop_reason": "end_turn"}, "usage": {"output_tokens": 3}})),
+        ("message_stop", json!({"type": "message_stop"})),
+    ]
+    .iter()
+    .map(|(name, data)| format!("event: {}\ndata: {}\n\n", name, data))
+    .collect::<String>();
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(events, "text/event-stream"))
+        .mount(&server)
+        .await;
+    let app = setup_app(backend(&server, BackendKind::Anthropic)).await;
+
+    let (status, _, body) = send(
+        app,
+        "/v1/chat/completions",
+        json!({
+            "model": "test-model",
+            "stream": true,
+            "stream_options": {"include_usage": true},
+            "messages": [{"role": "user", "content": "Hi"}]
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.ends_with("data: [DONE]\n\n"));
+    let chunks = chunks(&body);
+    assert_eq!(chunks[0]["choices"][0]["delta"]["role"], "assistant");
+    assert_eq!(streamed_content(&chunks), "Hi there");
+    let usage = chunks.last().unwrap();
+    assert_eq!(usage["usage"]["completion_tokens"], 3);
+    assert_eq!(usage["usage"]["prompt_tokens"], 4);
+    assert_eq!(sent_request(&server).await["stream"], true);
+}
+
+#[tokio::test]
+async fn test_anthropic_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(529).set_body_json(json!({
+            "type": "error",
+            "error": {"type": "overloaded_error", "message": "Overloaded"}
+        })))
+        .mount(&server)
+        .await;
+    let app = setup_app(backend(&server, BackendKind::Anthropic)).await;
+
+    let (status, _, body) = send(
+        app,
+        "/v1/chat/completions",
+        json!({"model": "test-model", "messages": [{"role": "user", "content": "Hi"}]}),
+    )
+    .await;
+    assert_eq!(status.as_u16(), 529);
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["error"]["message"], "Overloaded");
+}
+
+#[tokio::test]
+async fn test_unsupported_endpoint() {
+    let server = MockServer::start().await;
+    let app = setup_app(backend(&server, BackendKind::Anthropic)).await;
+
+    let (status, _, body) = send(
+        app,
+        "/v1/completions",
+        json!({"model": "test-model", "prompt": "Hi"}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["error"]["code"], "unsupported_endpoint");
+    assert!(server.received_requests().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_invalid_credentials() {
+    let server = MockServer::start().await;
+    let mut backend = backend(&server, BackendKind::OpenAi);
+    backend.auth = Some(AuthConfig::Bearer {
+        token: "first\nsecond".to_string(),
+    });
+    let app = setup_app(backend).await;
+
+    let (status, _, _) = send(
+        app,
+        "/v1/chat/completions",
+        json!({"model": "test-model", "messages": [{"role": "user", "content": "Hi"}]}),
+    )
+    .await;
+    assert!(status.is_server_error());
+    assert!(server.received_requests().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_gemini_chat() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1beta/models/test-model:generateContent"))
+        .and(header("x-goog-api-key", "secret"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "candidates": [{
+                "content": {"role": "model", "parts": [{"text": "Bonjour"}]},
+                "finishReason": "STOP"
+            }],
+            "usageMetadata": {"promptTokenCount": 6, "candidatesTokenCount": 2}
+        })))
+        .mount(&server)
+        .await;
+    let app = setup_app(backend(&server, BackendKind::Gemini)).await;
+
+    let (status, _, body) = send(
+        app,
+        "/v1/chat/completions",
+        json!({
+            "model": "test-model",
+            "max_tokens": 50,
+            "stop": "END",
+            "messages": [
+                {"role": "system", "content": "Answer in French."},
+                {"role": "user", "content": "Hello"}
+            ]
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["choices"][0]["message"]["content"], "Bonjour");
+    assert_eq!(body["choices"][0]["finish_reason"], "stop");
+    assert_eq!(body["usage"]["completion_tokens"], 2);
+
+    let sent = sent_request(&server).await;
+    assert_eq!(
+        sent["systemInstruction"],
+        json!({"parts": [{"text": "Answer in French."}]})
+    );
+    assert_eq!(
+        sent["contents"],
+        json!([{"role": "user", "parts": [{"text": "Hello"}]}])
+    );
+    assert_eq!(
+        sent["generationConfig"],
+        json!({"maxOutputTokens": 50, "stopSequences": ["END"]})
+    );
+}
+
+#[tokio::test]
+async fn test_gemini_stream() {
+    let server = MockServer::start().await;
+    let events = [
+        json!({"candidates": [{"content": {"parts": [{"text": "Bon"}]}}]}),
+        json!({"candidates": [{"content": {"parts": [{"text": "jour"}]}, "finishReason": "MAX_TOKENS"}]}),
+    ]
+    .iter()
+    .map(|data| format!("data: {}\r\n\r\n", data))
+    .collect::<String>();
+    Mock::given(method("POST"))
+        .and(path("/v1beta/models/test-model:streamGenerateContent"))
+        .and(query_param("alt", "sse"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(events, "text/event-stream"))
+        .mount(&server)
+        .await;
+    let app = setup_app(backend(&server, BackendKind::Gemini)).await;
+
+    let (status, _, body) = send(
+        app,
+        "/v1/chat/completions",
+        json!({"model": "test-model", "stream": true, "messages": [{"role": "user", "content": "Hello"}]}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.ends_with("data: [DONE]\n\n"));
+    let chunks = chunks(&body);
+    assert_eq!(streamed_content(&chunks), "Bonjour");
+    assert_eq!(
+        chunks.last().unwrap()["choices"][0]["finish_reason"],
+        "length"
+    );
+}
+
+#[tokio::test]
+async fn test_azure_request() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/openai/deployments/test-model/chat/completions"))
+        .and(query_param("api-version", "2024-06-01"))
+        .and(header("api-key", "secret"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hi"}, "finish_reason": "stop"}]
+        })))
+        .mount(&server)
+        .await;
+    let app = setup_app(BackendConfig {
+        api_version: Some("2024-06-01".to_string()),
+        ..backend(&server, BackendKind::Azure)
+    })
+    .await;
+
+    let request = json!({"model": "test-model", "messages": [{"role": "user", "content": "Hi"}]});
+    let (status, _, body) = send(app, "/v1/chat/completions", request.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["id"], "chatcmpl-1");
+    assert_eq!(sent_request(&server).await, request);
+}
+
+#[tokio::test]
+async fn test_discovery() {
+    let ollama = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/tags"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "models": [{"name": "llama3:8b", "modified_at": "2024-05-01T10:00:00Z"}]
+        })))
+        .mount(&ollama)
+        .await;
+    let tgi = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/info"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({"model_id": "bigscience/bloom"})),
+        )
+        .mount(&tgi)
+        .await;
+    let gemini = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1beta/models"))
+        .and(header("x-goog-api-key", "secret"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "models": [
+                {"name": "models/gemini-2.0-flash", "supportedGenerationMethods": ["generateContent"]},
+                {"name": "models/text-embedding-004", "supportedGenerationMethods": ["embedContent"]}
+            ]
+        })))
+        .mount(&gemini)
+        .await;
+    let azure = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/openai/deployments"))
+        .and(header("api-key", "secret"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": [{"id": "gpt-4o-prod", "model": "gpt-4o"}]
+        })))
+        .mount(&azure)
+        .await;
+
+    let state = AppState::new(Config {
+        refresh_interval: 300,
+        backends: vec![
+            BackendConfig {
+                name: "ollama".to_string(),
+                ..backend(&ollama, BackendKind::Ollama)
+            },
+            BackendConfig {
+                name: "tgi".to_string(),
+                ..backend(&tgi, BackendKind::Tgi)
+            },
+            BackendConfig {
+                name: "gemini".to_string(),
+                ..backend(&gemini, BackendKind::Gemini)
+            },
+            BackendConfig {
+                name: "azure".to_string(),
+                ..backend(&azure, BackendKind::Azure)
+            },
+        ],
+        ..Default::default()
+    });
+    refresh_models(&state).await;
+
+    let routing_table = state.routing_table.read().await;
+    assert_eq!(routing_table["llama3:8b"], vec!["ollama".to_string()]);
+    assert_eq!(routing_table["bigscience/bloom"], vec!["tgi".to_string()]);
+    assert_eq!(
+        routing_table["gemini-2.0-flash"],
+        vec!["gemini".to_string()]
+    );
+    assert_eq!(routing_table["gpt-4o-prod"], vec!["azure".to_string()]);
+    assert_eq!(routing_table.len(), 4);
+    let models = state.model_cache.read().await;
+    let llama = models.iter().find(|m| m.id == "llama3:8b").unwrap();
+    assert_eq!(llama.created, 1_714_557_600);
+    assert_eq!(llama.owned_by, "ollama");
+}
+
+#[tokio::test]
+async fn test_azure_deployments() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/openai/deployments/gpt4o-prod/chat/completions"))
+        .and(query_param("api-version", "2024-10-21"))
+        .and(header("api-key", "secret"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hi"}, "finish_reason": "stop"}]
+        })))
+        .mount(&server)
+        .await;
+    let state = AppState::new(Config {
+        refresh_interval: 300,
+        backends: vec![BackendConfig {
+            deployments: [
+                ("gpt-4o".to_string(), "gpt4o-prod".to_string()),
+                ("gpt-4o-mini".to_string(), "gpt4o-mini-prod".to_string()),
+            ]
+            .into(),
+            ..backend(&server, BackendKind::Azure)
+        }],
+        ..Default::default()
+    });
+    refresh_models(&state).await;
+    assert!(server.received_requests().await.unwrap().is_empty());
+    let app = Router::new()
+        .route("/v1/models", get(list_models))
+        .route("/v1/chat/completions", post(forward_request))
+        .with_state(state);
+
+    let request = Request::builder()
+        .uri("/v1/models")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    let ids: Vec<&str> = body["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|model| model["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, ["gpt-4o", "gpt-4o-mini"]);
+    assert_eq!(body["data"][0]["owned_by"], "azure");
+
+    let (status, _, _) = send(
+        app,
+        "/v1/chat/completions",
+        json!({"model": "gpt-4o", "messages": [{"role": "user", "content": "Hi"}]}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[test]
+fn test_azure_settings_are_validated() {
+    let config: Config = serde_yml::from_str(
+        r#"
+refresh_interval: 300
+backends:
+  - name: "vllm"
+    url: "http://vllm:8000"
+    api_version: "2024-10-21"
+    deployments: {"gpt-4o": "gpt4o-prod"}
+  - name: "azure"
+    url: "https://my-resource.openai.azure.com"
+    kind: "azure"
+    deployments: {"gpt-4o": ""}
+"#,
+    )
+    .unwrap();
+    let issues = config.validate().unwrap_err();
+    let fields: Vec<&str> = issues.iter().map(|issue| issue.field.as_str()).collect();
+    assert_eq!(
+        fields,
+        [
+            "backends[0].api_version",
+            "backends[0].deployments",
+            "backends[1].deployments.gpt-4o"
+        ]
+    );
+}
@@ -0,0 +1,137 @@
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode},
+    routing::get,
+};
+use http_body_util::BodyExt;
+use llm_router::{
+    admin::list_backends,
+    auth::hash_key,
+    config::{BackendConfig, ClientAuthConfig, Config},
+    model::{AppState, refresh_models},
+};
+use serde_json::json;
+use tower::ServiceExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn test_list_backends() {
+    let healthy = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "object": "list",
+            "data": [{
+                "id": "model-1",
+                "object": "model",
+                "created": 0,
+                "owned_by": "test"
+            }]
+        })))
+        .mount(&healthy)
+        .await;
+
+    let config = Config {
+        refresh_interval: 300,
+        backends: vec![
+            BackendConfig {
+                name: "healthy".to_string(),
+                url: healthy.uri(),
+                ..Default::default()
+            },
+            BackendConfig {
+                name: "down".to_string(),
+                url: "http://localhost:1".to_string(),
+                ..Default::default()
+            },
+        ],
+        client_auth: ClientAuthConfig {
+            admin_key_sha256: Some(hash_key("sk-admin")),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let state = AppState::new(config);
+    refresh_models(&state).await;
+
+    let app = Router::new()
+        .route("/admin/backends", get(list_backends))
+        .with_state(state);
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/admin/backends")
+                .header("Authorization", "Bearer sk-admin")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+
+    assert_eq!(body[0]["name"], "healthy");
+    assert_eq!(body[0]["models"], json!(["model-1"]));
+    assert_eq!(body[0]["age_secs"], 0);
+    assert_eq!(body[0]["stale"], false);
+    assert_eq!(body[0]["last_error"], serde_json::Value::Null);
+
+    assert_eq!(body[1]["name"], "down");
+    assert_eq!(body[1]["models"], json!([]));
+    assert_eq!(body[1]["age_secs"], serde_json::Value::Null);
+    assert!(body[1]["last_error"].is_string());
+}
+
+async fn get_backends(state: AppState, key: Option<&str>) -> (StatusCode, serde_json::Value) {
+    let app = Router::new()
+        .route("/admin/backends", get(list_backends))
+        .with_state(state);
+    let mut request = Request::builder().uri("/admin/backends");
+    if let Some(key) = key {
+        request = request.header("x-api-key", key);
+    }
+    let response = app
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+    (status, serde_json::from_slice(&body_bytes).unwrap())
+}
+
+#[tokio::test]
+async fn test_admin_requires_admin_key() {
+    let state = AppState::new(Config {
+        refresh_interval: 300,
+        client_auth: ClientAuthConfig {
+            admin_key_sha256: Some(hash_key("sk-admin")),
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+
+    let (status, body) = get_backends(state.clone(), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error"]["code"], "missing_api_key");
+    let (status, body) = get_backends(state.clone(), Some("sk-team-a")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error"]["code"], "invalid_api_key");
+    let (status, _) = get_backends(state, Some("sk-admin")).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_admin_disabled_without_admin_key() {
+    let state = AppState::new(Config {
+        refresh_interval: 300,
+        ..Default::default()
+    });
+
+    let (status, body) = get_backends(state, Some("sk-admin")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"]["code"], "admin_disabled");
+}
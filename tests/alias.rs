@@ -0,0 +1,219 @@
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode},
+    routing::{get, post},
+};
+use http_body_util::BodyExt;
+use llm_router::{
+    config::{AliasTarget, BackendConfig, Config},
+    forward_request, list_models,
+    model::{AppState, ModelInfo},
+};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+use wiremock::matchers::{body_partial_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const UPSTREAM: &str = "meta-llama/Llama-3.3-70B-Instruct";
+
+fn target(backend: &str, model: &str) -> AliasTarget {
+    AliasTarget {
+        backend: backend.to_string(),
+        model: model.to_string(),
+    }
+}
+
+async fn setup_app(backends: &[&MockServer], targets: Vec<AliasTarget>) -> Router {
+    let state = AppState::new(Config {
+        refresh_interval: 300,
+        backends: backends
+            .iter()
+            .enumerate()
+            .map(|(i, server)| BackendConfig {
+                name: format!("backend-{}", i + 1),
+                url: server.uri(),
+                ..Default::default()
+            })
+            .collect(),
+        aliases: [("gpt-4o".to_string(), targets)].into(),
+        ..Default::default()
+    });
+    state
+        .routing_table
+        .write()
+        .await
+        .insert(UPSTREAM.to_string(), vec!["backend-1".to_string()]);
+    state.model_cache.write().await.push(ModelInfo {
+        id: UPSTREAM.to_string(),
+        object: "model".to_string(),
+        created: 1_700_000_000,
+        owned_by: "vllm".to_string(),
+    });
+    Router::new()
+        .route("/v1/models", get(list_models))
+        .route("/v1/chat/completions", post(forward_request))
+        .with_state(state)
+}
+
+async fn chat(app: Router, body: Value) -> (StatusCode, String) {
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/chat/completions")
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+fn completion(model: &str) -> Value {
+    json!({
+        "id": "chatcmpl-1",
+        "object": "chat.completion",
+        "model": model,
+        "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hi"}, "finish_reason": "stop"}]
+    })
+}
+
+#[tokio::test]
+async fn test_alias_rewrites_model() {
+    let backend = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(body_partial_json(json!({"model": UPSTREAM})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(completion(UPSTREAM)))
+        .expect(1)
+        .mount(&backend)
+        .await;
+    let app = setup_app(&[&backend], vec![target("backend-1", UPSTREAM)]).await;
+
+    let (status, body) = chat(
+        app,
+        json!({"model": "gpt-4o", "messages": [{"role": "user", "content": "Hi"}]}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["model"], "gpt-4o");
+    assert_eq!(body["choices"][0]["message"]["content"], "Hi");
+}
+
+#[tokio::test]
+async fn test_alias_rewrites_stream() {
+    let backend = MockServer::start().await;
+    let events = format!(
+        "data: {}\n\ndata: {}\n\ndata: [DONE]\n\n",
+        json!({"object": "chat.completion.chunk", "model": UPSTREAM, "choices": [{"index": 0, "delta": {"content": "Hel"}}]}),
+        json!({"object": "chat.completion.chunk", "model": UPSTREAM, "choices": [{"index": 0, "delta": {"content": "lo"}}]}),
+    );
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(events, "text/event-stream"))
+        .mount(&backend)
+        .await;
+    let app = setup_app(&[&backend], vec![target("backend-1", UPSTREAM)]).await;
+
+    let (status, body) = chat(
+        app,
+        json!({"model": "gpt-4o", "stream": true, "messages": [{"role": "user", "content": "Hi"}]}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(!body.contains(UPSTREAM));
+    assert!(body.ends_with("data: [DONE]\n\n"));
+    let chunks: Vec<Value> = body
+        .split("\n\n")
+        .filter_map(|event| event.strip_prefix("data: "))
+        .filter(|data| *data != "[DONE]")
+        .map(|data| serde_json::from_str(data).unwrap())
+        .collect();
+    assert_eq!(chunks.len(), 2);
+    assert!(chunks.iter().all(|chunk| chunk["model"] == "gpt-4o"));
+    assert_eq!(chunks[1]["choices"][0]["delta"]["content"], "lo");
+}
+
+#[tokio::test]
+async fn test_alias_targets_per_backend() {
+    let first = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(body_partial_json(json!({"model": UPSTREAM})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(completion(UPSTREAM)))
+        .expect(1)
+        .mount(&first)
+        .await;
+    let second = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(body_partial_json(json!({"model": "llama-3.3-70b-awq"})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(completion("llama-3.3-70b-awq")))
+        .expect(1)
+        .mount(&second)
+        .await;
+    let app = setup_app(
+        &[&first, &second],
+        vec![
+            target("backend-1", UPSTREAM),
+            target("backend-2", "llama-3.3-70b-awq"),
+        ],
+    )
+    .await;
+
+    for _ in 0..2 {
+        let (status, body) = chat(
+            app.clone(),
+            json!({"model": "gpt-4o", "messages": [{"role": "user", "content": "Hi"}]}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["model"], "gpt-4o");
+    }
+}
+
+#[tokio::test]
+async fn test_aliases_are_listed() {
+    let backend = MockServer::start().await;
+    let app = setup_app(&[&backend], vec![target("backend-1", UPSTREAM)]).await;
+
+    let request = Request::builder()
+        .uri("/v1/models")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    let ids: Vec<&str> = body["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|model| model["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, [UPSTREAM, "gpt-4o"]);
+}
+
+#[test]
+fn test_aliases_are_validated() {
+    let config: Config = serde_yml::from_str(
+        r#"
+refresh_interval: 300
+backends:
+  - name: "vllm-1"
+    url: "http://vllm-1:8000"
+aliases:
+  "gpt-4o":
+    - backend: "vllm-1"
+      model: "meta-llama/Llama-3.3-70B-Instruct"
+    - backend: "vllm-9"
+      model: ""
+"#,
+    )
+    .unwrap();
+    let issues = config.validate().unwrap_err();
+    let fields: Vec<&str> = issues.iter().map(|issue| issue.field.as_str()).collect();
+    assert_eq!(
+        fields,
+        ["aliases.gpt-4o[1].backend", "aliases.gpt-4o[1].model"]
+    );
+}
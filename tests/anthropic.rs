@@ -0,0 +1,313 @@
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode},
+    routing::post,
+};
+use http_body_util::BodyExt;
+use llm_router::{
+    anthropic,
+    config::{BackendConfig, Config},
+    model::AppState,
+};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+async fn setup_app(backend: &MockServer) -> Router {
+    let state = AppState::new(Config {
+        refresh_interval: 300,
+        backends: vec![BackendConfig {
+            name: "primary".to_string(),
+            url: backend.uri(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    });
+    state
+        .routing_table
+        .write()
+        .await
+        .insert("test-model".to_string(), vec!["primary".to_string()]);
+    Router::new()
+        .route("/v1/messages", post(anthropic::messages))
+        .with_state(state)
+}
+
+async fn send(app: Router, body: Value) -> (StatusCode, String, String) {
+    let request = Request::builder()
+        .method("POST")
+        .uri("/v1/messages")
+        .header("Content-Type", "application/json")
+        .header("anthropic-version", "2023-06-01")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get("content-type")
+        .map(|value| value.to_str().unwrap().to_string())
+        .unwrap_or_default();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (
+        status,
+        content_type,
+        String::from_utf8(body.to_vec()).unwrap(),
+    )
+}
+
+/// Parses an SSE body into `(event, data)` pairs.
+fn events(body: &str) -> Vec<(String, Value)> {
+    body.split("\n\n")
+        .filter(|event| !event.is_empty())
+        .map(|event| {
+            let mut lines = event.lines();
+            let name = lines.next().unwrap().strip_prefix("event: ").unwrap();
+            let data = lines.next().unwrap().strip_prefix("data: ").unwrap();
+            (name.to_string(), serde_json::from_str(data).unwrap())
+        })
+        .collect()
+}
+
+async fn sent_request(backend: &MockServer) -> Value {
+    let received = backend.received_requests().await.unwrap();
+    serde_json::from_slice(&received[0].body).unwrap()
+}
+
+#[tokio::test]
+async fn test_translates_request() {
+    let backend = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "chatcmpl-1",
+            "choices": [{"index": 0, "message": {"role": "assistant", "content": "Sunny."}, "finish_reason": "stop"}]
+        })))
+        .mount(&backend)
+        .await;
+    let app = setup_app(&backend).await;
+
+    let (status, _, _) = send(
+        app,
+        json!({
+            "model": "test-model",
+            "max_tokens": 256,
+            "system": [{"type": "text", "text": "Be brief."}],
+            "stop_sequences": ["END"],
+            "tools": [{
+                "name": "get_weather",
+                "description": "Current weather",
+                "input_schema": {"type": "object", "properties": {"city": {"type": "string"}}}
+            }],
+            "tool_choice": {"type": "any"},
+            "messages": [
+                {"role": "user", "content": [
+                    {"type": "text", "text": "Weather here?"},
+                    {"type": "image", "source": {"type": "base64", "media_type": "image/jpeg", "data": "/9j/AA=="}}
+                ]},
+                {"role": "assistant", "content": [
+                    {"type": "text", "text": "Checking."},
+                    {"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {"city": "Paris"}}
+                ]},
+                {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "toolu_1", "content": [{"type": "text", "text": "18C"}]}
+                ]}
+            ]
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let sent = sent_request(&backend).await;
+    assert_eq!(sent["max_tokens"], 256);
+    assert_eq!(sent["stop"], json!(["END"]));
+    assert_eq!(sent["tool_choice"], "required");
+    assert_eq!(
+        sent["tools"][0],
+        json!({
+            "type": "function",
+            "function": {
+                "name": "get_weather",
+                "description": "Current weather",
+                "parameters": {"type": "object", "properties": {"city": {"type": "string"}}}
+            }
+        })
+    );
+    assert_eq!(
+        sent["messages"],
+        json!([
+            {"role": "system", "content": "Be brief."},
+            {"role": "user", "content": [
+                {"type": "text", "text": "Weather here?"},
+                {"type": "image_url", "image_url": {"url": "data:image/jpeg;base64,/9j/AA=="}}
+            ]},
+            {"role": "assistant", "content": "Checking.", "tool_calls": [{
+                "id": "toolu_1",
+                "type": "function",
+                "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}
+            }]},
+            {"role": "tool", "tool_call_id": "toolu_1", "content": "18C"}
+        ])
+    );
+}
+
+#[tokio::test]
+async fn test_translates_response() {
+    let backend = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "chatcmpl-1",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": "Let me check.",
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}
+                    }]
+                },
+                "finish_reason": "tool_calls"
+            }],
+            "usage": {"prompt_tokens": 20, "completion_tokens": 9, "total_tokens": 29}
+        })))
+        .mount(&backend)
+        .await;
+    let app = setup_app(&backend).await;
+
+    let (status, content_type, body) = send(
+        app,
+        json!({
+            "model": "test-model",
+            "max_tokens": 256,
+            "messages": [{"role": "user", "content": "Weather in Paris?"}]
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type, "application/json");
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert!(body["id"].as_str().unwrap().starts_with("msg_"));
+    assert_eq!(body["type"], "message");
+    assert_eq!(body["role"], "assistant");
+    assert_eq!(body["model"], "test-model");
+    assert_eq!(
+        body["content"],
+        json!([
+            {"type": "text", "text": "Let me check."},
+            {"type": "tool_use", "id": "call_1", "name": "get_weather", "input": {"city": "Paris"}}
+        ])
+    );
+    assert_eq!(body["stop_reason"], "tool_use");
+    assert_eq!(
+        body["usage"],
+        json!({"input_tokens": 20, "output_tokens": 9})
+    );
+}
+
+#[tokio::test]
+async fn test_streams_message_events() {
+    let backend = MockServer::start().await;
+    let sse = concat!(
+        "data: {\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"Hel\"}}]}\n\n",
+        "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"lo\"}}]}\n\n",
+        "data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_1\",\"function\":{\"name\":\"get_weather\",\"arguments\":\"{\\\"city\\\":\"}}]}}]}\n\n",
+        "data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"\\\"Paris\\\"}\"}}]},\"finish_reason\":\"tool_calls\"}]}\n\n",
+        "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":5,\"completion_tokens\":7,\"total_tokens\":12}}\n\n",
+        "data: [DONE]\n\n",
+    );
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(sse, "text/event-stream"))
+        .mount(&backend)
+        .await;
+    let app = setup_app(&backend).await;
+
+    let (status, content_type, body) = send(
+        app,
+        json!({
+            "model": "test-model",
+            "max_tokens": 256,
+            "stream": true,
+            "messages": [{"role": "user", "content": "Hi"}]
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(content_type.starts_with("text/event-stream"));
+
+    let events = events(&body);
+    let names: Vec<&str> = events.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "message_start",
+            "content_block_start",
+            "content_block_delta",
+            "content_block_delta",
+            "content_block_stop",
+            "content_block_start",
+            "content_block_delta",
+            "content_block_delta",
+            "content_block_stop",
+            "message_delta",
+            "message_stop",
+        ]
+    );
+    for (name, data) in &events {
+        assert_eq!(data["type"], name.as_str());
+    }
+    assert_eq!(events[0].1["message"]["model"], "test-model");
+    assert_eq!(
+        events[2].1["delta"],
+        json!({"type": "text_delta", "text": "Hel"})
+    );
+    assert_eq!(
+        events[5].1,
+        json!({
+            "type": "content_block_start",
+            "index": 1,
+            "content_block": {"type": "tool_use", "id": "call_1", "name": "get_weather", "input": {}}
+        })
+    );
+    assert_eq!(
+        events[7].1["delta"],
+        json!({"type": "input_json_delta", "partial_json": "\"Paris\"}"})
+    );
+    assert_eq!(events[9].1["delta"]["stop_reason"], "tool_use");
+    assert_eq!(events[9].1["usage"]["output_tokens"], 7);
+
+    let sent = sent_request(&backend).await;
+    assert_eq!(sent["stream_options"]["include_usage"], true);
+}
+
+#[tokio::test]
+async fn test_errors_use_messages_format() {
+    let backend = MockServer::start().await;
+    let app = setup_app(&backend).await;
+
+    let (status, _, body) = send(
+        app,
+        json!({
+            "model": "unknown-model",
+            "max_tokens": 16,
+            "messages": [{"role": "user", "content": "Hi"}]
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(
+        serde_json::from_str::<Value>(&body).unwrap(),
+        json!({
+            "type": "error",
+            "error": {
+                "type": "not_found_error",
+                "message": "The model `unknown-model` does not exist."
+            }
+        })
+    );
+}
@@ -0,0 +1,213 @@
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode},
+    routing::{get, post},
+};
+use http_body_util::BodyExt;
+use llm_router::{
+    ModelInfo,
+    auth::{glob_match, hash_key},
+    config::{BackendConfig, ClientAuthConfig, ClientKeyConfig, Config},
+    model::AppState,
+    router::{forward_request, list_models},
+};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn client_key(name: &str, key: &str, models: &[&str]) -> ClientKeyConfig {
+    ClientKeyConfig {
+        name: name.to_string(),
+        key_sha256: hash_key(key),
+        models: models.iter().map(|m| m.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+async fn setup_auth_app(backend_url: String) -> Router {
+    let mut disabled = client_key("disabled", "sk-disabled", &["*"]);
+    disabled.enabled = false;
+    let config = Config {
+        refresh_interval: 300,
+        backends: vec![BackendConfig {
+            name: "backend".to_string(),
+            url: backend_url,
+            ..Default::default()
+        }],
+        client_auth: ClientAuthConfig {
+            keys: vec![
+                client_key("team-a", "sk-team-a", &["llama-*"]),
+                client_key("admin", "sk-admin", &["*"]),
+                disabled,
+            ],
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let state = AppState::new(config);
+    {
+        let mut routing_table = state.routing_table.write().await;
+        let mut model_cache = state.model_cache.write().await;
+        for id in ["llama-3-8b", "gpt-4o"] {
+            routing_table.insert(id.to_string(), vec!["backend".to_string()]);
+            model_cache.push(ModelInfo {
+                id: id.to_string(),
+                object: "model".to_string(),
+                created: 0,
+                owned_by: "test".to_string(),
+            });
+        }
+    }
+
+    Router::new()
+        .route("/v1/models", get(list_models))
+        .route("/v1/chat/completions", post(forward_request))
+        .with_state(state)
+}
+
+async fn chat(app: Router, key: Option<&str>, model: &str) -> (StatusCode, Value) {
+    let mut request = Request::builder()
+        .method("POST")
+        .uri("/v1/chat/completions")
+        .header("Content-Type", "application/json");
+    if let Some(key) = key {
+        request = request.header("Authorization", format!("Bearer {}", key));
+    }
+    let body = json!({"model": model, "messages": []}).to_string();
+    let response = app
+        .oneshot(request.body(Body::from(body)).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    (status, serde_json::from_slice(&bytes).unwrap())
+}
+
+#[test]
+fn test_glob_match() {
+    assert!(glob_match("*", "anything"));
+    assert!(glob_match("llama-*", "llama-3-8b"));
+    assert!(glob_match(
+        "meta-llama/*-Instruct",
+        "meta-llama/Llama-3.3-70B-Instruct"
+    ));
+    assert!(glob_match("gpt-4?", "gpt-4o"));
+    assert!(glob_match("gpt-4o", "gpt-4o"));
+    assert!(!glob_match("gpt-4o", "gpt-4o-mini"));
+    assert!(!glob_match("llama-*", "codellama-7b"));
+    assert!(!glob_match("gpt-4?", "gpt-4"));
+}
+
+#[test]
+fn test_hash_key() {
+    assert_eq!(
+        hash_key("abc"),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+}
+
+#[tokio::test]
+async fn test_request_without_key_is_rejected() {
+    let app = setup_auth_app("http://localhost:1".to_string()).await;
+    let (status, body) = chat(app, None, "llama-3-8b").await;
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error"]["code"], "missing_api_key");
+}
+
+#[tokio::test]
+async fn test_unknown_and_disabled_keys_are_rejected() {
+    let app = setup_auth_app("http://localhost:1".to_string()).await;
+
+    let (status, body) = chat(app.clone(), Some("sk-wrong"), "llama-3-8b").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error"]["code"], "invalid_api_key");
+
+    let (status, body) = chat(app, Some("sk-disabled"), "llama-3-8b").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error"]["code"], "invalid_api_key");
+}
+
+#[tokio::test]
+async fn test_model_outside_allowlist_is_forbidden() {
+    let app = setup_auth_app("http://localhost:1".to_string()).await;
+    let (status, body) = chat(app, Some("sk-team-a"), "gpt-4o").await;
+
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"]["type"], "permission_error");
+    assert_eq!(body["error"]["code"], "model_not_allowed");
+    assert_eq!(body["error"]["param"], "model");
+}
+
+#[tokio::test]
+async fn test_allowed_model_is_forwarded() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"id": "ok"})))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let app = setup_auth_app(mock_server.uri()).await;
+    let (status, body) = chat(app, Some("sk-team-a"), "llama-3-8b").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["id"], "ok");
+}
+
+#[tokio::test]
+async fn test_list_models_is_filtered_by_key() {
+    let app = setup_auth_app("http://localhost:1".to_string()).await;
+
+    let list = |request: Request<Body>| {
+        let app = app.clone();
+        async move {
+            let response = app.oneshot(request).await.unwrap();
+            let status = response.status();
+            let bytes = response.into_body().collect().await.unwrap().to_bytes();
+            (status, serde_json::from_slice::<Value>(&bytes).unwrap())
+        }
+    };
+    let ids = |body: &Value| -> Vec<String> {
+        body["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["id"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    let (status, body) = list(
+        Request::builder()
+            .uri("/v1/models")
+            .header("x-api-key", "sk-team-a")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(ids(&body), vec!["llama-3-8b"]);
+
+    let (_, body) = list(
+        Request::builder()
+            .uri("/v1/models")
+            .header("Authorization", "Bearer sk-admin")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(ids(&body), vec!["llama-3-8b", "gpt-4o"]);
+
+    let (status, _) = list(
+        Request::builder()
+            .uri("/v1/models")
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
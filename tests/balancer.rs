@@ -0,0 +1,110 @@
+use llm_router::balancer::Balancer;
+use llm_router::config::{BackendConfig, Strategy};
+
+fn backend(name: &str, weight: u32) -> BackendConfig {
+    BackendConfig {
+        name: name.to_string(),
+        url: format!("http://{}", name),
+        weight,
+        ..Default::default()
+    }
+}
+
+fn pick_names(
+    balancer: &Balancer,
+    strategy: Strategy,
+    backends: &[BackendConfig],
+    n: usize,
+) -> Vec<String> {
+    let candidates: Vec<&BackendConfig> = backends.iter().collect();
+    (0..n)
+        .map(|_| {
+            balancer
+                .pick("model", strategy, &candidates)
+                .unwrap()
+                .name
+                .clone()
+        })
+        .collect()
+}
+
+#[test]
+fn test_pick_without_candidates() {
+    let balancer = Balancer::default();
+    assert!(balancer.pick("model", Strategy::RoundRobin, &[]).is_none());
+}
+
+#[test]
+fn test_round_robin() {
+    let balancer = Balancer::default();
+    let backends = [backend("a", 1), backend("b", 1), backend("c", 1)];
+
+    let picks = pick_names(&balancer, Strategy::RoundRobin, &backends, 6);
+    assert_eq!(picks, ["a", "b", "c", "a", "b", "c"]);
+}
+
+#[test]
+fn test_round_robin_is_per_model() {
+    let balancer = Balancer::default();
+    let backends = [backend("a", 1), backend("b", 1)];
+    let candidates: Vec<&BackendConfig> = backends.iter().collect();
+
+    let first = balancer
+        .pick("m1", Strategy::RoundRobin, &candidates)
+        .unwrap();
+    let second = balancer
+        .pick("m2", Strategy::RoundRobin, &candidates)
+        .unwrap();
+    assert_eq!(first.name, "a");
+    assert_eq!(second.name, "a");
+}
+
+#[test]
+fn test_weighted() {
+    let balancer = Balancer::default();
+    let backends = [backend("a", 3), backend("b", 1)];
+
+    let picks = pick_names(&balancer, Strategy::Weighted, &backends, 8);
+    assert_eq!(picks.iter().filter(|n| *n == "a").count(), 6);
+    assert_eq!(picks.iter().filter(|n| *n == "b").count(), 2);
+    // Smooth weighting interleaves instead of sending bursts.
+    assert_eq!(&picks[..4], ["a", "a", "b", "a"]);
+}
+
+#[test]
+fn test_random_only_returns_candidates() {
+    let balancer = Balancer::default();
+    let backends = [backend("a", 1), backend("b", 1)];
+
+    let picks = pick_names(&balancer, Strategy::Random, &backends, 50);
+    assert!(picks.iter().all(|n| n == "a" || n == "b"));
+}
+
+#[test]
+fn test_least_outstanding() {
+    let balancer = Balancer::default();
+    let backends = [backend("a", 1), backend("b", 1)];
+    let candidates: Vec<&BackendConfig> = backends.iter().collect();
+
+    let _busy = balancer.begin("a");
+    for _ in 0..4 {
+        let picked = balancer
+            .pick("model", Strategy::LeastOutstanding, &candidates)
+            .unwrap();
+        assert_eq!(picked.name, "b");
+    }
+}
+
+#[test]
+fn test_outstanding_guard() {
+    let balancer = Balancer::default();
+
+    let first = balancer.begin("a");
+    let second = balancer.begin("a");
+    assert_eq!(balancer.outstanding("a"), 2);
+
+    drop(first);
+    assert_eq!(balancer.outstanding("a"), 1);
+    drop(second);
+    assert_eq!(balancer.outstanding("a"), 0);
+}
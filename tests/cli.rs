@@ -0,0 +1,174 @@
+use axum::{Router, routing::get};
+use clap::Parser;
+use llm_router::cli::{Cli, Command};
+use llm_router::config::{Config, LogFormat, ServerConfig};
+use llm_router::listen::{ListenAddr, serve};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+#[test]
+fn test_listen_addr_parsing() {
+    assert_eq!(
+        "127.0.0.1:9000".parse::<ListenAddr>().unwrap(),
+        ListenAddr::Tcp(SocketAddr::from(([127, 0, 0, 1], 9000)))
+    );
+    assert_eq!(
+        "[::1]:9000".parse::<ListenAddr>().unwrap().to_string(),
+        "[::1]:9000"
+    );
+    assert_eq!(
+        "unix:/run/llm-router.sock".parse::<ListenAddr>().unwrap(),
+        ListenAddr::Unix(PathBuf::from("/run/llm-router.sock"))
+    );
+    assert!("localhost".parse::<ListenAddr>().is_err());
+    assert!("unix:".parse::<ListenAddr>().is_err());
+}
+
+#[test]
+fn test_cli_defaults() {
+    let cli = Cli::try_parse_from(["llm-router"]).unwrap();
+    let config = Config::default();
+
+    assert_eq!(cli.command(), Command::Serve);
+    assert_eq!(cli.config, PathBuf::from("config.yml"));
+    assert_eq!(cli.listen_addrs(&config), vec![ListenAddr::default()]);
+    assert_eq!(cli.log_level(&config), None);
+    assert_eq!(cli.log_format(&config), LogFormat::Text);
+}
+
+#[test]
+fn test_cli_flags_override_config() {
+    let config = Config {
+        server: ServerConfig {
+            listen: vec!["127.0.0.1:7000".parse().unwrap()],
+            log_level: Some("debug".to_string()),
+            log_format: Some(LogFormat::Json),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let cli = Cli::try_parse_from(["llm-router"]).unwrap();
+    assert_eq!(cli.listen_addrs(&config), config.server.listen);
+    assert_eq!(cli.log_level(&config).as_deref(), Some("debug"));
+    assert_eq!(cli.log_format(&config), LogFormat::Json);
+
+    let cli = Cli::try_parse_from([
+        "llm-router",
+        "list-models",
+        "--config",
+        "/etc/llm-router/config.yaml",
+        "--listen",
+        "[::]:8081,unix:/tmp/router.sock",
+        "--log-level",
+        "warn",
+        "--log-format",
+        "text",
+    ])
+    .unwrap();
+    assert_eq!(cli.command(), Command::ListModels);
+    assert_eq!(cli.config, PathBuf::from("/etc/llm-router/config.yaml"));
+    assert_eq!(
+        cli.listen_addrs(&config)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        vec!["[::]:8081", "unix:/tmp/router.sock"]
+    );
+    assert_eq!(cli.log_level(&config).as_deref(), Some("warn"));
+    assert_eq!(cli.log_format(&config), LogFormat::Text);
+}
+
+#[test]
+fn test_check_config_flag() {
+    let cli = Cli::try_parse_from(["llm-router", "--check-config", "x.yml"]).unwrap();
+    assert_eq!(cli.command(), Command::CheckConfig);
+    assert_eq!(cli.config_path(), Path::new("x.yml"));
+
+    let cli = Cli::try_parse_from(["llm-router", "--check-config"]).unwrap();
+    assert_eq!(cli.command(), Command::CheckConfig);
+    assert_eq!(cli.config_path(), Path::new("config.yml"));
+
+    let cli = Cli::try_parse_from(["llm-router", "check-config", "--config", "y.yml"]).unwrap();
+    assert_eq!(cli.command(), Command::CheckConfig);
+    assert_eq!(cli.config_path(), Path::new("y.yml"));
+
+    let cli = Cli::try_parse_from(["llm-router", "x.yml"]).unwrap();
+    assert_eq!(cli.command(), Command::Serve);
+    assert_eq!(cli.config_path(), Path::new("x.yml"));
+}
+
+#[test]
+fn test_cli_rejects_invalid_listen_addr() {
+    assert!(Cli::try_parse_from(["llm-router", "--listen", "nowhere"]).is_err());
+}
+
+#[test]
+fn test_server_config_from_yaml() {
+    let config: Config = serde_yml::from_str(
+        r#"
+refresh_interval: 60
+backends: []
+server:
+  listen: ["0.0.0.0:8080", "unix:/run/llm-router.sock"]
+  log_format: json
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(config.server.listen.len(), 2);
+    assert_eq!(config.server.log_format, Some(LogFormat::Json));
+    assert!(config.server.access_log);
+    assert!(
+        serde_yml::from_str::<Config>(
+            "refresh_interval: 60\nbackends: []\nserver:\n  listen: [\"bad\"]\n"
+        )
+        .is_err()
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_serve_on_tcp_and_unix_socket() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let socket = dir.path().join("router.sock");
+    // A leftover socket from an earlier run must not prevent binding.
+    drop(std::os::unix::net::UnixListener::bind(&socket).unwrap());
+
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let addrs = vec![
+        ListenAddr::Tcp(SocketAddr::from(([127, 0, 0, 1], port))),
+        ListenAddr::Unix(socket.clone()),
+    ];
+    let app = Router::new().route("/healthz", get(|| async { "OK" }));
+    tokio::spawn(async move { serve(app, &addrs).await });
+
+    let mut stream = loop {
+        if let Ok(stream) = tokio::net::UnixStream::connect(&socket).await {
+            break stream;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    };
+    stream
+        .write_all(b"GET /healthz HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"));
+    assert!(response.ends_with("OK"));
+
+    let body = reqwest::get(format!("http://127.0.0.1:{}/healthz", port))
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert_eq!(body, "OK");
+}
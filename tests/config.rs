@@ -1,6 +1,10 @@
 #[cfg(test)]
 mod tests {
-    use llm_router::config::{load_config, try_load_config};
+    use llm_router::config::{
+        AuthConfig, BackendConfig, ConfigError, RetryConfig, Strategy, interpolate_env,
+        load_config, try_load_config,
+    };
+    use std::time::Duration;
     use std::io::Write;
     use tempfile::NamedTempFile;
 
@@ -22,7 +26,7 @@ mod tests {
         let new_path = format!("{}.yml", path);
         std::fs::rename(temp_file.path(), &new_path).unwrap();
 
-        let config = load_config(&new_path);
+        let (config, _) = load_config(&new_path).unwrap();
         assert_eq!(config.refresh_interval, 300);
     }
 
@@ -43,7 +47,7 @@ mod tests {
         let new_path = format!("{}.yml", path);
 
         std::fs::write(&new_path, config_content).unwrap();
-        let config = load_config(&new_path);
+        let (config, _) = load_config(&new_path).unwrap();
         assert_eq!(config.refresh_interval, 300);
         std::fs::remove_file(&new_path).unwrap();
     }
@@ -62,19 +66,21 @@ mod tests {
         let new_path = format!("{}.yaml", path);
 
         std::fs::write(&new_path, config_content).unwrap();
-        let config = load_config(&new_path);
+        let (config, _) = load_config(&new_path).unwrap();
         assert_eq!(config.refresh_interval, 300);
         std::fs::remove_file(&new_path).unwrap();
     }
 
     #[test]
-    #[should_panic(expected = "Configuration file not found. Check if config.yml or config.yaml file exists")]
     fn test_load_config_not_found() {
-        load_config("nonexistent_config");
+        let err = load_config("nonexistent_config").unwrap_err();
+        assert!(matches!(err, ConfigError::NotFound { .. }));
+        assert!(err.to_string().starts_with(
+            "Configuration file not found. Check if config.yml or config.yaml file exists"
+        ));
     }
 
     #[test]
-    #[should_panic(expected = "Configuration file not found. Check if config.yml or config.yaml file exists")]
     fn test_load_config_invalid_extension() {
         let config_content = r#"
             refresh_interval: 300
@@ -88,7 +94,10 @@ mod tests {
         std::fs::write(&new_path, config_content).unwrap();
         let result = load_config(&new_path);
         std::fs::remove_file(&new_path).unwrap();
-        drop(result);
+        assert!(matches!(
+            result,
+            Err(ConfigError::UnsupportedExtension { .. })
+        ));
     }
 
 
@@ -99,19 +108,392 @@ mod tests {
         write!(temp_file, "{}", invalid_content).unwrap();
 
         let result = try_load_config(temp_file.path());
-        assert!(result.is_none());
+        match result {
+            Err(ConfigError::Parse { line, column, .. }) => {
+                assert_eq!((line, column), (Some(1), Some(10)));
+            }
+            other => panic!("expected a parse error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_try_load_config_reports_offending_field() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        write!(
+            temp_file,
+            "refresh_interval: 300\nbackends:\n  - name: a\n    url: http://a\n  - name: b\n"
+        )
+        .unwrap();
+
+        let err = try_load_config(temp_file.path()).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains(&format!("{}:5:5:", temp_file.path().display())));
+        assert!(message.contains("backends[1]: missing field `url`"));
+    }
+
+    #[test]
+    fn test_load_config_does_not_hide_parse_errors() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("config.yml");
+        std::fs::write(&path, "refresh_interval: soon\nbackends: []\n").unwrap();
+
+        let err = load_config(path.to_str().unwrap()).unwrap_err();
+        assert!(matches!(err, ConfigError::Parse { line: Some(1), .. }));
+        assert!(err.to_string().contains("refresh_interval"));
+    }
+
+    #[test]
+    fn test_load_config_client_auth() {
+        let config_content = r#"
+refresh_interval: 300
+backends: []
+client_auth:
+  keys:
+    - name: "team-a"
+      key_sha256: "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+      models: ["llama-*"]
+    - name: "team-b"
+      key_sha256: "not-a-digest"
+      enabled: false
+"#;
+        let mut temp_file = NamedTempFile::with_suffix(".yml").unwrap();
+        write!(temp_file, "{}", config_content).unwrap();
+
+        let Err(ConfigError::Invalid { issues, .. }) = try_load_config(temp_file.path()) else {
+            panic!("expected validation to fail");
+        };
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].field, "client_auth.keys[1].key_sha256");
+
+        let config: llm_router::Config = serde_yml::from_str(config_content).unwrap();
+        assert_eq!(config.client_auth.keys[0].models, vec!["llama-*"]);
+        assert!(config.client_auth.keys[0].enabled);
+        assert_eq!(config.client_auth.keys[1].models, vec!["*"]);
+        assert!(!config.client_auth.keys[1].enabled);
+    }
+
+    #[test]
+    fn test_validate_reports_every_issue() {
+        let config_content = r#"
+refresh_interval: 0
+backends:
+  - name: "dup"
+    url: "http://localhost:8000"
+  - name: "dup"
+    url: "not a url"
+    weight: 0
+  - name: "ftp"
+    url: "ftp://localhost"
+    auth:
+      type: "header"
+      name: "bad header"
+      value: "secret"
+"#;
+        let mut temp_file = NamedTempFile::with_suffix(".yml").unwrap();
+        write!(temp_file, "{}", config_content).unwrap();
+
+        let Err(ConfigError::Invalid { issues, .. }) = try_load_config(temp_file.path()) else {
+            panic!("expected validation to fail");
+        };
+        let fields: Vec<&str> = issues.iter().map(|i| i.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "refresh_interval",
+                "backends[1].name",
+                "backends[1].url",
+                "backends[1].weight",
+                "backends[2].url",
+                "backends[2].auth.name",
+            ]
+        );
+        assert!(issues[1].message.contains("duplicate backend name 'dup'"));
+        let err = try_load_config(temp_file.path()).unwrap_err().to_string();
+        assert!(!err.contains("secret"));
+    }
+
+    #[test]
+    fn test_validate_auth_credentials() {
+        let backend = |name: &str, auth| BackendConfig {
+            name: name.to_string(),
+            url: "http://localhost:8000".to_string(),
+            auth: Some(auth),
+            ..Default::default()
+        };
+        let config = llm_router::Config {
+            refresh_interval: 300,
+            backends: vec![
+                backend(
+                    "bearer",
+                    AuthConfig::Bearer {
+                        token: "first\nsecond".to_string(),
+                    },
+                ),
+                backend(
+                    "basic",
+                    AuthConfig::Basic {
+                        username: "a:b".to_string(),
+                        password: "first\rsecond".to_string(),
+                    },
+                ),
+            ],
+            ..Default::default()
+        };
+
+        let issues = config.validate().unwrap_err();
+        let fields: Vec<&str> = issues.iter().map(|i| i.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "backends[0].auth.token",
+                "backends[1].auth.username",
+                "backends[1].auth.password",
+            ]
+        );
+        assert!(!format!("{:?}", issues).contains("second"));
     }
 
     #[test]
-    #[should_panic(expected = "Configuration file not found. Check if config.yml or config.yaml file exists")]
     fn test_load_config_invalid_path() {
-        load_config(".yml");
+        assert!(matches!(
+            load_config(".yml"),
+            Err(ConfigError::NotFound { .. })
+        ));
     }
 
     #[test]
-    #[should_panic(expected = "Configuration file not found. Check if config.yml or config.yaml file exists")]
     fn test_load_config_empty_path() {
-        load_config("");
+        assert!(matches!(load_config(""), Err(ConfigError::NotFound { .. })));
+    }
+
+    #[test]
+    fn test_load_config_load_balancing() {
+        let config_content = r#"
+            refresh_interval: 300
+            load_balancing:
+              strategy: "least_outstanding"
+              models:
+                llama-70b: "weighted"
+            backends:
+              - name: "vllm-1"
+                url: "http://vllm-1:8000"
+                weight: 3
+              - name: "vllm-2"
+                url: "http://vllm-2:8000"
+        "#;
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let new_path = format!("{}.yml", temp_file.path().to_str().unwrap());
+        std::fs::write(&new_path, config_content).unwrap();
+        let (config, _) = load_config(&new_path).unwrap();
+        std::fs::remove_file(&new_path).unwrap();
+
+        assert_eq!(config.backends[0].weight, 3);
+        assert_eq!(config.backends[1].weight, 1);
+        assert_eq!(
+            config.load_balancing.strategy_for("llama-70b"),
+            Strategy::Weighted
+        );
+        assert_eq!(
+            config.load_balancing.strategy_for("other"),
+            Strategy::LeastOutstanding
+        );
+    }
+
+    #[test]
+    fn test_load_config_retry() {
+        let config_content = r#"
+            refresh_interval: 300
+            retry:
+              max_attempts: 3
+              retry_on_status: [429, 503]
+            backends: []
+        "#;
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let new_path = format!("{}.yml", temp_file.path().to_str().unwrap());
+        std::fs::write(&new_path, config_content).unwrap();
+        let (config, _) = load_config(&new_path).unwrap();
+        std::fs::remove_file(&new_path).unwrap();
+
+        assert_eq!(config.retry.max_attempts, 3);
+        assert_eq!(config.retry.retry_on_status, vec![429, 503]);
+        assert!(config.retry.retry_on_connect_error);
+        assert_eq!(config.retry.backoff_ms, 100);
+    }
+
+    #[test]
+    fn test_retry_backoff() {
+        let retry = RetryConfig {
+            backoff_ms: 100,
+            max_backoff_ms: 300,
+            ..Default::default()
+        };
+
+        assert_eq!(retry.backoff(2), Duration::from_millis(100));
+        assert_eq!(retry.backoff(3), Duration::from_millis(200));
+        assert_eq!(retry.backoff(4), Duration::from_millis(300));
+        assert_eq!(retry.backoff(40), Duration::from_millis(300));
+    }
+
+    #[test]
+    fn test_load_config_timeouts() {
+        let config_content = r#"
+            refresh_interval: 300
+            backends:
+              - name: "vllm"
+                url: "http://vllm:8000"
+                timeouts:
+                  connect: 5
+                  first_byte: 30
+                  idle: 10
+              - name: "ollama"
+                url: "http://ollama:11434"
+        "#;
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let new_path = format!("{}.yml", temp_file.path().to_str().unwrap());
+        std::fs::write(&new_path, config_content).unwrap();
+        let (config, _) = load_config(&new_path).unwrap();
+        std::fs::remove_file(&new_path).unwrap();
+
+        let timeouts = &config.backends[0].timeouts;
+        assert_eq!(timeouts.connect(), Some(Duration::from_secs(5)));
+        assert_eq!(timeouts.request(), None);
+        assert_eq!(timeouts.first_byte(), Some(Duration::from_secs(30)));
+        assert_eq!(timeouts.idle(), Some(Duration::from_secs(10)));
+        assert_eq!(config.backends[1].timeouts.connect(), None);
+    }
+
+    #[test]
+    fn test_interpolate_env() {
+        // SAFETY: the variable names are unique to this test.
+        unsafe {
+            std::env::set_var("LLM_ROUTER_TEST_INTERPOLATE", "secret");
+            std::env::remove_var("LLM_ROUTER_TEST_INTERPOLATE_MISSING");
+        }
+
+        assert_eq!(
+            interpolate_env("Bearer ${LLM_ROUTER_TEST_INTERPOLATE}").unwrap(),
+            "Bearer secret"
+        );
+        assert_eq!(
+            interpolate_env("${LLM_ROUTER_TEST_INTERPOLATE_MISSING:-fallback}").unwrap(),
+            "fallback"
+        );
+        assert_eq!(interpolate_env("pa$$word $5").unwrap(), "pa$word $5");
+        assert_eq!(
+            interpolate_env("${LLM_ROUTER_TEST_INTERPOLATE_MISSING}").unwrap_err(),
+            "environment variable LLM_ROUTER_TEST_INTERPOLATE_MISSING is not set"
+        );
+        assert!(interpolate_env("${UNTERMINATED").is_err());
+    }
+
+    #[test]
+    fn test_auth_config_from_env() {
+        // SAFETY: the variable name is unique to this test.
+        unsafe { std::env::set_var("LLM_ROUTER_TEST_TOKEN", "env-token") };
+
+        let auth: AuthConfig =
+            serde_yml::from_str("type: bearer\ntoken: ${LLM_ROUTER_TEST_TOKEN}").unwrap();
+        assert_eq!(
+            auth,
+            AuthConfig::Bearer {
+                token: "env-token".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_auth_config_from_file() {
+        let mut secret = NamedTempFile::new().unwrap();
+        writeln!(secret, "file-password").unwrap();
+
+        let yaml = format!(
+            "type: basic\nusername: admin\npassword_file: {}",
+            secret.path().display()
+        );
+        let auth: AuthConfig = serde_yml::from_str(&yaml).unwrap();
+        assert_eq!(
+            auth,
+            AuthConfig::Basic {
+                username: "admin".to_string(),
+                password: "file-password".to_string()
+            }
+        );
+
+        let yaml = format!(
+            "type: header\nname: X-API-Key\nvalue_file: {}",
+            secret.path().display()
+        );
+        let auth: AuthConfig = serde_yml::from_str(&yaml).unwrap();
+        assert_eq!(
+            auth,
+            AuthConfig::CustomHeader {
+                name: "X-API-Key".to_string(),
+                value: "file-password".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_auth_config_missing_secret() {
+        let err = serde_yml::from_str::<AuthConfig>("type: bearer\ntoken_file: /nonexistent/token")
+            .unwrap_err();
+        assert!(err.to_string().contains("cannot read token_file '/nonexistent/token'"));
+
+        let err = serde_yml::from_str::<AuthConfig>(
+            "type: bearer\ntoken: ${LLM_ROUTER_TEST_UNSET_TOKEN}",
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("environment variable LLM_ROUTER_TEST_UNSET_TOKEN is not set")
+        );
+
+        let err =
+            serde_yml::from_str::<AuthConfig>("type: bearer\ntoken: a\ntoken_file: b").unwrap_err();
+        assert!(err.to_string().contains("only one of `token` and `token_file`"));
+
+        assert!(serde_yml::from_str::<AuthConfig>("type: bearer").is_err());
+    }
+
+    #[test]
+    fn test_auth_config_rejects_invalid_header_characters() {
+        let mut secret = NamedTempFile::new().unwrap();
+        writeln!(secret, "first-line\nsecond-line").unwrap();
+
+        let yaml = format!("type: bearer\ntoken_file: {}", secret.path().display());
+        let err = serde_yml::from_str::<AuthConfig>(&yaml).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("`token` contains characters not allowed in a header value")
+        );
+
+        let yaml = format!(
+            "type: basic\nusername: admin\npassword_file: {}",
+            secret.path().display()
+        );
+        let err = serde_yml::from_str::<AuthConfig>(&yaml).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("`password` contains characters not allowed in a header value")
+        );
+
+        let err = serde_yml::from_str::<AuthConfig>("type: basic\nusername: a:b\npassword: c")
+            .unwrap_err();
+        assert!(err.to_string().contains("`username` must not contain ':'"));
+    }
+
+    #[test]
+    fn test_auth_config_debug_redacts_secrets() {
+        let auth = AuthConfig::Basic {
+            username: "admin".to_string(),
+            password: "secret123".to_string(),
+        };
+        let debug = format!("{:?}", auth);
+        assert!(debug.contains("admin"));
+        assert!(!debug.contains("secret123"));
     }
 
 }
@@ -0,0 +1,95 @@
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use http_body_util::BodyExt;
+use llm_router::error::RouterError;
+use serde_json::json;
+
+#[test]
+fn test_error_statuses_and_codes() {
+    let cases = [
+        (
+            RouterError::InvalidJson("eof".to_string()),
+            StatusCode::BAD_REQUEST,
+            "invalid_json",
+        ),
+        (
+            RouterError::MissingModel,
+            StatusCode::BAD_REQUEST,
+            "missing_model",
+        ),
+        (
+            RouterError::ModelNotFound("gpt-5".to_string()),
+            StatusCode::NOT_FOUND,
+            "model_not_found",
+        ),
+        (
+            RouterError::Upstream("down".to_string()),
+            StatusCode::BAD_GATEWAY,
+            "upstream_error",
+        ),
+        (
+            RouterError::Timeout("slow".to_string()),
+            StatusCode::GATEWAY_TIMEOUT,
+            "timeout",
+        ),
+        (
+            RouterError::MissingApiKey,
+            StatusCode::UNAUTHORIZED,
+            "missing_api_key",
+        ),
+        (
+            RouterError::InvalidApiKey,
+            StatusCode::UNAUTHORIZED,
+            "invalid_api_key",
+        ),
+        (
+            RouterError::ModelNotAllowed("gpt-4o".to_string()),
+            StatusCode::FORBIDDEN,
+            "model_not_allowed",
+        ),
+        (
+            RouterError::TokenRateLimited("slow down".to_string()),
+            StatusCode::TOO_MANY_REQUESTS,
+            "rate_limit_exceeded",
+        ),
+    ];
+
+    for (err, status, code) in cases {
+        assert_eq!(err.status(), status);
+        assert_eq!(err.code(), code);
+    }
+}
+
+#[tokio::test]
+async fn test_error_response_body() {
+    let response = RouterError::ModelNotFound("gpt-5".to_string()).into_response();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(response.headers()["content-type"], "application/json");
+
+    let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(
+        body,
+        json!({
+            "error": {
+                "message": "The model `gpt-5` does not exist.",
+                "type": "invalid_request_error",
+                "param": "model",
+                "code": "model_not_found"
+            }
+        })
+    );
+}
+
+#[test]
+fn test_error_sse_event() {
+    let event = RouterError::Timeout("slow".to_string()).to_sse_event();
+
+    assert!(event.starts_with("data: {"));
+    assert!(event.ends_with("}\n\n"));
+
+    let body: serde_json::Value = serde_json::from_str(&event[6..event.len() - 2]).unwrap();
+    assert_eq!(body["error"]["code"], "timeout");
+    assert_eq!(body["error"]["param"], serde_json::Value::Null);
+}
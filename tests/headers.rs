@@ -0,0 +1,188 @@
+use axum::{
+    Router,
+    body::Body,
+    http::{HeaderMap, Request, StatusCode},
+    routing::post,
+};
+use llm_router::{
+    config::{BackendConfig, Config, HeaderPolicy, HeaderRules},
+    headers::{request_headers, response_headers},
+    model::AppState,
+    router::forward_request,
+};
+use serde_json::json;
+use std::collections::HashMap;
+use tower::ServiceExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn header_map(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for (name, value) in pairs {
+        headers.append(*name, value.parse().unwrap());
+    }
+    headers
+}
+
+#[test]
+fn test_request_headers_strip_hop_by_hop_and_credentials() {
+    let inbound = header_map(&[
+        ("authorization", "Bearer sk-router"),
+        ("x-api-key", "sk-router"),
+        ("connection", "keep-alive, x-trace-hop"),
+        ("x-trace-hop", "1"),
+        ("keep-alive", "timeout=5"),
+        ("host", "router.local"),
+        ("content-length", "42"),
+        ("transfer-encoding", "chunked"),
+        ("content-type", "application/json"),
+        ("x-request-source", "sdk"),
+    ]);
+
+    let headers = request_headers(&inbound, &BackendConfig::default());
+
+    let mut names: Vec<&str> = headers.keys().map(|name| name.as_str()).collect();
+    names.sort();
+    assert_eq!(names, vec!["content-type", "x-request-source"]);
+}
+
+#[test]
+fn test_request_headers_can_forward_client_credentials() {
+    let backend = BackendConfig {
+        headers: HeaderPolicy {
+            forward_client_credentials: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let inbound = header_map(&[("authorization", "Bearer sk-user")]);
+
+    let headers = request_headers(&inbound, &backend);
+    assert_eq!(headers["authorization"], "Bearer sk-user");
+}
+
+#[test]
+fn test_header_rules() {
+    let backend = BackendConfig {
+        headers: HeaderPolicy {
+            request: HeaderRules {
+                allow: vec!["Content-Type".to_string(), "X-Tenant".to_string()],
+                set: HashMap::from([("x-tenant".to_string(), "router".to_string())]),
+                add: HashMap::from([("x-forwarded-by".to_string(), "llm-router".to_string())]),
+                ..Default::default()
+            },
+            response: HeaderRules {
+                deny: vec!["server".to_string()],
+                remove: vec!["x-internal".to_string()],
+                add: HashMap::from([("x-served-by".to_string(), "backend".to_string())]),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let inbound = header_map(&[
+        ("content-type", "application/json"),
+        ("x-tenant", "client"),
+        ("user-agent", "curl"),
+    ]);
+    let headers = request_headers(&inbound, &backend);
+    assert_eq!(headers.len(), 3);
+    assert_eq!(headers["x-tenant"], "router");
+    assert_eq!(headers["x-forwarded-by"], "llm-router");
+    assert!(!headers.contains_key("user-agent"));
+
+    let upstream = header_map(&[
+        ("server", "uvicorn"),
+        ("x-internal", "1"),
+        ("transfer-encoding", "chunked"),
+        ("content-type", "application/json"),
+    ]);
+    let headers = response_headers(&upstream, &backend);
+    let mut names: Vec<&str> = headers.keys().map(|name| name.as_str()).collect();
+    names.sort();
+    assert_eq!(names, vec!["content-type", "x-served-by"]);
+}
+
+#[test]
+fn test_header_rules_are_validated() {
+    let config: Config = serde_yml::from_str(
+        r#"
+refresh_interval: 300
+backends:
+  - name: "backend"
+    url: "http://localhost:8000"
+    headers:
+      request:
+        deny: ["bad header"]
+      response:
+        set:
+          x-ok: "line\nbreak"
+"#,
+    )
+    .unwrap();
+
+    let issues = config.validate().unwrap_err();
+    let fields: Vec<&str> = issues.iter().map(|i| i.field.as_str()).collect();
+    assert_eq!(
+        fields,
+        vec![
+            "backends[0].headers.request.deny",
+            "backends[0].headers.response.set.x-ok",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_client_key_does_not_reach_unauthenticated_backend() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("connection", "close")
+                .set_body_json(json!({"id": "ok"})),
+        )
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let config = Config {
+        refresh_interval: 300,
+        backends: vec![BackendConfig {
+            name: "open".to_string(),
+            url: mock_server.uri(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let state = AppState::new(config);
+    state
+        .routing_table
+        .write()
+        .await
+        .insert("test-model".to_string(), vec!["open".to_string()]);
+    let app = Router::new()
+        .route("/v1/chat/completions", post(forward_request))
+        .with_state(state);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/chat/completions")
+                .header("Content-Type", "application/json")
+                .header("Authorization", "Bearer sk-router")
+                .body(Body::from(json!({"model": "test-model"}).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(!response.headers().contains_key("connection"));
+    let received = mock_server.received_requests().await.unwrap();
+    assert!(!received[0].headers.contains_key("authorization"));
+    assert_eq!(received[0].headers["content-type"], "application/json");
+}
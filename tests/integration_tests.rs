@@ -44,7 +44,9 @@ async fn test_full_request_flow() {
             auth: Some(AuthConfig::Bearer {
                 token: "test-token".to_string(),
             }),
+            ..Default::default()
         }],
+        ..Default::default()
     };
 
     let state = AppState::new(config);
@@ -52,7 +54,7 @@ async fn test_full_request_flow() {
     // Manually update routing table for test
     {
         let mut routing_table = state.routing_table.write().await;
-        routing_table.insert("gpt-3.5-turbo".to_string(), mock_server.uri());
+        routing_table.insert("gpt-3.5-turbo".to_string(), vec!["test-backend".to_string()]);
     }
 
     // Make a completion request
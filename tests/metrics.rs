@@ -0,0 +1,165 @@
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode},
+    routing::{get, post},
+};
+use http_body_util::BodyExt;
+use llm_router::{
+    config::{BackendConfig, Config},
+    model::{AppState, refresh_models},
+    router::{forward_request, metrics},
+};
+use serde_json::json;
+use tower::ServiceExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+async fn setup_app(backend: &MockServer) -> (AppState, Router) {
+    let config = Config {
+        refresh_interval: 300,
+        backends: vec![
+            BackendConfig {
+                name: "primary".to_string(),
+                url: backend.uri(),
+                ..Default::default()
+            },
+            BackendConfig {
+                name: "offline".to_string(),
+                url: "http://127.0.0.1:1".to_string(),
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+    let state = AppState::new(config);
+    let app = Router::new()
+        .route("/v1/chat/completions", post(forward_request))
+        .route("/metrics", get(metrics))
+        .with_state(state.clone());
+    (state, app)
+}
+
+async fn chat(app: &Router, model: &str) -> StatusCode {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/chat/completions")
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({"model": model}).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    response.into_body().collect().await.unwrap();
+    status
+}
+
+async fn scrape(app: &Router) -> String {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/metrics")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(
+        response.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/plain")
+    );
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn test_requests_are_counted_per_model_and_backend() {
+    let backend = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": [{"id": "test-model", "object": "model", "created": 0, "owned_by": "test"}]
+        })))
+        .mount(&backend)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "ok",
+            "usage": {"prompt_tokens": 12, "completion_tokens": 30, "total_tokens": 42}
+        })))
+        .mount(&backend)
+        .await;
+
+    let (state, app) = setup_app(&backend).await;
+    refresh_models(&state).await;
+    assert_eq!(chat(&app, "test-model").await, StatusCode::OK);
+    assert_eq!(chat(&app, "unknown-model").await, StatusCode::NOT_FOUND);
+
+    let metrics = scrape(&app).await;
+    for line in [
+        r#"llm_router_requests_total{backend="primary",model="test-model",status="200"} 1"#,
+        r#"llm_router_requests_total{backend="",model="",status="404"} 1"#,
+        r#"llm_router_errors_total{backend="",kind="model_not_found",model=""} 1"#,
+        r#"llm_router_upstream_responses_total{backend="primary",model="test-model",status="200"} 1"#,
+        r#"llm_router_tokens_total{backend="primary",direction="input",model="test-model"} 12"#,
+        r#"llm_router_tokens_total{backend="primary",direction="output",model="test-model"} 30"#,
+        r#"llm_router_request_duration_seconds_count{backend="primary",model="test-model"} 1"#,
+        r#"llm_router_in_flight_requests{backend="primary",model="test-model"} 0"#,
+        r#"llm_router_discovery_total{backend="primary",result="success"} 1"#,
+        r#"llm_router_discovery_total{backend="offline",result="failure"} 1"#,
+        r#"llm_router_discovered_models{backend="primary"} 1"#,
+    ] {
+        assert!(
+            metrics.contains(line),
+            "missing `{}` in:\n{}",
+            line,
+            metrics
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_streaming_request_records_first_token_and_usage() {
+    let backend = MockServer::start().await;
+    let sse = concat!(
+        "data: {\"choices\":[{\"delta\":{\"content\":\"hi\"}}]}\n\n",
+        "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":5,\"completion_tokens\":7}}\n\n",
+        "data: [DONE]\n\n",
+    );
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(sse, "text/event-stream"))
+        .mount(&backend)
+        .await;
+
+    let (state, app) = setup_app(&backend).await;
+    state
+        .routing_table
+        .write()
+        .await
+        .insert("test-model".to_string(), vec!["primary".to_string()]);
+    assert_eq!(chat(&app, "test-model").await, StatusCode::OK);
+
+    let metrics = scrape(&app).await;
+    for line in [
+        r#"llm_router_time_to_first_token_seconds_count{backend="primary",model="test-model"} 1"#,
+        r#"llm_router_tokens_total{backend="primary",direction="output",model="test-model"} 7"#,
+        r#"llm_router_request_duration_seconds_count{backend="primary",model="test-model"} 1"#,
+    ] {
+        assert!(
+            metrics.contains(line),
+            "missing `{}` in:\n{}",
+            line,
+            metrics
+        );
+    }
+}
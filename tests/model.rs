@@ -1,8 +1,8 @@
 #[cfg(test)]
 mod tests {
     use llm_router::{
-        config::{BackendConfig, Config},
-        model::{AppState, refresh_models_loop},
+        config::{BackendConfig, Config, ModelFilter, TimeoutConfig},
+        model::{AppState, refresh_models, refresh_models_loop},
     };
     use serde_json::json;
     use std::time::Duration;
@@ -17,11 +17,13 @@ mod tests {
                 name: "test".to_string(),
                 url: "http://localhost:8000".to_string(),
                 auth: None,
+                ..Default::default()
             }],
+            ..Default::default()
         };
 
         let state = AppState::new(config);
-        assert_eq!(state.config.refresh_interval, 300);
+        assert_eq!(state.config.load().refresh_interval, 300);
     }
 
     #[tokio::test]
@@ -56,7 +58,9 @@ mod tests {
                 name: "test".to_string(),
                 url: mock_server.uri(),
                 auth: None,
+                ..Default::default()
             }],
+            ..Default::default()
         };
 
         let state = AppState::new(config);
@@ -97,7 +101,9 @@ mod tests {
                 name: "test".to_string(),
                 url: mock_server.uri(),
                 auth: None,
+                ..Default::default()
             }],
+            ..Default::default()
         };
 
         let state = AppState::new(config);
@@ -143,7 +149,9 @@ mod tests {
                 name: "test".to_string(),
                 url: mock_server.uri(),
                 auth: None,
+                ..Default::default()
             }],
+            ..Default::default()
         };
 
         let state = AppState::new(config);
@@ -180,7 +188,9 @@ mod tests {
                 name: "test".to_string(),
                 url: "http://non-existent-server:1234".to_string(),
                 auth: None,
+                ..Default::default()
             }],
+            ..Default::default()
         };
 
         let state = AppState::new(config);
@@ -209,4 +219,361 @@ mod tests {
         handle.abort();
     }
 
+    #[tokio::test]
+    async fn test_refresh_models_loop_shared_model() {
+        let mut backends = Vec::new();
+        let mut servers = Vec::new();
+        for name in ["a", "b"] {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/v1/models"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "object": "list",
+                    "data": [{
+                        "id": "shared-model",
+                        "object": "model",
+                        "created": 0,
+                        "owned_by": name
+                    }]
+                })))
+                .mount(&mock_server)
+                .await;
+            backends.push(BackendConfig {
+                name: name.to_string(),
+                url: mock_server.uri(),
+                ..Default::default()
+            });
+            servers.push(mock_server);
+        }
+
+        let config = Config {
+            refresh_interval: 1,
+            backends,
+            ..Default::default()
+        };
+
+        let state = AppState::new(config);
+        let state_clone = state.clone();
+
+        let handle = tokio::spawn(async move {
+            refresh_models_loop(state).await;
+        });
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+
+        let cache = state_clone.model_cache.read().await;
+        assert_eq!(cache.len(), 1, "Shared model should be listed once");
+
+        let routing = state_clone.routing_table.read().await;
+        assert_eq!(routing["shared-model"], vec!["a", "b"]);
+
+        handle.abort();
+    }
+
+    async fn flaky_backend() -> MockServer {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "object": "list",
+                "data": [{
+                    "id": "model-1",
+                    "object": "model",
+                    "created": 0,
+                    "owned_by": "test"
+                }]
+            })))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&mock_server)
+            .await;
+
+        mock_server
+    }
+
+    #[tokio::test]
+    async fn test_refresh_models_keeps_last_known_good() {
+        let mock_server = flaky_backend().await;
+
+        let config = Config {
+            refresh_interval: 300,
+            backends: vec![BackendConfig {
+                name: "test".to_string(),
+                url: mock_server.uri(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let state = AppState::new(config);
+
+        refresh_models(&state).await;
+        refresh_models(&state).await;
+
+        let cache = state.model_cache.read().await;
+        assert_eq!(cache.len(), 1, "Cached models should survive a failed refresh");
+        assert!(state.routing_table.read().await.contains_key("model-1"));
+
+        let statuses = state.backend_status.read().await;
+        let status = &statuses["test"];
+        assert!(status.is_stale());
+        assert!(status.last_error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_models_evicts_stale_models() {
+        let mock_server = flaky_backend().await;
+
+        let config = Config {
+            refresh_interval: 300,
+            max_staleness: Some(0),
+            backends: vec![BackendConfig {
+                name: "test".to_string(),
+                url: mock_server.uri(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let state = AppState::new(config);
+
+        refresh_models(&state).await;
+        assert_eq!(state.model_cache.read().await.len(), 1);
+
+        refresh_models(&state).await;
+
+        assert!(state.model_cache.read().await.is_empty());
+        assert!(state.routing_table.read().await.is_empty());
+    }
+
+    async fn slow_backend(model: &str, delay: Duration) -> MockServer {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({
+                        "object": "list",
+                        "data": [{
+                            "id": model,
+                            "object": "model",
+                            "created": 0,
+                            "owned_by": "test"
+                        }]
+                    }))
+                    .set_delay(delay),
+            )
+            .mount(&mock_server)
+            .await;
+        mock_server
+    }
+
+    #[tokio::test]
+    async fn test_refresh_models_queries_backends_concurrently() {
+        let first = slow_backend("model-1", Duration::from_millis(800)).await;
+        let second = slow_backend("model-2", Duration::from_millis(800)).await;
+
+        let config = Config {
+            refresh_interval: 300,
+            backends: vec![
+                BackendConfig {
+                    name: "first".to_string(),
+                    url: first.uri(),
+                    ..Default::default()
+                },
+                BackendConfig {
+                    name: "second".to_string(),
+                    url: second.uri(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let state = AppState::new(config);
+
+        let started = std::time::Instant::now();
+        refresh_models(&state).await;
+
+        assert!(started.elapsed() < Duration::from_millis(1500));
+        assert_eq!(state.model_cache.read().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_models_discovery_timeout() {
+        let fast = slow_backend("model-1", Duration::ZERO).await;
+        let slow = slow_backend("model-2", Duration::from_secs(5)).await;
+
+        let config = Config {
+            refresh_interval: 300,
+            discovery_timeout: 1,
+            backends: vec![
+                BackendConfig {
+                    name: "fast".to_string(),
+                    url: fast.uri(),
+                    ..Default::default()
+                },
+                BackendConfig {
+                    name: "slow".to_string(),
+                    url: slow.uri(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let state = AppState::new(config);
+
+        let started = std::time::Instant::now();
+        refresh_models(&state).await;
+
+        assert!(started.elapsed() < Duration::from_secs(3));
+        let routing = state.routing_table.read().await;
+        assert!(routing.contains_key("model-1"));
+        assert!(!routing.contains_key("model-2"));
+
+        let statuses = state.backend_status.read().await;
+        assert!(
+            statuses["slow"]
+                .last_error
+                .as_deref()
+                .unwrap()
+                .contains("timed out")
+        );
+    }
+
+    #[test]
+    fn test_discovery_timeout_override() {
+        let config = Config {
+            discovery_timeout: 10,
+            ..Default::default()
+        };
+        let backend = BackendConfig {
+            timeouts: TimeoutConfig {
+                discovery: Some(2),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            config.discovery_timeout_for(&backend),
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            config.discovery_timeout_for(&BackendConfig::default()),
+            Duration::from_secs(10)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_static_models_skip_discovery() {
+        let mock_server = MockServer::start().await;
+        let state = AppState::new(Config {
+            refresh_interval: 300,
+            backends: vec![BackendConfig {
+                name: "static".to_string(),
+                url: mock_server.uri(),
+                models: vec!["llama-3-8b".to_string(), "llama-3-70b".to_string()],
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+        refresh_models(&state).await;
+
+        assert!(mock_server.received_requests().await.unwrap().is_empty());
+        let cache = state.model_cache.read().await;
+        let ids: Vec<&str> = cache.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&"llama-3-8b") && ids.contains(&"llama-3-70b"));
+        assert!(cache.iter().all(|m| m.owned_by == "static"));
+        let routing = state.routing_table.read().await;
+        assert_eq!(routing["llama-3-8b"], vec!["static".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_model_filter() {
+        let mock_server = MockServer::start().await;
+        let models: Vec<_> = [
+            "meta-llama/Llama-3-8B",
+            "meta-llama/Llama-3-8B-base",
+            "qwen2-7b-instruct",
+            "qwen2-7b",
+            "mistral-7b",
+        ]
+        .iter()
+        .map(|id| json!({"id": id, "object": "model", "created": 0, "owned_by": "test"}))
+        .collect();
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"data": models})))
+            .mount(&mock_server)
+            .await;
+        let state = AppState::new(Config {
+            refresh_interval: 300,
+            backends: vec![BackendConfig {
+                name: "test".to_string(),
+                url: mock_server.uri(),
+                model_filter: ModelFilter {
+                    include: vec![
+                        "meta-llama/*".parse().unwrap(),
+                        "/^qwen.*-instruct$/".parse().unwrap(),
+                    ],
+                    exclude: vec!["*-base".parse().unwrap()],
+                },
+                ..Default::default()
+            }],
+            ..Default::default()
+        });
+        refresh_models(&state).await;
+
+        let mut ids: Vec<String> = state
+            .model_cache
+            .read()
+            .await
+            .iter()
+            .map(|m| m.id.clone())
+            .collect();
+        ids.sort();
+        assert_eq!(ids, ["meta-llama/Llama-3-8B", "qwen2-7b-instruct"]);
+        let routing = state.routing_table.read().await;
+        assert_eq!(routing.len(), 2);
+        assert!(!routing.contains_key("mistral-7b"));
+    }
+
+    #[test]
+    fn test_model_filter_is_validated() {
+        let config: Config = serde_yml::from_str(
+            r#"
+refresh_interval: 300
+backends:
+  - name: "vllm"
+    url: "http://vllm:8000"
+    models: ["llama-3-8b", ""]
+    model_filter:
+      include: ["llama-*"]
+"#,
+        )
+        .unwrap();
+        let issues = config.validate().unwrap_err();
+        let fields: Vec<&str> = issues.iter().map(|issue| issue.field.as_str()).collect();
+        assert_eq!(fields, ["backends[0].models[1]"]);
+
+        let err = serde_yml::from_str::<Config>(
+            r#"
+refresh_interval: 300
+backends:
+  - name: "vllm"
+    url: "http://vllm:8000"
+    model_filter:
+      exclude: ["/llama-(/"]
+"#,
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("invalid regular expression '/llama-(/'")
+        );
+    }
 }
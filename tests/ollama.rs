@@ -0,0 +1,348 @@
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode},
+    routing::{get, post},
+};
+use http_body_util::BodyExt;
+use llm_router::{
+    config::{AliasTarget, BackendConfig, Config},
+    model::{AppState, ModelInfo},
+    ollama,
+};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use tower::ServiceExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+async fn setup_app(backend: &MockServer) -> Router {
+    setup_app_with_aliases(backend, HashMap::new()).await
+}
+
+async fn setup_app_with_aliases(
+    backend: &MockServer,
+    aliases: HashMap<String, Vec<AliasTarget>>,
+) -> Router {
+    let state = AppState::new(Config {
+        refresh_interval: 300,
+        backends: vec![BackendConfig {
+            name: "primary".to_string(),
+            url: backend.uri(),
+            ..Default::default()
+        }],
+        aliases,
+        ..Default::default()
+    });
+    state
+        .routing_table
+        .write()
+        .await
+        .insert("test-model".to_string(), vec!["primary".to_string()]);
+    state.model_cache.write().await.push(ModelInfo {
+        id: "test-model".to_string(),
+        object: "model".to_string(),
+        created: 1_700_000_000,
+        owned_by: "vllm".to_string(),
+    });
+    Router::new()
+        .route("/api/tags", get(ollama::list_tags))
+        .route("/api/show", post(ollama::show))
+        .route("/api/chat", post(ollama::chat))
+        .route("/api/generate", post(ollama::generate))
+        .route("/api/version", get(ollama::version))
+        .with_state(state)
+}
+
+async fn send(app: Router, uri: &str, body: Option<Value>) -> (StatusCode, String, String) {
+    let request = match body {
+        Some(body) => Request::builder()
+            .method("POST")
+            .uri(uri)
+            .body(Body::from(body.to_string())),
+        None => Request::builder().uri(uri).body(Body::empty()),
+    };
+    let response = app.oneshot(request.unwrap()).await.unwrap();
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get("content-type")
+        .map(|value| value.to_str().unwrap().to_string())
+        .unwrap_or_default();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (
+        status,
+        content_type,
+        String::from_utf8(body.to_vec()).unwrap(),
+    )
+}
+
+fn lines(body: &str) -> Vec<Value> {
+    body.lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+async fn completion_backend() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "Hello!"},
+                "finish_reason": "stop"
+            }],
+            "usage": {"prompt_tokens": 12, "completion_tokens": 3, "total_tokens": 15}
+        })))
+        .mount(&server)
+        .await;
+    server
+}
+
+#[tokio::test]
+async fn test_version() {
+    let backend = MockServer::start().await;
+    let app = setup_app(&backend).await;
+
+    let (status, _, body) = send(app, "/api/version", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["version"], ollama::OLLAMA_VERSION);
+}
+
+#[tokio::test]
+async fn test_tags_lists_cached_models() {
+    let backend = MockServer::start().await;
+    let app = setup_app(&backend).await;
+
+    let (status, _, body) = send(app, "/api/tags", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let body: Value = serde_json::from_str(&body).unwrap();
+    let models = body["models"].as_array().unwrap();
+    assert_eq!(models.len(), 1);
+    assert_eq!(models[0]["name"], "test-model");
+    assert_eq!(models[0]["model"], "test-model");
+    assert_eq!(models[0]["modified_at"], "2023-11-14T22:13:20Z");
+}
+
+#[tokio::test]
+async fn test_show() {
+    let backend = MockServer::start().await;
+    let app = setup_app(&backend).await;
+
+    let (status, _, body) = send(
+        app.clone(),
+        "/api/show",
+        Some(json!({"model": "test-model"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert!(body["details"].is_object());
+
+    let (status, _, body) = send(app, "/api/show", Some(json!({"name": "missing"}))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(
+        serde_json::from_str::<Value>(&body).unwrap(),
+        json!({"error": "model 'missing' not found"})
+    );
+}
+
+#[tokio::test]
+async fn test_chat_translates_request_and_response() {
+    let backend = completion_backend().await;
+    let app = setup_app(&backend).await;
+
+    let (status, content_type, body) = send(
+        app,
+        "/api/chat",
+        Some(json!({
+            "model": "test-model",
+            "stream": false,
+            "messages": [{"role": "user", "content": "Describe this", "images": ["iVBORw0KGgo="]}],
+            "options": {"temperature": 0.2, "num_predict": 64},
+            "format": "json"
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type, "application/json");
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["model"], "test-model");
+    assert_eq!(
+        body["message"],
+        json!({"role": "assistant", "content": "Hello!"})
+    );
+    assert_eq!(body["done"], true);
+    assert_eq!(body["done_reason"], "stop");
+    assert_eq!(body["prompt_eval_count"], 12);
+    assert_eq!(body["eval_count"], 3);
+    assert!(body["created_at"].as_str().unwrap().ends_with('Z'));
+
+    let received = backend.received_requests().await.unwrap();
+    let sent: Value = serde_json::from_slice(&received[0].body).unwrap();
+    assert_eq!(sent["stream"], false);
+    assert_eq!(sent["temperature"], 0.2);
+    assert_eq!(sent["max_tokens"], 64);
+    assert_eq!(sent["response_format"], json!({"type": "json_object"}));
+    assert_eq!(
+        sent["messages"][0]["content"],
+        json!([
+            {"type": "text", "text": "Describe this"},
+            {"type": "image_url", "image_url": {"url": "data:image/png;base64,iVBORw0KGgo="}}
+        ])
+    );
+}
+
+#[tokio::test]
+async fn test_generate_uses_system_prompt() {
+    let backend = completion_backend().await;
+    let app = setup_app(&backend).await;
+
+    let (status, _, body) = send(
+        app,
+        "/api/generate",
+        Some(json!({
+            "model": "test-model",
+            "stream": false,
+            "system": "Be brief.",
+            "prompt": "Hi"
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["response"], "Hello!");
+    assert_eq!(body["done"], true);
+
+    let received = backend.received_requests().await.unwrap();
+    let sent: Value = serde_json::from_slice(&received[0].body).unwrap();
+    assert_eq!(
+        sent["messages"],
+        json!([
+            {"role": "system", "content": "Be brief."},
+            {"role": "user", "content": "Hi"}
+        ])
+    );
+}
+
+#[tokio::test]
+async fn test_chat_streams_ndjson() {
+    let backend = MockServer::start().await;
+    let sse = concat!(
+        "data: {\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"Hel\"}}]}\n\n",
+        "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"lo\"}}]}\n\n",
+        "data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_1\",\"function\":{\"name\":\"get_weather\",\"arguments\":\"{\\\"city\\\":\"}}]}}]}\n\n",
+        "data: {\"choices\":[{\"index\":0,\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"\\\"Paris\\\"}\"}}]},\"finish_reason\":\"tool_calls\"}]}\n\n",
+        "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":5,\"completion_tokens\":7,\"total_tokens\":12}}\n\n",
+        "data: [DONE]\n\n",
+    );
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(sse, "text/event-stream"))
+        .mount(&backend)
+        .await;
+    let app = setup_app(&backend).await;
+
+    let (status, content_type, body) = send(
+        app,
+        "/api/chat",
+        Some(json!({
+            "model": "test-model",
+            "messages": [{"role": "user", "content": "Weather in Paris?"}]
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type, "application/x-ndjson");
+    let lines = lines(&body);
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0]["message"]["content"], "Hel");
+    assert_eq!(lines[0]["done"], false);
+    assert_eq!(lines[1]["message"]["content"], "lo");
+    assert_eq!(
+        lines[2]["message"]["tool_calls"],
+        json!([{"function": {"name": "get_weather", "arguments": {"city": "Paris"}}}])
+    );
+    assert_eq!(lines[3]["done"], true);
+    assert_eq!(lines[3]["done_reason"], "stop");
+    assert_eq!(lines[3]["prompt_eval_count"], 5);
+    assert_eq!(lines[3]["eval_count"], 7);
+
+    // Streams ask the backend to report usage.
+    let received = backend.received_requests().await.unwrap();
+    let sent: Value = serde_json::from_slice(&received[0].body).unwrap();
+    assert_eq!(sent["stream"], true);
+    assert_eq!(sent["stream_options"]["include_usage"], true);
+}
+
+#[tokio::test]
+async fn test_errors_use_ollama_format() {
+    let backend = completion_backend().await;
+    let app = setup_app(&backend).await;
+
+    let (status, _, body) = send(
+        app,
+        "/api/chat",
+        Some(json!({
+            "model": "unknown-model",
+            "messages": [{"role": "user", "content": "Hi"}]
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(
+        serde_json::from_str::<Value>(&body).unwrap(),
+        json!({"error": "The model `unknown-model` does not exist."})
+    );
+}
+
+#[tokio::test]
+async fn test_empty_generate_loads_model() {
+    let backend = completion_backend().await;
+    let app = setup_app(&backend).await;
+
+    let (status, _, body) = send(app, "/api/generate", Some(json!({"model": "test-model"}))).await;
+    assert_eq!(status, StatusCode::OK);
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["done"], true);
+    assert_eq!(body["done_reason"], "load");
+    assert!(backend.received_requests().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_aliases_are_listed_shown_and_loaded() {
+    let backend = completion_backend().await;
+    let target = AliasTarget {
+        backend: "primary".to_string(),
+        model: "test-model".to_string(),
+    };
+    let app = setup_app_with_aliases(
+        &backend,
+        HashMap::from([("gpt-4o".to_string(), vec![target])]),
+    )
+    .await;
+
+    let (status, _, body) = send(app.clone(), "/api/tags", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let body: Value = serde_json::from_str(&body).unwrap();
+    let names: Vec<&str> = body["models"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|model| model["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["test-model", "gpt-4o"]);
+
+    let (status, _, _) = send(app.clone(), "/api/show", Some(json!({"model": "gpt-4o"}))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _, body) = send(app, "/api/generate", Some(json!({"model": "gpt-4o"}))).await;
+    assert_eq!(status, StatusCode::OK);
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["done_reason"], "load");
+    assert!(backend.received_requests().await.unwrap().is_empty());
+}
@@ -0,0 +1,566 @@
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode},
+    routing::post,
+};
+use http_body_util::BodyExt;
+use llm_router::{
+    auth::hash_key,
+    config::{
+        BackendConfig, ClientAuthConfig, ClientKeyConfig, Config, RateLimit, RateLimitsConfig,
+    },
+    model::AppState,
+    ratelimit::{
+        RateLimiter, Scope, UsageRecorder, estimate_tokens, is_usage_event, request_stream_usage,
+    },
+    router::forward_request,
+};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use tower::ServiceExt;
+use wiremock::matchers::{body_partial_json, header_exists, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn rpm(requests_per_minute: u32) -> RateLimit {
+    RateLimit {
+        requests_per_minute: Some(requests_per_minute),
+        ..Default::default()
+    }
+}
+
+fn concurrent(max_concurrent: u32) -> RateLimit {
+    RateLimit {
+        max_concurrent: Some(max_concurrent),
+        ..Default::default()
+    }
+}
+
+async fn ok_backend() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"id": "ok"})))
+        .mount(&server)
+        .await;
+    server
+}
+
+async fn app_with(config: Config) -> Router {
+    let backends: Vec<String> = config.backends.iter().map(|b| b.name.clone()).collect();
+    let state = AppState::new(config);
+    state
+        .routing_table
+        .write()
+        .await
+        .insert("test-model".to_string(), backends);
+    Router::new()
+        .route("/v1/chat/completions", post(forward_request))
+        .with_state(state)
+}
+
+fn chat_request() -> Request<Body> {
+    Request::builder()
+        .method("POST")
+        .uri("/v1/chat/completions")
+        .header("Content-Type", "application/json")
+        .header("Authorization", "Bearer sk-test")
+        .body(Body::from(json!({"model": "test-model"}).to_string()))
+        .unwrap()
+}
+
+#[test]
+fn test_requests_per_minute_bucket() {
+    let limiter = RateLimiter::default();
+    let limit = rpm(2);
+
+    let first = limiter
+        .try_acquire(&[(Scope::Key, "a", &limit)], 0)
+        .unwrap();
+    assert_eq!(first.quotas[0].remaining, 1);
+    let second = limiter
+        .try_acquire(&[(Scope::Key, "a", &limit)], 0)
+        .unwrap();
+    assert_eq!(second.quotas[0].remaining, 0);
+
+    let limited = limiter
+        .try_acquire(&[(Scope::Key, "a", &limit)], 0)
+        .err()
+        .unwrap();
+    assert_eq!(limited.scope, Scope::Key);
+    // One request per 30 seconds refills the bucket.
+    assert!(limited.retry_after.as_secs() > 25 && limited.retry_after.as_secs() <= 30);
+
+    // Other ids have their own buckets.
+    assert!(limiter.try_acquire(&[(Scope::Key, "b", &limit)], 0).is_ok());
+}
+
+#[test]
+fn test_rejected_request_consumes_nothing() {
+    let limiter = RateLimiter::default();
+    let generous = rpm(10);
+    let strict = rpm(1);
+
+    limiter
+        .try_acquire(&[(Scope::Model, "m", &strict)], 0)
+        .unwrap();
+    assert!(
+        limiter
+            .try_acquire(
+                &[(Scope::Key, "a", &generous), (Scope::Model, "m", &strict)],
+                0
+            )
+            .is_err()
+    );
+
+    let permit = limiter
+        .try_acquire(&[(Scope::Key, "a", &generous)], 0)
+        .unwrap();
+    assert_eq!(permit.quotas[0].remaining, 9);
+}
+
+#[test]
+fn test_concurrency_slots_are_released_on_drop() {
+    let limiter = RateLimiter::default();
+    let limit = concurrent(1);
+
+    let permit = limiter
+        .try_acquire(&[(Scope::Backend, "b", &limit)], 0)
+        .unwrap();
+    let limited = limiter
+        .try_acquire(&[(Scope::Backend, "b", &limit)], 0)
+        .err()
+        .unwrap();
+    assert!(limited.quota.is_none());
+
+    drop(permit);
+    assert!(
+        limiter
+            .try_acquire(&[(Scope::Backend, "b", &limit)], 0)
+            .is_ok()
+    );
+}
+
+#[tokio::test]
+async fn test_key_limit_returns_429_with_headers() {
+    let backend = ok_backend().await;
+    let app = app_with(Config {
+        refresh_interval: 300,
+        backends: vec![BackendConfig {
+            name: "backend".to_string(),
+            url: backend.uri(),
+            ..Default::default()
+        }],
+        client_auth: ClientAuthConfig {
+            keys: vec![ClientKeyConfig {
+                name: "team-a".to_string(),
+                key_sha256: hash_key("sk-test"),
+                rate_limit: Some(rpm(2)),
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .await;
+
+    let response = app.clone().oneshot(chat_request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-ratelimit-limit-requests"], "2");
+    assert_eq!(response.headers()["x-ratelimit-remaining-requests"], "1");
+    assert!(
+        response
+            .headers()
+            .contains_key("x-ratelimit-reset-requests")
+    );
+
+    app.clone().oneshot(chat_request()).await.unwrap();
+    let response = app.oneshot(chat_request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()["retry-after"], "30");
+    assert_eq!(response.headers()["x-ratelimit-remaining-requests"], "0");
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["error"]["type"], "requests");
+    assert_eq!(body["error"]["code"], "rate_limit_exceeded");
+    assert_eq!(backend.received_requests().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_model_limit_applies_without_client_auth() {
+    let backend = ok_backend().await;
+    let app = app_with(Config {
+        refresh_interval: 300,
+        backends: vec![BackendConfig {
+            name: "backend".to_string(),
+            url: backend.uri(),
+            ..Default::default()
+        }],
+        rate_limits: RateLimitsConfig {
+            models: HashMap::from([("test-model".to_string(), rpm(1))]),
+        },
+        ..Default::default()
+    })
+    .await;
+
+    let response = app.clone().oneshot(chat_request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.oneshot(chat_request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[tokio::test]
+async fn test_saturated_backend_is_skipped() {
+    let limited = ok_backend().await;
+    let spare = ok_backend().await;
+    let app = app_with(Config {
+        refresh_interval: 300,
+        backends: vec![
+            BackendConfig {
+                name: "limited".to_string(),
+                url: limited.uri(),
+                rate_limit: Some(rpm(1)),
+                ..Default::default()
+            },
+            BackendConfig {
+                name: "spare".to_string(),
+                url: spare.uri(),
+                rate_limit: Some(rpm(2)),
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    })
+    .await;
+
+    for _ in 0..3 {
+        let response = app.clone().oneshot(chat_request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    assert_eq!(limited.received_requests().await.unwrap().len(), 1);
+    assert_eq!(spare.received_requests().await.unwrap().len(), 2);
+
+    let response = app.oneshot(chat_request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(response.headers().contains_key("retry-after"));
+}
+
+#[tokio::test]
+async fn test_saturated_backend_refunds_key_limit() {
+    let backend = ok_backend().await;
+    let app = app_with(Config {
+        refresh_interval: 300,
+        backends: vec![BackendConfig {
+            name: "backend".to_string(),
+            url: backend.uri(),
+            rate_limit: Some(rpm(1)),
+            ..Default::default()
+        }],
+        client_auth: ClientAuthConfig {
+            keys: vec![ClientKeyConfig {
+                name: "team-a".to_string(),
+                key_sha256: hash_key("sk-test"),
+                rate_limit: Some(rpm(3)),
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .await;
+
+    let response = app.clone().oneshot(chat_request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-ratelimit-remaining-requests"], "2");
+
+    // The backend is saturated, so the key keeps its two remaining requests.
+    for _ in 0..3 {
+        let response = app.clone().oneshot(chat_request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()["x-ratelimit-remaining-requests"], "2");
+    }
+    assert_eq!(backend.received_requests().await.unwrap().len(), 1);
+}
+
+fn tpm(tokens_per_minute: u32) -> RateLimit {
+    RateLimit {
+        tokens_per_minute: Some(tokens_per_minute),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_estimate_tokens() {
+    let request = json!({
+        "model": "test-model",
+        "messages": [{"role": "user", "content": "0123456789ab"}],
+        "max_tokens": 100
+    });
+    // "user" + "0123456789ab" = 16 characters, about 4 tokens.
+    assert_eq!(estimate_tokens(&request), 104);
+    assert_eq!(estimate_tokens(&json!({"prompt": "abcde"})), 2);
+}
+
+#[test]
+fn test_usage_recorder() {
+    let usage = UsageRecorder::default();
+    usage.record_sse_event(b"data: {\"choices\":[{\"delta\":{\"content\":\"hi\"}}]}\n\n");
+    assert_eq!(usage.get(), None);
+
+    usage.record_sse_event(
+        b"data: {\"choices\":[],\"usage\":{\"prompt_tokens\":12,\"completion_tokens\":30}}\n\n",
+    );
+    assert_eq!(usage.get(), Some(42));
+    assert_eq!(usage.usage().unwrap().completion_tokens, 30);
+
+    usage.record_json(
+        br#"{"usage": {"prompt_tokens": 5, "completion_tokens": 5, "total_tokens": 10}}"#,
+    );
+    assert_eq!(usage.get(), Some(10));
+}
+
+#[test]
+fn test_token_charge_is_reconciled_with_usage() {
+    let limiter = RateLimiter::default();
+    let limit = tpm(100);
+
+    let permit = limiter
+        .try_acquire(&[(Scope::Key, "a", &limit)], 80)
+        .unwrap();
+    assert_eq!(permit.quotas[0].resource, "tokens");
+    assert_eq!(permit.quotas[0].remaining, 20);
+    permit
+        .usage()
+        .record_json(br#"{"usage": {"total_tokens": 20}}"#);
+    drop(permit);
+
+    // 80 were charged but only 20 used, so 80 are available again.
+    let permit = limiter
+        .try_acquire(&[(Scope::Key, "a", &limit)], 75)
+        .unwrap();
+    drop(permit);
+
+    // Without reported usage the estimate stands.
+    let limited = limiter
+        .try_acquire(&[(Scope::Key, "a", &limit)], 75)
+        .err()
+        .unwrap();
+    assert_eq!(limited.quota.unwrap().resource, "tokens");
+}
+
+#[test]
+fn test_refund_restores_charge() {
+    let limiter = RateLimiter::default();
+    let limit = RateLimit {
+        requests_per_minute: Some(1),
+        tokens_per_minute: Some(100),
+        ..Default::default()
+    };
+
+    let permit = limiter
+        .try_acquire(&[(Scope::Key, "a", &limit)], 80)
+        .unwrap();
+    let quotas = permit.refund();
+    assert_eq!(quotas[0].remaining, 1);
+    assert_eq!(quotas[1].remaining, 100);
+
+    assert!(
+        limiter
+            .try_acquire(&[(Scope::Key, "a", &limit)], 80)
+            .is_ok()
+    );
+}
+
+#[tokio::test]
+async fn test_tokens_per_minute_uses_streamed_usage() {
+    let backend = MockServer::start().await;
+    let sse = concat!(
+        "data: {\"choices\":[{\"delta\":{\"content\":\"hi\"}}]}\n\n",
+        "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":400,\"completion_tokens\":800,\"total_tokens\":1200}}\n\n",
+        "data: [DONE]\n\n",
+    );
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_raw(sse, "text/event-stream"),
+        )
+        .mount(&backend)
+        .await;
+
+    let app = app_with(Config {
+        refresh_interval: 300,
+        backends: vec![BackendConfig {
+            name: "backend".to_string(),
+            url: backend.uri(),
+            ..Default::default()
+        }],
+        rate_limits: RateLimitsConfig {
+            models: HashMap::from([("test-model".to_string(), tpm(1000))]),
+        },
+        ..Default::default()
+    })
+    .await;
+
+    let response = app.clone().oneshot(chat_request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-ratelimit-limit-tokens"], "1000");
+    // Reading the stream to the end releases the permit with the reported usage.
+    response.into_body().collect().await.unwrap();
+
+    let response = app.oneshot(chat_request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()["x-ratelimit-remaining-tokens"], "0");
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["error"]["type"], "tokens");
+}
+
+#[tokio::test]
+async fn test_streamed_usage_is_requested_and_stripped() {
+    let backend = MockServer::start().await;
+    let sse = concat!(
+        "data: {\"choices\":[{\"delta\":{\"content\":\"hi\"}}]}\n\n",
+        "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":400,\"completion_tokens\":800,\"total_tokens\":1200}}\n\n",
+        "data: [DONE]\n\n",
+    );
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(body_partial_json(
+            json!({"stream": true, "stream_options": {"include_usage": true}}),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(sse, "text/event-stream"))
+        .mount(&backend)
+        .await;
+
+    let app = app_with(Config {
+        refresh_interval: 300,
+        backends: vec![BackendConfig {
+            name: "backend".to_string(),
+            url: backend.uri(),
+            ..Default::default()
+        }],
+        rate_limits: RateLimitsConfig {
+            models: HashMap::from([("test-model".to_string(), tpm(1000))]),
+        },
+        ..Default::default()
+    })
+    .await;
+    let stream_request = |body: Value| {
+        Request::builder()
+            .method("POST")
+            .uri("/v1/chat/completions")
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(stream_request(
+            json!({"model": "test-model", "stream": true}),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body = String::from_utf8(body.to_vec()).unwrap();
+    assert!(body.contains("\"content\":\"hi\""));
+    assert!(!body.contains("usage"));
+    assert!(body.ends_with("data: [DONE]\n\n"));
+
+    // The usage reached the limiter even though the client never saw it.
+    let response = app
+        .oneshot(stream_request(
+            json!({"model": "test-model", "stream": true}),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[test]
+fn test_request_stream_usage() {
+    let mut request = json!({"model": "m", "stream": true});
+    assert!(request_stream_usage(&mut request));
+    assert_eq!(request["stream_options"], json!({"include_usage": true}));
+
+    let mut request = json!({"stream": true, "stream_options": {"include_usage": true}});
+    assert!(!request_stream_usage(&mut request));
+    let mut request = json!({"model": "m"});
+    assert!(!request_stream_usage(&mut request));
+    assert!(request.get("stream_options").is_none());
+
+    assert!(is_usage_event(
+        b"data: {\"choices\":[],\"usage\":{\"total_tokens\":3}}\n\n"
+    ));
+    assert!(!is_usage_event(
+        b"data: {\"choices\":[{\"delta\":{}}],\"usage\":null}\n\n"
+    ));
+}
+
+/// `{"id":"ok","usage":{"prompt_tokens":400,"completion_tokens":800,"total_tokens":1200}}`,
+/// gzip-compressed.
+const GZIP_USAGE: [u8; 87] = [
+    31, 139, 8, 0, 0, 0, 0, 0, 2, 3, 171, 86, 202, 76, 81, 178, 82, 202, 207, 86, 210, 81, 42, 45,
+    78, 76, 79, 85, 178, 170, 86, 42, 40, 202, 207, 45, 40, 137, 47, 201, 207, 78, 205, 43, 86,
+    178, 50, 49, 48, 208, 81, 74, 6, 10, 229, 164, 150, 100, 230, 231, 193, 197, 45, 64, 226, 37,
+    249, 37, 137, 57, 112, 33, 67, 35, 3, 131, 218, 90, 0, 55, 102, 128, 27, 85, 0, 0, 0,
+];
+
+#[tokio::test]
+async fn test_usage_is_read_from_backends_that_compress() {
+    let backend = MockServer::start().await;
+    // Compresses only for clients that accept it, like most servers.
+    Mock::given(method("POST"))
+        .and(header_exists("accept-encoding"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-encoding", "gzip")
+                .set_body_raw(GZIP_USAGE.to_vec(), "application/json"),
+        )
+        .with_priority(1)
+        .mount(&backend)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "ok",
+            "usage": {"prompt_tokens": 400, "completion_tokens": 800, "total_tokens": 1200}
+        })))
+        .mount(&backend)
+        .await;
+
+    let app = app_with(Config {
+        refresh_interval: 300,
+        backends: vec![BackendConfig {
+            name: "backend".to_string(),
+            url: backend.uri(),
+            ..Default::default()
+        }],
+        rate_limits: RateLimitsConfig {
+            models: HashMap::from([("test-model".to_string(), tpm(1000))]),
+        },
+        ..Default::default()
+    })
+    .await;
+    let request = || {
+        let mut request = chat_request();
+        request
+            .headers_mut()
+            .insert("accept-encoding", "gzip, deflate".parse().unwrap());
+        request
+    };
+
+    let response = app.clone().oneshot(request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(!response.headers().contains_key("content-encoding"));
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["usage"]["total_tokens"], 1200);
+
+    // The reported usage was charged against the limit.
+    let response = app.oneshot(request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+}
@@ -0,0 +1,248 @@
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode},
+    routing::post,
+};
+use http_body_util::BodyExt;
+use llm_router::{
+    config::{BackendConfig, Config, RecorderConfig},
+    metrics::Metrics,
+    model::AppState,
+    recorder::Recorder,
+    router::forward_request,
+};
+use serde_json::{Value, json};
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::TempDir;
+use tower::ServiceExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn recorder_config(dir: &TempDir) -> RecorderConfig {
+    RecorderConfig {
+        path: dir.path().join("requests.jsonl"),
+        sample_rate: 1.0,
+        max_bytes: None,
+        max_files: 5,
+        redact: Vec::new(),
+    }
+}
+
+async fn setup_state(backend: &MockServer, recorder: RecorderConfig) -> AppState {
+    let mut config = Config {
+        refresh_interval: 300,
+        backends: vec![BackendConfig {
+            name: "primary".to_string(),
+            url: backend.uri(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    config.server.recorder = Some(recorder);
+    let state = AppState::new(config);
+    state
+        .routing_table
+        .write()
+        .await
+        .insert("test-model".to_string(), vec!["primary".to_string()]);
+    state
+}
+
+async fn chat(state: &AppState, body: Value) -> StatusCode {
+    let app = Router::new()
+        .route("/v1/chat/completions", post(forward_request))
+        .with_state(state.clone());
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/chat/completions")
+                .header("Content-Type", "application/json")
+                .header("x-request-id", "req-1")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    response.into_body().collect().await.unwrap();
+    status
+}
+
+fn read_records(state: &AppState, path: &Path) -> Vec<Value> {
+    state.recorder.as_ref().unwrap().flush();
+    std::fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+async fn json_backend() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "ok",
+            "object": "chat.completion",
+            "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hello"}}]
+        })))
+        .mount(&server)
+        .await;
+    server
+}
+
+fn chat_request() -> Value {
+    json!({
+        "model": "test-model",
+        "messages": [{"role": "user", "content": "secret question"}]
+    })
+}
+
+#[tokio::test]
+async fn test_records_request_and_response() {
+    let dir = TempDir::new().unwrap();
+    let backend = json_backend().await;
+    let mut config = recorder_config(&dir);
+    config.redact = vec!["request.messages.*.content".to_string()];
+    let state = setup_state(&backend, config.clone()).await;
+
+    assert_eq!(chat(&state, chat_request()).await, StatusCode::OK);
+
+    let records = read_records(&state, &config.path);
+    assert_eq!(records.len(), 1);
+    let record = &records[0];
+    assert_eq!(record["request_id"], "req-1");
+    assert_eq!(record["endpoint"], "/v1/chat/completions");
+    assert_eq!(record["model"], "test-model");
+    assert_eq!(record["backend"], "primary");
+    assert_eq!(record["status"], 200);
+    assert!(record["latency_ms"].as_f64().is_some());
+    assert_eq!(record["request"]["messages"][0]["role"], "user");
+    assert_eq!(record["request"]["messages"][0]["content"], "[REDACTED]");
+    assert_eq!(
+        record["response"]["choices"][0]["message"]["content"],
+        "Hello"
+    );
+}
+
+#[tokio::test]
+async fn test_reassembles_streamed_response() {
+    let dir = TempDir::new().unwrap();
+    let backend = MockServer::start().await;
+    let sse = concat!(
+        "data: {\"id\":\"s1\",\"object\":\"chat.completion.chunk\",\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"Hel\"}}]}\n\n",
+        "data: {\"id\":\"s1\",\"object\":\"chat.completion.chunk\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"lo\"},\"finish_reason\":\"stop\"}]}\n\n",
+        "data: [DONE]\n\n",
+    );
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(sse, "text/event-stream"))
+        .mount(&backend)
+        .await;
+    let config = recorder_config(&dir);
+    let state = setup_state(&backend, config.clone()).await;
+
+    let mut request = chat_request();
+    request["stream"] = json!(true);
+    assert_eq!(chat(&state, request).await, StatusCode::OK);
+
+    let records = read_records(&state, &config.path);
+    assert_eq!(records.len(), 1);
+    let response = &records[0]["response"];
+    assert_eq!(response["id"], "s1");
+    assert_eq!(response["object"], "chat.completion");
+    assert_eq!(response["choices"][0]["message"]["role"], "assistant");
+    assert_eq!(response["choices"][0]["message"]["content"], "Hello");
+    assert_eq!(response["choices"][0]["finish_reason"], "stop");
+    assert!(records[0]["ttft_ms"].as_f64().is_some());
+}
+
+#[tokio::test]
+async fn test_rotates_by_size() {
+    let dir = TempDir::new().unwrap();
+    let backend = json_backend().await;
+    let mut config = recorder_config(&dir);
+    config.max_bytes = Some(100);
+    config.max_files = 1;
+    let state = setup_state(&backend, config.clone()).await;
+
+    for _ in 0..3 {
+        chat(&state, chat_request()).await;
+    }
+
+    assert_eq!(read_records(&state, &config.path).len(), 1);
+    let rotated = dir.path().join("requests.jsonl.1");
+    assert_eq!(std::fs::read_to_string(rotated).unwrap().lines().count(), 1);
+    assert!(!dir.path().join("requests.jsonl.2").exists());
+}
+
+#[tokio::test]
+async fn test_sample_rate_zero_records_nothing() {
+    let dir = TempDir::new().unwrap();
+    let backend = json_backend().await;
+    let mut config = recorder_config(&dir);
+    config.sample_rate = 0.0;
+    let state = setup_state(&backend, config.clone()).await;
+
+    assert_eq!(chat(&state, chat_request()).await, StatusCode::OK);
+    assert!(read_records(&state, &config.path).is_empty());
+}
+
+#[test]
+fn test_recorder_config() {
+    let config: Config = serde_yml::from_str(
+        r#"
+refresh_interval: 300
+server:
+  recorder:
+    path: requests.jsonl
+    sample_rate: 1.5
+    redact: ["request..content"]
+backends: []
+"#,
+    )
+    .unwrap();
+    let recorder = config.server.recorder.as_ref().unwrap();
+    assert_eq!(recorder.max_files, 5);
+    assert_eq!(recorder.max_bytes, None);
+
+    let issues = config.validate().unwrap_err();
+    let fields: Vec<&str> = issues.iter().map(|issue| issue.field.as_str()).collect();
+    assert_eq!(
+        fields,
+        ["server.recorder.sample_rate", "server.recorder.redact[0]"]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_full_queue_drops_records() {
+    let dir = TempDir::new().unwrap();
+    let config = recorder_config(&dir);
+    // Opening a FIFO without a reader blocks, so the writer never drains the queue.
+    let status = std::process::Command::new("mkfifo")
+        .arg(&config.path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let metrics = Arc::new(Metrics::default());
+    let recorder = Arc::new(Recorder::new(config, metrics.clone()));
+
+    for _ in 0..2000 {
+        let capture = recorder.sample("/v1/chat/completions", &chat_request());
+        capture.unwrap().finish(json!({}));
+    }
+
+    let dropped: u64 = metrics
+        .render()
+        .lines()
+        .find_map(|line| line.strip_prefix("llm_router_recorder_dropped_total "))
+        .unwrap()
+        .parse()
+        .unwrap();
+    // One record is taken by the blocked writer and 1024 fit in the queue.
+    assert!(dropped >= 2000 - 1025);
+}
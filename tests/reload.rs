@@ -0,0 +1,281 @@
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode},
+    routing::post,
+};
+use http_body_util::BodyExt;
+use llm_router::{
+    admin,
+    auth::hash_key,
+    config::{BackendConfig, Config, load_config},
+    model::{AppState, refresh_models},
+    reload::{ReloadSummary, apply_config, reload_config, watch_config},
+};
+use serde_json::json;
+use std::time::Duration;
+use tempfile::TempDir;
+use tower::ServiceExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+async fn backend_with_model(model: &str) -> MockServer {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "object": "list",
+            "data": [{
+                "id": model,
+                "object": "model",
+                "created": 0,
+                "owned_by": "test"
+            }]
+        })))
+        .mount(&mock_server)
+        .await;
+    mock_server
+}
+
+fn config_yaml(backends: &[(&str, &str)]) -> String {
+    let mut yaml = format!(
+        "refresh_interval: 300\nwatch_interval: 1\nclient_auth:\n  admin_key_sha256: \"{}\"\nbackends:\n",
+        hash_key("sk-admin")
+    );
+    for (name, url) in backends {
+        yaml.push_str(&format!("  - name: \"{}\"\n    url: \"{}\"\n", name, url));
+    }
+    yaml
+}
+
+#[tokio::test]
+async fn test_apply_config_rediscovers_changed_backends() {
+    let first = backend_with_model("model-1").await;
+    let second = backend_with_model("model-2").await;
+
+    let state = AppState::new(Config {
+        refresh_interval: 300,
+        backends: vec![BackendConfig {
+            name: "first".to_string(),
+            url: first.uri(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    });
+    refresh_models(&state).await;
+    assert!(state.routing_table.read().await.contains_key("model-1"));
+    let gauge = |name: &str| format!("llm_router_discovered_models{{backend=\"{}\"}} 1", name);
+    assert!(state.metrics.render().contains(&gauge("first")));
+
+    let summary = apply_config(
+        &state,
+        Config {
+            refresh_interval: 300,
+            backends: vec![BackendConfig {
+                name: "second".to_string(),
+                url: second.uri(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+    )
+    .await;
+
+    assert_eq!(
+        summary,
+        ReloadSummary {
+            added: vec!["second".to_string()],
+            changed: vec![],
+            removed: vec!["first".to_string()],
+        }
+    );
+    let routing = state.routing_table.read().await;
+    assert!(!routing.contains_key("model-1"));
+    assert_eq!(routing["model-2"], vec!["second"]);
+    assert_eq!(state.config.load().backends[0].name, "second");
+    let metrics = state.metrics.render();
+    assert!(metrics.contains(&gauge("second")));
+    assert!(!metrics.contains("llm_router_discovered_models{backend=\"first\"}"));
+}
+
+#[tokio::test]
+async fn test_reload_config_from_file() {
+    let first = backend_with_model("model-1").await;
+    let second = backend_with_model("model-2").await;
+
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("config.yml");
+    std::fs::write(&path, config_yaml(&[("first", &first.uri())])).unwrap();
+
+    let (config, config_path) = load_config(&path).unwrap();
+    let state = AppState::new(config).with_config_path(config_path);
+    refresh_models(&state).await;
+
+    std::fs::write(
+        &path,
+        config_yaml(&[("first", &first.uri()), ("second", &second.uri())]),
+    )
+    .unwrap();
+    let summary = reload_config(&state).await.unwrap();
+
+    assert_eq!(summary.added, vec!["second"]);
+    assert!(summary.changed.is_empty());
+    let routing = state.routing_table.read().await;
+    assert!(routing.contains_key("model-1"));
+    assert!(routing.contains_key("model-2"));
+}
+
+#[tokio::test]
+async fn test_reload_config_keeps_current_config_when_invalid() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("config.yml");
+    std::fs::write(&path, config_yaml(&[("first", "http://localhost:1")])).unwrap();
+
+    let (config, config_path) = load_config(&path).unwrap();
+    let state = AppState::new(config).with_config_path(config_path);
+
+    std::fs::write(
+        &path,
+        config_yaml(&[("dup", "http://localhost:1"), ("dup", "http://localhost:2")]),
+    )
+    .unwrap();
+    let err = reload_config(&state).await.unwrap_err();
+    assert!(err.contains("duplicate backend name 'dup'"));
+
+    std::fs::write(&path, "backends: [").unwrap();
+    assert!(reload_config(&state).await.is_err());
+
+    assert_eq!(state.config.load().backends[0].name, "first");
+}
+
+#[tokio::test]
+async fn test_reload_config_from_fallback_path() {
+    let backend = backend_with_model("model-1").await;
+
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("config.yaml");
+    std::fs::write(&path, config_yaml(&[])).unwrap();
+
+    // `--config config` falls back to `config.yaml`; reloads must read it too.
+    let (config, config_path) = load_config(dir.path().join("config")).unwrap();
+    assert_eq!(config_path, path);
+    let state = AppState::new(config).with_config_path(config_path);
+
+    std::fs::write(&path, config_yaml(&[("fallback", &backend.uri())])).unwrap();
+    let summary = reload_config(&state).await.unwrap();
+    assert_eq!(summary.added, vec!["fallback"]);
+    assert!(state.routing_table.read().await.contains_key("model-1"));
+}
+
+#[tokio::test]
+async fn test_reload_config_without_path() {
+    let state = AppState::new(Config::default());
+    assert!(reload_config(&state).await.is_err());
+}
+
+#[tokio::test]
+async fn test_watch_config_reloads_on_change() {
+    let backend = backend_with_model("model-1").await;
+
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("config.yml");
+    std::fs::write(&path, config_yaml(&[])).unwrap();
+
+    let (config, config_path) = load_config(&path).unwrap();
+    let state = AppState::new(config).with_config_path(config_path);
+    let handle = tokio::spawn(watch_config(state.clone()));
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    std::fs::write(&path, config_yaml(&[("watched", &backend.uri())])).unwrap();
+    tokio::time::sleep(Duration::from_millis(2500)).await;
+
+    assert_eq!(state.config.load().backends.len(), 1);
+    assert!(state.routing_table.read().await.contains_key("model-1"));
+
+    handle.abort();
+}
+
+#[tokio::test]
+async fn test_admin_reload_endpoint() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("config.yml");
+    std::fs::write(&path, config_yaml(&[])).unwrap();
+
+    let (config, config_path) = load_config(&path).unwrap();
+    let state = AppState::new(config).with_config_path(config_path);
+    let app = Router::new()
+        .route("/admin/reload", post(admin::reload))
+        .with_state(state);
+
+    std::fs::write(&path, config_yaml(&[("new", "http://localhost:1")])).unwrap();
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/admin/reload")
+                .header("Authorization", "Bearer sk-admin")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(body["added"], json!(["new"]));
+
+    std::fs::write(&path, "refresh_interval: 0\nbackends: []\n").unwrap();
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/admin/reload")
+                .header("Authorization", "Bearer sk-admin")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(body["error"]["code"], "invalid_config");
+    assert_eq!(body["error"]["type"], "server_error");
+    assert!(
+        body["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("refresh_interval: must be greater than 0")
+    );
+}
+
+#[tokio::test]
+async fn test_admin_reload_requires_admin_key() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("config.yml");
+    std::fs::write(&path, config_yaml(&[])).unwrap();
+
+    let (config, config_path) = load_config(&path).unwrap();
+    let state = AppState::new(config).with_config_path(config_path);
+    let app = Router::new()
+        .route("/admin/reload", post(admin::reload))
+        .with_state(state.clone());
+
+    std::fs::write(&path, config_yaml(&[("new", "http://localhost:1")])).unwrap();
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/admin/reload")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert!(state.config.load().backends.is_empty());
+}
@@ -2,14 +2,17 @@ use axum::extract::State;
 use axum::{
     Router,
     body::Body,
-    http::{Request, StatusCode},
+    http::{HeaderMap, Request, StatusCode},
     routing::{get, post},
 };
 use base64::Engine;
 use http_body_util::BodyExt;
 use llm_router::{
     ModelInfo,
-    config::{AuthConfig, BackendConfig, Config},
+    config::{
+        AuthConfig, BackendConfig, Config, LoadBalancingConfig, RetryConfig, Strategy,
+        TimeoutConfig,
+    },
     model::AppState,
     router::{forward_completion, forward_request, healthz, list_models},
 };
@@ -27,13 +30,15 @@ async fn setup_test_app(mock_server_url: String) -> Router {
             auth: Some(AuthConfig::Bearer {
                 token: "test-token".to_string(),
             }),
+            ..Default::default()
         }],
+        ..Default::default()
     };
 
     let state = AppState::new(config);
     {
         let mut routing_table = state.routing_table.write().await;
-        routing_table.insert("test-model".to_string(), mock_server_url);
+        routing_table.insert("test-model".to_string(), vec!["test".to_string()]);
     }
 
     Router::new()
@@ -97,7 +102,9 @@ async fn test_list_models() {
             name: "test".to_string(),
             url: mock_server.uri(),
             auth: None,
+            ..Default::default()
         }],
+        ..Default::default()
     };
 
     let state = AppState::new(config);
@@ -122,7 +129,9 @@ async fn test_list_models() {
         *cache = test_models;
     }
 
-    let response = list_models(State(state.clone())).await;
+    let response = list_models(State(state.clone()), HeaderMap::new())
+        .await
+        .unwrap();
 
     let models = response.0.get("data").expect("Should have 'data' field");
     assert_eq!(models.len(), 2);
@@ -197,13 +206,15 @@ async fn test_forward_with_basic_auth() {
                 username: "testuser".to_string(),
                 password: "testpass".to_string(),
             }),
+            ..Default::default()
         }],
+        ..Default::default()
     };
 
     let state = AppState::new(config);
     {
         let mut routing_table = state.routing_table.write().await;
-        routing_table.insert("test-model".to_string(), mock_server.uri());
+        routing_table.insert("test-model".to_string(), vec!["test".to_string()]);
     }
 
     let app = Router::new()
@@ -254,13 +265,15 @@ async fn test_forward_with_custom_header() {
                 name: "X-Custom-Auth".to_string(),
                 value: "custom-token".to_string(),
             }),
+            ..Default::default()
         }],
+        ..Default::default()
     };
 
     let state = AppState::new(config);
     {
         let mut routing_table = state.routing_table.write().await;
-        routing_table.insert("test-model".to_string(), mock_server.uri());
+        routing_table.insert("test-model".to_string(), vec!["test".to_string()]);
     }
 
     let app = Router::new()
@@ -297,7 +310,9 @@ async fn test_forward_unknown_model() {
             name: "test".to_string(),
             url: mock_server.uri(),
             auth: None,
+            ..Default::default()
         }],
+        ..Default::default()
     };
 
     let state = AppState::new(config);
@@ -323,10 +338,61 @@ async fn test_forward_unknown_model() {
         .await
         .unwrap();
 
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(body["error"]["code"], "model_not_found");
+    assert_eq!(body["error"]["type"], "invalid_request_error");
+    assert_eq!(body["error"]["param"], "model");
+}
+
+#[tokio::test]
+async fn test_forward_invalid_json() {
+    let app = setup_test_app("http://localhost:1".to_string()).await;
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/chat/completions")
+                .header("Content-Type", "application/json")
+                .body(Body::from("{not json"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response.headers()["content-type"], "application/json");
+
+    let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(body["error"]["code"], "invalid_json");
+}
+
+#[tokio::test]
+async fn test_forward_missing_model() {
+    let app = setup_test_app("http://localhost:1".to_string()).await;
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/chat/completions")
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({ "messages": [] }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 
     let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
-    assert_eq!(&body_bytes[..], b"Unknown model");
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(body["error"]["code"], "missing_model");
+    assert_eq!(body["error"]["param"], "model");
 }
 
 #[tokio::test]
@@ -346,13 +412,15 @@ async fn test_forward_server_error() {
             name: "test".to_string(),
             url: mock_server.uri(),
             auth: None,
+            ..Default::default()
         }],
+        ..Default::default()
     };
 
     let state = AppState::new(config);
     {
         let mut routing_table = state.routing_table.write().await;
-        routing_table.insert("test-model".to_string(), mock_server.uri());
+        routing_table.insert("test-model".to_string(), vec!["test".to_string()]);
     }
 
     let app = Router::new()
@@ -388,13 +456,15 @@ async fn test_forward_network_error() {
             name: "test".to_string(),
             url: "http://localhost:1".to_string(),
             auth: None,
+            ..Default::default()
         }],
+        ..Default::default()
     };
 
     let state = AppState::new(config);
     {
         let mut routing_table = state.routing_table.write().await;
-        routing_table.insert("test-model".to_string(), "http://localhost:1".to_string());
+        routing_table.insert("test-model".to_string(), vec!["test".to_string()]);
     }
 
     let app = Router::new()
@@ -419,11 +489,11 @@ async fn test_forward_network_error() {
         .await
         .unwrap();
 
-    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
 
-    let body = response.into_body().collect().await.unwrap().to_bytes();
-    let body_str = String::from_utf8(body.to_vec()).unwrap();
-    assert_eq!(body_str, "Internal forwarding error");
+    let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(body["error"]["code"], "upstream_error");
 }
 
 #[tokio::test]
@@ -447,3 +517,489 @@ async fn test_healthz() {
     let body_str = String::from_utf8(body.to_vec()).unwrap();
     assert_eq!(body_str, "OK");
 }
+
+async fn spawn_sse_backend(
+    dropped: tokio::sync::oneshot::Sender<()>,
+) -> (String, tokio::sync::oneshot::Sender<()>) {
+    struct DropSignal(Option<tokio::sync::oneshot::Sender<()>>);
+    impl Drop for DropSignal {
+        fn drop(&mut self) {
+            if let Some(tx) = self.0.take() {
+                let _ = tx.send(());
+            }
+        }
+    }
+
+    let (release_tx, release_rx) = tokio::sync::oneshot::channel::<()>();
+    let state = std::sync::Arc::new(std::sync::Mutex::new(Some((dropped, release_rx))));
+
+    let upstream = Router::new().route(
+        "/v1/chat/completions",
+        post(move || {
+            let state = state.clone();
+            async move {
+                let (dropped, release_rx) = state.lock().unwrap().take().unwrap();
+                let signal = DropSignal(Some(dropped));
+                let events = futures_util::stream::unfold(
+                    (0, Some(release_rx), signal),
+                    |(step, release_rx, signal)| async move {
+                        match step {
+                            0 => Some((
+                                Ok::<_, std::convert::Infallible>("data: {\"n\":1}\n\n"),
+                                (1, release_rx, signal),
+                            )),
+                            1 => {
+                                let _ = release_rx.unwrap().await;
+                                Some((Ok("data: [DONE]\n\n"), (2, None, signal)))
+                            }
+                            _ => None,
+                        }
+                    },
+                );
+                axum::response::Response::builder()
+                    .header("Content-Type", "text/event-stream")
+                    .body(Body::from_stream(events))
+                    .unwrap()
+            }
+        }),
+    );
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, upstream).await.unwrap() });
+    (url, release_tx)
+}
+
+fn streaming_request() -> Request<Body> {
+    Request::builder()
+        .method("POST")
+        .uri("/v1/chat/completions")
+        .header("Content-Type", "application/json")
+        .body(Body::from(
+            json!({
+                "model": "test-model",
+                "stream": true,
+                "messages": [{"role": "user", "content": "Hello"}]
+            })
+            .to_string(),
+        ))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_forward_streams_sse_events() {
+    let (dropped_tx, _dropped_rx) = tokio::sync::oneshot::channel();
+    let (url, release) = spawn_sse_backend(dropped_tx).await;
+    let app = setup_test_app(url).await;
+
+    let response = app.oneshot(streaming_request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+
+    let mut body = response.into_body();
+
+    // The first event must arrive while the backend is still generating.
+    let first = tokio::time::timeout(std::time::Duration::from_secs(2), body.frame())
+        .await
+        .expect("first event was not flushed")
+        .unwrap()
+        .unwrap();
+    assert_eq!(&first.into_data().unwrap()[..], b"data: {\"n\":1}\n\n");
+
+    release.send(()).unwrap();
+    let rest = body.collect().await.unwrap().to_bytes();
+    assert_eq!(&rest[..], b"data: [DONE]\n\n");
+}
+
+#[tokio::test]
+async fn test_forward_stream_client_disconnect_cancels_upstream() {
+    let (dropped_tx, dropped_rx) = tokio::sync::oneshot::channel();
+    let (url, _release) = spawn_sse_backend(dropped_tx).await;
+    let app = setup_test_app(url).await;
+
+    let response = app.oneshot(streaming_request()).await.unwrap();
+    let mut body = response.into_body();
+    body.frame().await.unwrap().unwrap();
+    drop(body);
+
+    tokio::time::timeout(std::time::Duration::from_secs(2), dropped_rx)
+        .await
+        .expect("upstream stream was not cancelled")
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_forward_round_robin_across_backends() {
+    let first = MockServer::start().await;
+    let second = MockServer::start().await;
+    for (server, reply) in [(&first, "first"), (&second, "second")] {
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "backend": reply })))
+            .expect(2)
+            .mount(server)
+            .await;
+    }
+
+    let config = Config {
+        refresh_interval: 300,
+        backends: vec![
+            BackendConfig {
+                name: "first".to_string(),
+                url: first.uri(),
+                ..Default::default()
+            },
+            BackendConfig {
+                name: "second".to_string(),
+                url: second.uri(),
+                ..Default::default()
+            },
+        ],
+        load_balancing: LoadBalancingConfig {
+            strategy: Strategy::RoundRobin,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let state = AppState::new(config);
+    state.routing_table.write().await.insert(
+        "test-model".to_string(),
+        vec!["first".to_string(), "second".to_string()],
+    );
+
+    let app = Router::new()
+        .route("/v1/chat/completions", post(forward_request))
+        .with_state(state);
+
+    let mut replies = Vec::new();
+    for _ in 0..4 {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/v1/chat/completions")
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(json!({ "model": "test-model" }).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let body: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+        replies.push(body["backend"].as_str().unwrap().to_string());
+    }
+
+    assert_eq!(replies, ["first", "second", "first", "second"]);
+    first.verify().await;
+    second.verify().await;
+}
+
+async fn setup_failover_app(urls: &[String], retry: RetryConfig) -> Router {
+    let backends = urls
+        .iter()
+        .enumerate()
+        .map(|(i, url)| BackendConfig {
+            name: format!("backend-{}", i),
+            url: url.clone(),
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+    let names = backends.iter().map(|b| b.name.clone()).collect();
+
+    let config = Config {
+        refresh_interval: 300,
+        backends,
+        retry,
+        ..Default::default()
+    };
+
+    let state = AppState::new(config);
+    state
+        .routing_table
+        .write()
+        .await
+        .insert("test-model".to_string(), names);
+
+    Router::new()
+        .route("/v1/chat/completions", post(forward_request))
+        .with_state(state)
+}
+
+fn chat_request() -> Request<Body> {
+    Request::builder()
+        .method("POST")
+        .uri("/v1/chat/completions")
+        .header("Content-Type", "application/json")
+        .body(Body::from(
+            json!({
+                "model": "test-model",
+                "messages": [{"role": "user", "content": "Hello"}]
+            })
+            .to_string(),
+        ))
+        .unwrap()
+}
+
+fn quick_retry(max_attempts: u32) -> RetryConfig {
+    RetryConfig {
+        max_attempts,
+        backoff_ms: 1,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_forward_retries_on_retryable_status() {
+    let failing = MockServer::start().await;
+    let healthy = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(503))
+        .expect(1)
+        .mount(&failing)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(wiremock::matchers::body_json(json!({
+            "model": "test-model",
+            "messages": [{"role": "user", "content": "Hello"}]
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "ok": true })))
+        .expect(1)
+        .mount(&healthy)
+        .await;
+
+    let app = setup_failover_app(&[failing.uri(), healthy.uri()], quick_retry(3)).await;
+    let response = app.oneshot(chat_request()).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-router-attempts"], "2");
+    assert_eq!(
+        response.headers()["x-router-attempt-history"],
+        "backend-0=503, backend-1=200"
+    );
+    failing.verify().await;
+    healthy.verify().await;
+}
+
+#[tokio::test]
+async fn test_forward_fails_over_on_connect_error() {
+    let healthy = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "ok": true })))
+        .expect(1)
+        .mount(&healthy)
+        .await;
+
+    let app = setup_failover_app(
+        &["http://localhost:1".to_string(), healthy.uri()],
+        quick_retry(2),
+    )
+    .await;
+    let response = app.oneshot(chat_request()).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()["x-router-attempt-history"],
+        "backend-0=connect_error, backend-1=200"
+    );
+}
+
+#[tokio::test]
+async fn test_forward_returns_last_response_when_retries_exhausted() {
+    let first = MockServer::start().await;
+    let second = MockServer::start().await;
+    for server in [&first, &second] {
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    let app = setup_failover_app(&[first.uri(), second.uri()], quick_retry(2)).await;
+    let response = app.oneshot(chat_request()).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.headers()["x-router-attempts"], "2");
+}
+
+#[tokio::test]
+async fn test_forward_does_not_retry_by_default() {
+    let first = MockServer::start().await;
+    let second = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(503))
+        .expect(1)
+        .mount(&first)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&second)
+        .await;
+
+    let app = setup_failover_app(&[first.uri(), second.uri()], RetryConfig::default()).await;
+    let response = app.oneshot(chat_request()).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.headers()["x-router-attempts"], "1");
+    first.verify().await;
+    second.verify().await;
+}
+
+async fn setup_timeout_app(url: String, timeouts: TimeoutConfig) -> Router {
+    let config = Config {
+        refresh_interval: 300,
+        backends: vec![BackendConfig {
+            name: "test".to_string(),
+            url,
+            timeouts,
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    let state = AppState::new(config);
+    state
+        .routing_table
+        .write()
+        .await
+        .insert("test-model".to_string(), vec!["test".to_string()]);
+
+    Router::new()
+        .route("/v1/chat/completions", post(forward_request))
+        .with_state(state)
+}
+
+async fn assert_gateway_timeout(response: axum::response::Response) {
+    assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+    let body_bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(body["error"]["code"], "timeout");
+}
+
+#[tokio::test]
+async fn test_forward_first_byte_timeout() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_delay(std::time::Duration::from_secs(3)))
+        .mount(&mock_server)
+        .await;
+
+    let timeouts = TimeoutConfig {
+        first_byte: Some(1),
+        ..Default::default()
+    };
+    let app = setup_timeout_app(mock_server.uri(), timeouts).await;
+    let response = app.oneshot(chat_request()).await.unwrap();
+
+    assert_gateway_timeout(response).await;
+}
+
+#[tokio::test]
+async fn test_forward_request_timeout() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_delay(std::time::Duration::from_secs(3)))
+        .mount(&mock_server)
+        .await;
+
+    let timeouts = TimeoutConfig {
+        request: Some(1),
+        ..Default::default()
+    };
+    let app = setup_timeout_app(mock_server.uri(), timeouts).await;
+    let response = app.oneshot(chat_request()).await.unwrap();
+
+    assert_gateway_timeout(response).await;
+}
+
+#[tokio::test]
+async fn test_forward_stream_idle_timeout() {
+    let (dropped_tx, _dropped_rx) = tokio::sync::oneshot::channel();
+    let (url, _release) = spawn_sse_backend(dropped_tx).await;
+
+    let timeouts = TimeoutConfig {
+        idle: Some(1),
+        ..Default::default()
+    };
+    let app = setup_timeout_app(url, timeouts).await;
+    let response = app.oneshot(streaming_request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        response.into_body().collect(),
+    )
+    .await
+    .expect("idle stream was not terminated")
+    .unwrap()
+    .to_bytes();
+    let body = String::from_utf8(body.to_vec()).unwrap();
+
+    assert!(body.starts_with("data: {\"n\":1}\n\n"));
+    assert!(body.contains("\"code\":\"timeout\""));
+}
+
+#[tokio::test]
+async fn test_forward_retries_after_timeout() {
+    let slow = MockServer::start().await;
+    let healthy = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_delay(std::time::Duration::from_secs(3)))
+        .mount(&slow)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "ok": true })))
+        .mount(&healthy)
+        .await;
+
+    let timeouts = TimeoutConfig {
+        first_byte: Some(1),
+        ..Default::default()
+    };
+    let config = Config {
+        refresh_interval: 300,
+        backends: vec![
+            BackendConfig {
+                name: "slow".to_string(),
+                url: slow.uri(),
+                timeouts: timeouts.clone(),
+                ..Default::default()
+            },
+            BackendConfig {
+                name: "healthy".to_string(),
+                url: healthy.uri(),
+                timeouts,
+                ..Default::default()
+            },
+        ],
+        retry: quick_retry(2),
+        ..Default::default()
+    };
+    let state = AppState::new(config);
+    state.routing_table.write().await.insert(
+        "test-model".to_string(),
+        vec!["slow".to_string(), "healthy".to_string()],
+    );
+    let app = Router::new()
+        .route("/v1/chat/completions", post(forward_request))
+        .with_state(state);
+
+    let response = app.oneshot(chat_request()).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()["x-router-attempt-history"],
+        "slow=timeout, healthy=200"
+    );
+}
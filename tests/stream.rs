@@ -0,0 +1,55 @@
+use axum::http::{HeaderMap, HeaderValue, header};
+use llm_router::stream::{SseFramer, is_event_stream};
+
+#[test]
+fn test_framer_splits_events() {
+    let mut framer = SseFramer::default();
+
+    let events = framer.push(b"data: {\"a\":1}\n\ndata: {\"a\":2}\n\n");
+    assert_eq!(events.len(), 2);
+    assert_eq!(&events[0][..], b"data: {\"a\":1}\n\n");
+    assert_eq!(&events[1][..], b"data: {\"a\":2}\n\n");
+    assert!(framer.finish().is_none());
+}
+
+#[test]
+fn test_framer_buffers_partial_events() {
+    let mut framer = SseFramer::default();
+
+    assert!(framer.push(b"data: {\"a\"").is_empty());
+    assert!(framer.push(b":1}\n").is_empty());
+
+    let events = framer.push(b"\ndata: [DONE]");
+    assert_eq!(events.len(), 1);
+    assert_eq!(&events[0][..], b"data: {\"a\":1}\n\n");
+
+    assert_eq!(&framer.finish().unwrap()[..], b"data: [DONE]");
+}
+
+#[test]
+fn test_framer_handles_crlf() {
+    let mut framer = SseFramer::default();
+
+    let events = framer.push(b"event: ping\r\ndata: 1\r\n\r\ndata: 2\r\n\r\n");
+    assert_eq!(events.len(), 2);
+    assert_eq!(&events[0][..], b"event: ping\r\ndata: 1\r\n\r\n");
+    assert_eq!(&events[1][..], b"data: 2\r\n\r\n");
+}
+
+#[test]
+fn test_is_event_stream() {
+    let mut headers = HeaderMap::new();
+    assert!(!is_event_stream(&headers));
+
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/event-stream; charset=utf-8"),
+    );
+    assert!(is_event_stream(&headers));
+
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    assert!(!is_event_stream(&headers));
+}
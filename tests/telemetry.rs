@@ -0,0 +1,202 @@
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode},
+    routing::post,
+};
+use http_body_util::BodyExt;
+use llm_router::{
+    config::{BackendConfig, Config, OtlpProtocol},
+    model::AppState,
+    router::forward_request,
+    telemetry,
+};
+use opentelemetry::trace::{SpanKind, Status};
+use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider, SpanData};
+use serde_json::json;
+use tower::ServiceExt;
+use tracing_subscriber::prelude::*;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const CLIENT_TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+const CLIENT_SPAN_ID: &str = "00f067aa0ba902b7";
+
+async fn backend_with_usage() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "ok",
+            "usage": {"prompt_tokens": 12, "completion_tokens": 30, "total_tokens": 42}
+        })))
+        .mount(&server)
+        .await;
+    server
+}
+
+async fn send_chat(backend: &MockServer, model: &str) -> StatusCode {
+    let state = AppState::new(Config {
+        refresh_interval: 300,
+        backends: vec![BackendConfig {
+            name: "primary".to_string(),
+            url: backend.uri(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    });
+    state
+        .routing_table
+        .write()
+        .await
+        .insert("test-model".to_string(), vec!["primary".to_string()]);
+    let app = Router::new()
+        .route("/v1/chat/completions", post(forward_request))
+        .with_state(state);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/chat/completions")
+                .header("Content-Type", "application/json")
+                .header(
+                    "traceparent",
+                    format!("00-{}-{}-01", CLIENT_TRACE_ID, CLIENT_SPAN_ID),
+                )
+                .body(Body::from(
+                    json!({"model": model, "max_tokens": 64, "temperature": 0.5}).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    response.into_body().collect().await.unwrap();
+    status
+}
+
+fn attribute(span: &SpanData, key: &str) -> Option<String> {
+    span.attributes
+        .iter()
+        .find(|kv| kv.key.as_str() == key)
+        .map(|kv| kv.value.to_string())
+}
+
+#[tokio::test]
+async fn test_request_span_continues_client_trace() {
+    let exporter = InMemorySpanExporter::default();
+    let provider = SdkTracerProvider::builder()
+        .with_simple_exporter(exporter.clone())
+        .build();
+    let _guard = tracing_subscriber::registry()
+        .with(telemetry::layer(&provider))
+        .set_default();
+
+    let backend = backend_with_usage().await;
+    assert_eq!(send_chat(&backend, "test-model").await, StatusCode::OK);
+
+    let spans = exporter.get_finished_spans().unwrap();
+    let span = spans
+        .iter()
+        .find(|span| span.name == "chat test-model")
+        .expect("request span");
+    assert_eq!(span.span_kind, SpanKind::Server);
+    assert_eq!(span.span_context.trace_id().to_string(), CLIENT_TRACE_ID);
+    assert_eq!(span.parent_span_id.to_string(), CLIENT_SPAN_ID);
+    assert_eq!(
+        attribute(span, "gen_ai.operation.name").as_deref(),
+        Some("chat")
+    );
+    assert_eq!(
+        attribute(span, "gen_ai.request.model").as_deref(),
+        Some("test-model")
+    );
+    assert_eq!(
+        attribute(span, "gen_ai.request.max_tokens").as_deref(),
+        Some("64")
+    );
+    assert_eq!(
+        attribute(span, "gen_ai.usage.input_tokens").as_deref(),
+        Some("12")
+    );
+    assert_eq!(
+        attribute(span, "gen_ai.usage.output_tokens").as_deref(),
+        Some("30")
+    );
+    assert_eq!(
+        attribute(span, "llm_router.backend").as_deref(),
+        Some("primary")
+    );
+    assert_eq!(
+        attribute(span, "http.response.status_code").as_deref(),
+        Some("200")
+    );
+
+    // The backend sees the router's span as the parent, in the same trace.
+    let received = backend.received_requests().await.unwrap();
+    let traceparent = received[0].headers["traceparent"].to_str().unwrap();
+    assert_eq!(
+        traceparent,
+        format!("00-{}-{}-01", CLIENT_TRACE_ID, span.span_context.span_id())
+    );
+}
+
+#[tokio::test]
+async fn test_error_response_marks_span_failed() {
+    let exporter = InMemorySpanExporter::default();
+    let provider = SdkTracerProvider::builder()
+        .with_simple_exporter(exporter.clone())
+        .build();
+    let _guard = tracing_subscriber::registry()
+        .with(telemetry::layer(&provider))
+        .set_default();
+
+    let backend = backend_with_usage().await;
+    assert_eq!(
+        send_chat(&backend, "unknown-model").await,
+        StatusCode::NOT_FOUND
+    );
+
+    let spans = exporter.get_finished_spans().unwrap();
+    let span = spans
+        .iter()
+        .find(|span| span.name == "chat unknown-model")
+        .expect("request span");
+    assert_eq!(
+        attribute(span, "error.type").as_deref(),
+        Some("model_not_found")
+    );
+    assert!(matches!(span.status, Status::Error { .. }));
+}
+
+#[tokio::test]
+async fn test_traceparent_passes_through_without_tracing() {
+    let backend = backend_with_usage().await;
+    assert_eq!(send_chat(&backend, "test-model").await, StatusCode::OK);
+
+    let received = backend.received_requests().await.unwrap();
+    assert_eq!(
+        received[0].headers["traceparent"],
+        format!("00-{}-{}-01", CLIENT_TRACE_ID, CLIENT_SPAN_ID).as_str()
+    );
+}
+
+#[test]
+fn test_tracing_config() {
+    let config: Config = serde_yml::from_str(
+        r#"
+refresh_interval: 300
+server:
+  tracing:
+    endpoint: "otel-collector:4317"
+backends: []
+"#,
+    )
+    .unwrap();
+    assert_eq!(config.server.tracing.protocol, OtlpProtocol::Grpc);
+    assert_eq!(config.server.tracing.service_name, "llm-router");
+
+    let issues = config.validate().unwrap_err();
+    assert_eq!(issues[0].field, "server.tracing.endpoint");
+}